@@ -1,16 +1,64 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use gray_matter::Matter;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use walkdir::WalkDir;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod recur;
+
+use recur::parse_recurrence;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Config {
     git: GitConfig,
+    #[serde(default)]
+    tasks: TasksConfig,
+    #[serde(default)]
+    slug: SlugConfig,
+    #[serde(default)]
+    planning: PlanningConfig,
+    #[serde(default)]
+    import: ImportConfig,
+    #[serde(default)]
+    notify: NotifyConfig,
+    #[serde(default)]
+    workflow: WorkflowConfig,
+    #[serde(default)]
+    body: BodyConfig,
+    #[serde(default)]
+    webhook: WebhookConfig,
+    #[serde(default)]
+    caldav: CaldavConfig,
+    #[serde(default)]
+    google_tasks: GoogleTasksConfig,
+    #[serde(default)]
+    hooks: HooksConfig,
+    #[serde(default)]
+    serve: ServeConfig,
+    #[serde(default)]
+    encryption: EncryptionConfig,
+    #[serde(default)]
+    urgency: UrgencyConfig,
+    /// Named task directories selectable with --context or MDTASKS_CONTEXT
+    #[serde(default)]
+    contexts: std::collections::BTreeMap<String, String>,
+    /// Command aliases, e.g. `hot = "list --priority high --sort due"`
+    #[serde(default)]
+    alias: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    virtual_sources: Option<Vec<VirtualSource>>,
+    /// Other repositories whose tasks are listed read-only
+    #[serde(default)]
+    overlays: Vec<Overlay>,
+    /// Further task directories of a monorepo by name, e.g. `api = "services/api/tasks"`;
+    /// `list` shows them all and --root selects one
+    #[serde(default)]
+    roots: std::collections::BTreeMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct GitConfig {
     branch_prefix: String,
     pr_enabled: bool,
@@ -19,6 +67,491 @@ struct GitConfig {
     pr_switch_to_main: bool,
     pr_default_reviewers: Option<Vec<String>>,
     pr_default_labels: Option<Vec<String>>,
+    /// Remote used by `mdtasks sync` (defaults to "origin")
+    #[serde(default)]
+    sync_remote: Option<String>,
+    /// Branch `mdtasks sync` must run on; it refuses to sync from any other branch
+    #[serde(default)]
+    sync_branch: Option<String>,
+    /// How `git-done --merge` merges into the base branch: "no-ff" (default),
+    /// "ff-only", "squash", or "rebase"
+    #[serde(default)]
+    merge_strategy: Option<String>,
+    /// Branch task branches start from and merge into (defaults to origin's HEAD)
+    #[serde(default)]
+    base_branch: Option<String>,
+    /// Task branch name, e.g. "{prefix}{id}-{slug}" (the default)
+    #[serde(default)]
+    branch_template: Option<String>,
+    /// Commit message and pull request title, e.g. "{type}: {title} (task #{id})"
+    /// (the default)
+    #[serde(default)]
+    commit_template: Option<String>,
+    /// Conventional-commit `{type}` per tag, on top of the built-in mapping
+    #[serde(default)]
+    commit_types: std::collections::BTreeMap<String, String>,
+    /// Never push from `git-done` (work offline)
+    #[serde(default)]
+    no_push: bool,
+    /// Never pull or fetch in `git-start` and `git-done` (work offline)
+    #[serde(default)]
+    no_pull: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TasksConfig {
+    /// Directory containing the task files
+    dir: String,
+    /// How symlinks inside the tasks directory are treated: "skip" or "follow"
+    symlinks: String,
+    /// Cache parsed task files in `.mdtasks/index.json`
+    index: bool,
+    /// Prefix for new task IDs, e.g. "API" for API-014 (empty for plain numbers)
+    id_prefix: String,
+    /// Per-project ID prefixes, overriding `id_prefix`
+    project_prefixes: std::collections::BTreeMap<String, String>,
+    /// How new IDs are generated: "sequential", "ulid", or "uuid"
+    id_scheme: String,
+    /// How `created`/`started`/`completed` are recorded: "date" or "rfc3339"
+    timestamps: String,
+    /// IANA timezone for dates, e.g. "Europe/Amsterdam" (defaults to the system's)
+    timezone: Option<String>,
+    /// Log each change to a task's fields as a line in its `## History` section
+    history: bool,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            dir: "tasks".to_string(),
+            symlinks: "skip".to_string(),
+            index: true,
+            id_prefix: String::new(),
+            project_prefixes: std::collections::BTreeMap::new(),
+            id_scheme: "sequential".to_string(),
+            timestamps: "date".to_string(),
+            timezone: None,
+            history: false,
+        }
+    }
+}
+
+/// Rules for turning task titles into file and branch name slugs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct SlugConfig {
+    /// Maximum slug length in characters (0 for no limit)
+    max_length: usize,
+    /// Word separator style: "kebab" or "snake"
+    case: String,
+    /// Replace accented and non-Latin letters with ASCII equivalents
+    transliterate: bool,
+    /// Words dropped from slugs (compared case-insensitively)
+    stop_words: Vec<String>,
+}
+
+impl Default for SlugConfig {
+    fn default() -> Self {
+        Self {
+            max_length: 0,
+            case: "kebab".to_string(),
+            transliterate: true,
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+/// Settings for `plan-week`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct PlanningConfig {
+    /// Hours of task work that fit into one day
+    day_capacity_hours: f64,
+    /// Hours assumed for tasks without an `estimate`
+    default_estimate_hours: f64,
+}
+
+impl Default for PlanningConfig {
+    fn default() -> Self {
+        Self {
+            day_capacity_hours: 6.0,
+            default_estimate_hours: 1.0,
+        }
+    }
+}
+
+/// Weights of the urgency score shown by `list --columns urgency` and used by `next`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct UrgencyConfig {
+    /// Full weight from a week overdue, scaling down to a fifth for tasks due in two weeks or later
+    due: f64,
+    high: f64,
+    medium: f64,
+    low: f64,
+    /// Full weight for tasks created a year ago or earlier
+    age: f64,
+    active: f64,
+}
+
+impl Default for UrgencyConfig {
+    fn default() -> Self {
+        Self {
+            due: 12.0,
+            high: 6.0,
+            medium: 3.9,
+            low: 1.8,
+            age: 2.0,
+            active: 4.0,
+        }
+    }
+}
+
+/// Chat webhook announcing task status changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct WebhookConfig {
+    /// Incoming-webhook URL receiving `{"text": ...}` JSON (Slack, Mattermost); unset disables it
+    url: Option<String>,
+    /// Events to announce: done, start, cancel
+    events: Vec<String>,
+    /// Only announce tasks in these projects (empty for all)
+    projects: Vec<String>,
+    /// Only announce tasks with one of these tags (empty for all)
+    tags: Vec<String>,
+    /// Queue messages for `webhook flush` instead of posting them immediately
+    queue: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            events: vec![
+                "done".to_string(),
+                "start".to_string(),
+                "cancel".to_string(),
+            ],
+            projects: Vec::new(),
+            tags: Vec::new(),
+            queue: false,
+        }
+    }
+}
+
+/// CalDAV task list mirrored by `sync caldav`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct CaldavConfig {
+    /// Calendar collection holding the VTODOs, e.g. a Nextcloud Tasks list
+    url: Option<String>,
+    username: Option<String>,
+    /// Command printing the password, e.g. "pass show nextcloud"; the
+    /// MDTASKS_CALDAV_PASSWORD environment variable takes precedence
+    password_command: Option<String>,
+    /// Plain-text password, used when neither of the above is set
+    password: Option<String>,
+}
+
+/// Google Tasks account mirrored by `sync google-tasks`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct GoogleTasksConfig {
+    /// OAuth client of type "TVs and Limited Input devices"
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    /// List holding tasks without a project (the account's default list when unset)
+    default_list: Option<String>,
+}
+
+/// Settings of the `serve` REST API
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ServeConfig {
+    /// Web origin allowed to call the API from a browser, e.g. "http://localhost:3000";
+    /// unset sends no CORS headers
+    allowed_origin: Option<String>,
+}
+
+/// External commands run on task lifecycle events, receiving the task as JSON on stdin
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct HooksConfig {
+    /// Run after `add` creates a task
+    on_add: Vec<String>,
+    /// Run when a task becomes active
+    on_start: Vec<String>,
+    /// Run when a task is marked done
+    on_done: Vec<String>,
+    /// Run after `set-*` or `modify` changes a field
+    on_modify: Vec<String>,
+}
+
+/// Encryption of the bodies of tasks marked `encrypted: true`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct EncryptionConfig {
+    /// Encryption program: "age" or "gpg"
+    tool: String,
+    /// age recipients (public keys) or gpg key IDs/emails the body is encrypted to
+    recipients: Vec<String>,
+    /// age identity file used to decrypt; gpg uses its agent
+    identity: Option<String>,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            tool: "age".to_string(),
+            recipients: Vec::new(),
+            identity: None,
+        }
+    }
+}
+
+/// Markdown body skeleton written by `add`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct BodyConfig {
+    /// Top-level heading ("" for none)
+    heading: String,
+    /// `##` sections in order; `--notes` goes into a "Notes" section, added first if missing
+    sections: Vec<BodySection>,
+    /// Write checklist dates in Obsidian Tasks syntax: `📅 <due>` and `✅ <done>`
+    obsidian_tasks: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BodySection {
+    name: String,
+    /// Initial content below the section heading
+    #[serde(default)]
+    content: String,
+}
+
+impl Default for BodyConfig {
+    fn default() -> Self {
+        Self {
+            heading: "Task Details".to_string(),
+            sections: vec![BodySection {
+                name: "Subtasks".to_string(),
+                content: String::new(),
+            }],
+            obsidian_tasks: false,
+        }
+    }
+}
+
+/// Settings for task statuses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct WorkflowConfig {
+    /// Valid statuses, in `list --sort status` order; built-in statuses left
+    /// out here are still accepted and sort after the listed ones
+    statuses: Vec<String>,
+    /// Mark a task done when `check` ticks off its last open subtask
+    auto_done: bool,
+    /// Most tasks that may be `active` at once; `start` and `git-start` warn
+    /// when another would go over it
+    wip_limit: Option<usize>,
+    /// Refuse instead of warning when the WIP limit is reached (`--force` overrides)
+    wip_strict: bool,
+}
+
+impl Default for WorkflowConfig {
+    fn default() -> Self {
+        Self {
+            statuses: [
+                "active",
+                "partial",
+                "pending",
+                "waiting",
+                "done",
+                "archived",
+                "cancelled",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            auto_done: false,
+            wip_limit: None,
+            wip_strict: false,
+        }
+    }
+}
+
+impl WorkflowConfig {
+    /// Configured statuses followed by any built-in ones they leave out
+    fn status_order(&self) -> Vec<&str> {
+        let mut order: Vec<&str> = self.statuses.iter().map(|s| s.as_str()).collect();
+        for status in BUILTIN_STATUSES {
+            if !order.contains(&status) {
+                order.push(status);
+            }
+        }
+        order
+    }
+
+    fn is_valid_status(&self, status: &str) -> bool {
+        self.status_order().contains(&status)
+    }
+}
+
+/// Task priority, from most to least important
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    fn parse(value: &str) -> Option<Self> {
+        <Self as clap::ValueEnum>::from_str(value, false).ok()
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// Statuses written by the built-in commands
+const BUILTIN_STATUSES: [&str; 7] = [
+    "pending",
+    "active",
+    "done",
+    "partial",
+    "waiting",
+    "archived",
+    "cancelled",
+];
+
+/// Reject status and priority values that aren't part of the workflow
+fn validate_field_value(field: &str, value: &str, config: &Config) -> Result<()> {
+    match field {
+        "status" if !config.workflow.is_valid_status(value) => Err(anyhow::anyhow!(
+            "Invalid status '{}' (expected one of: {})",
+            value,
+            config.workflow.status_order().join(", ")
+        )),
+        "priority" if Priority::parse(value).is_none() => Err(anyhow::anyhow!(
+            "Invalid priority '{}' (expected one of: high, medium, low)",
+            value
+        )),
+        "recur" => parse_recurrence(value).map(|_| ()),
+        "estimate" if parse_estimate_hours(value).is_none() => Err(anyhow::anyhow!(
+            "Invalid estimate '{}' (expected e.g. 30m, 2h, 1.5, or 3d)",
+            value
+        )),
+        "wait_until" | "follow_up" if parse_task_date(value).is_none() => Err(anyhow::anyhow!(
+            "Invalid {} date '{}' (expected YYYY-MM-DD)",
+            field,
+            value
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Settings for `notify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct NotifyConfig {
+    /// Reminder steps per priority: "Nd" fires N days before the due date,
+    /// "daily" repeats every day once the task is overdue
+    escalation: std::collections::BTreeMap<String, Vec<String>>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        let chain = |steps: &[&str]| steps.iter().map(|s| s.to_string()).collect();
+        Self {
+            escalation: [
+                (
+                    "high".to_string(),
+                    chain(&["7d", "3d", "1d", "0d", "daily"]),
+                ),
+                ("medium".to_string(), chain(&["3d", "0d", "daily"])),
+                ("low".to_string(), chain(&["0d"])),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+/// Settings for `import` sources
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ImportConfig {
+    notion: NotionImportConfig,
+}
+
+/// How Notion database properties map onto task fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct NotionImportConfig {
+    title_property: String,
+    status_property: String,
+    tags_property: String,
+    priority_property: String,
+    due_property: String,
+    project_property: String,
+    /// Notion status/select values mapped to mdtasks statuses
+    status_map: std::collections::BTreeMap<String, String>,
+    /// Other Notion properties copied into front-matter fields (property = field)
+    properties: std::collections::BTreeMap<String, String>,
+}
+
+impl Default for NotionImportConfig {
+    fn default() -> Self {
+        Self {
+            title_property: "Name".to_string(),
+            status_property: "Status".to_string(),
+            tags_property: "Tags".to_string(),
+            priority_property: "Priority".to_string(),
+            due_property: "Due".to_string(),
+            project_property: "Project".to_string(),
+            status_map: [
+                ("Not started", "pending"),
+                ("In progress", "active"),
+                ("Done", "done"),
+                ("Archived", "archived"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            properties: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+/// Another repository whose tasks are listed read-only alongside local ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Overlay {
+    /// Path to the repository (its mdtasks.toml decides the tasks directory)
+    repo: String,
+    /// Prefix for its task IDs: "PLAT" lists task 014 as PLAT-014
+    prefix: String,
+}
+
+/// Read-only external source whose items are shown alongside local tasks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VirtualSource {
+    /// Source name shown in listings
+    name: String,
+    /// Source kind: "github" (issue search via gh) or "ics" (calendar feed)
+    kind: String,
+    /// GitHub issue search query
+    query: Option<String>,
+    /// ICS feed URL or local file path
+    url: Option<String>,
+    /// Prefix for virtual task IDs (defaults to the source name)
+    prefix: Option<String>,
 }
 
 impl Default for Config {
@@ -32,7 +565,35 @@ impl Default for Config {
                 pr_switch_to_main: false,
                 pr_default_reviewers: None,
                 pr_default_labels: None,
+                sync_remote: None,
+                sync_branch: None,
+                merge_strategy: None,
+                base_branch: None,
+                branch_template: None,
+                commit_template: None,
+                commit_types: std::collections::BTreeMap::new(),
+                no_push: false,
+                no_pull: false,
             },
+            tasks: TasksConfig::default(),
+            slug: SlugConfig::default(),
+            planning: PlanningConfig::default(),
+            import: ImportConfig::default(),
+            notify: NotifyConfig::default(),
+            workflow: WorkflowConfig::default(),
+            body: BodyConfig::default(),
+            webhook: WebhookConfig::default(),
+            caldav: CaldavConfig::default(),
+            google_tasks: GoogleTasksConfig::default(),
+            hooks: HooksConfig::default(),
+            serve: ServeConfig::default(),
+            encryption: EncryptionConfig::default(),
+            urgency: UrgencyConfig::default(),
+            contexts: std::collections::BTreeMap::new(),
+            alias: std::collections::BTreeMap::new(),
+            virtual_sources: None,
+            overlays: Vec::new(),
+            roots: std::collections::BTreeMap::new(),
         }
     }
 }
@@ -44,6 +605,31 @@ impl Default for Config {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Allow modifying done, archived, or cancelled tasks, and starting tasks past a strict WIP limit
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Task file to use when several files share the given ID
+    #[arg(long, global = true)]
+    file: Option<String>,
+
+    /// Use a named context from [contexts] (defaults to $MDTASKS_CONTEXT)
+    #[arg(long, global = true)]
+    context: Option<String>,
+
+    /// Read tasks from this directory (defaults to $MDTASKS_DIR, then the nearest
+    /// `tasks/` in this or a parent directory)
+    #[arg(long, global = true)]
+    tasks_dir: Option<String>,
+
+    /// Use a task directory from [roots], e.g. `add --root api` in a monorepo
+    #[arg(long, global = true)]
+    root: Option<String>,
+
+    /// Show which files and git refs would change without changing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
 }
 
 #[derive(Subcommand)]
@@ -54,6 +640,9 @@ enum SubtaskAction {
         id: String,
         /// Subtask description
         item: String,
+        /// Due date (YYYY-MM-DD), written as `📅 <date>`
+        #[arg(long)]
+        due: Option<String>,
     },
     /// List all subtasks for a task
     List {
@@ -76,6 +665,240 @@ enum SubtaskAction {
     },
 }
 
+/// Date-range filters for `list`; each accepts YYYY-MM-DD or a relative
+/// date such as today, yesterday, -7d, or 2w
+#[derive(clap::Args)]
+struct DateRangeArgs {
+    /// Only tasks due before this date
+    #[arg(long, allow_hyphen_values = true)]
+    due_before: Option<String>,
+
+    /// Only tasks due after this date
+    #[arg(long, allow_hyphen_values = true)]
+    due_after: Option<String>,
+
+    /// Only tasks created on or after this date
+    #[arg(long, allow_hyphen_values = true)]
+    created_since: Option<String>,
+
+    /// Only tasks completed on or after this date
+    #[arg(long, allow_hyphen_values = true)]
+    completed_since: Option<String>,
+}
+
+impl DateRangeArgs {
+    /// Resolve the given bounds into a predicate over tasks
+    fn matcher(&self, today: chrono::NaiveDate) -> Result<impl Fn(&Task) -> bool> {
+        let resolve = |flag: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|value| {
+                    parse_due_shorthand(value.trim(), today).context(format!(
+                        "Invalid --{} date '{}' (try YYYY-MM-DD, yesterday, -7d, or 2w)",
+                        flag, value
+                    ))
+                })
+                .transpose()
+        };
+        let due_before = resolve("due-before", &self.due_before)?;
+        let due_after = resolve("due-after", &self.due_after)?;
+        let created_since = resolve("created-since", &self.created_since)?;
+        let completed_since = resolve("completed-since", &self.completed_since)?;
+
+        // Tasks without the date in question never match its filter
+        let within = |value: &Option<String>, check: &dyn Fn(chrono::NaiveDate) -> bool| {
+            value
+                .as_deref()
+                .and_then(parse_task_date)
+                .is_some_and(check)
+        };
+        Ok(move |task: &Task| {
+            due_before.is_none_or(|bound| within(&task.due, &|due| due < bound))
+                && due_after.is_none_or(|bound| within(&task.due, &|due| due > bound))
+                && created_since.is_none_or(|bound| within(&task.created, &|date| date >= bound))
+                && completed_since
+                    .is_none_or(|bound| within(&task.completed, &|date| date >= bound))
+        })
+    }
+}
+
+/// Merging a finished task branch locally instead of opening a pull request
+#[derive(clap::Args)]
+struct MergeArgs {
+    /// Merge the branch into the base branch and push it (no PR is created)
+    #[arg(long)]
+    merge: bool,
+
+    /// Squash the branch into a single commit on the base branch (implies --merge)
+    #[arg(long)]
+    squash: bool,
+
+    /// Keep the task branch after merging
+    #[arg(long)]
+    no_delete_branch: bool,
+}
+
+/// Output limiting and paging for long listings
+#[derive(clap::Args)]
+struct PagingArgs {
+    /// Only show the first N tasks
+    #[arg(long)]
+    top: Option<usize>,
+
+    /// Show N tasks at a time, waiting for Enter between pages
+    #[arg(long)]
+    page_size: Option<usize>,
+
+    /// Print directly instead of piping through $PAGER
+    #[arg(long)]
+    no_pager: bool,
+}
+
+#[derive(Subcommand)]
+enum ReportKind {
+    /// Compare estimates with the time logged on each task, per task and project
+    Accuracy {
+        /// Include open tasks, not only done ones
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Save a summary of every task under a label (defaults to today's date)
+    Save { label: Option<String> },
+    /// Report tasks added, removed, completed, or changed between two snapshots
+    Diff {
+        /// Older snapshot
+        from: String,
+        /// Newer snapshot (defaults to the current tasks)
+        to: Option<String>,
+    },
+    /// List saved snapshots
+    List,
+}
+
+#[derive(Subcommand)]
+enum RecurAction {
+    /// Show how a rule is understood and its next five occurrences
+    Explain {
+        /// Rule such as "every 2nd tuesday", "monthly on the 1st" or "weekdays"
+        #[arg(required = true)]
+        expr: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SyncSource {
+    /// Apply `Task-Done:` and `Refs-Task:` commit trailers from new commits on the base branch
+    Commits {
+        /// Scan commits after this ref instead of after the last synced commit
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Push tasks as VTODOs to the `[caldav]` task list and pull completions back
+    Caldav,
+    /// Sync tasks with Google Tasks, one list per project, in both directions
+    GoogleTasks,
+}
+
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Import a Notion database export (CSV plus page markdown files)
+    Notion {
+        /// Directory the Notion export was unzipped into
+        dir: String,
+    },
+    /// Import a todo.txt file
+    Todotxt {
+        /// Path to the todo.txt file
+        file: String,
+    },
+    /// Import tasks from Taskwarrior's `task export` JSON
+    Taskwarrior {
+        /// JSON file written by `task export` (runs `task export` when omitted)
+        file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MilestoneAction {
+    /// List milestones with their progress
+    List,
+    /// Show a milestone's tasks and burndown (defaults to the current milestone)
+    Show { name: Option<String> },
+    /// Record a milestone's start and due dates
+    New {
+        name: String,
+        /// First day (YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        start: Option<String>,
+        /// Last day (YYYY-MM-DD)
+        #[arg(long)]
+        due: Option<String>,
+    },
+    /// Close a milestone, warning about tasks that are still open
+    Close {
+        name: String,
+        /// Close without asking when tasks are still open
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    /// Rename a tag on every task that has it
+    Rename {
+        /// Current tag
+        old: String,
+        /// New tag
+        new: String,
+    },
+    /// Remove a tag from every task that has it
+    Rm {
+        /// Tag to remove
+        tag: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhookAction {
+    /// Post queued status-change messages
+    Flush,
+    /// Post a test message
+    Test,
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    /// Write every task as a todo.txt line
+    Todotxt {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Write every task as Taskwarrior JSON, ready for `task import`
+    Taskwarrior {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Write one JSON object per task and line, including the body
+    Jsonl {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Write a static HTML dashboard: board by status, overdue list, and a page per project
+    Html {
+        /// Output directory
+        #[arg(short, long, default_value = "site")]
+        out: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List tasks
@@ -91,22 +914,107 @@ enum Commands {
         /// Filter by priority (low, medium, high)
         #[arg(short, long)]
         priority: Option<String>,
+
+        /// Sort by field (tasks without a value are listed last)
+        #[arg(long, default_value = "id", value_parser = ["id", "due", "priority", "created", "status", "urgency"])]
+        sort: String,
+
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+
+        /// Comma-separated columns: id, status, priority, title, tags, project, created,
+        /// due, started, completed, scheduled, estimate, branch, parent, depends_on, wait_until, postponed,
+        /// milestone, assignee, delegated_to, follow_up, updated, age, subtasks, urgency
+        #[arg(long, default_value = "id,status,priority,title,subtasks")]
+        columns: String,
+
+        /// Also show due date, project, and tags (same as `--columns id,status,priority,title,due,project,tags,subtasks`)
+        #[arg(short, long, conflicts_with = "columns")]
+        long: bool,
+
+        /// Filter by assignee (`me` is your git config user.name)
+        #[arg(long)]
+        assignee: Option<String>,
+
+        /// Include tasks snoozed with a future `wait_until` date
+        #[arg(long)]
+        all: bool,
+
+        /// Only show open tasks whose due date or time has passed
+        #[arg(long)]
+        overdue: bool,
+
+        /// Only show open tasks nobody has touched for `--days` days
+        #[arg(long)]
+        stale: bool,
+
+        /// Days without changes after which `--stale` lists a task
+        #[arg(long, default_value_t = 30, requires = "stale")]
+        days: i64,
+
+        /// Total the remaining estimates of the listed open tasks, per project
+        #[arg(long)]
+        sum_estimates: bool,
+
+        /// Show tasks in sections with subtotals instead of one flat table
+        #[arg(long, value_parser = ["status", "project", "tag", "priority"])]
+        group_by: Option<String>,
+
+        #[command(flatten)]
+        dates: DateRangeArgs,
+
+        #[command(flatten)]
+        paging: PagingArgs,
+    },
+    /// Hand a task off: set it to `waiting` and record who it waits for
+    Delegate {
+        /// Task ID
+        id: String,
+        /// Person the task was handed to
+        #[arg(long)]
+        to: String,
+        /// Date to check back (YYYY-MM-DD, tomorrow, fri, 3d, 2w); `notify` reminds from then on
+        #[arg(long)]
+        follow_up: Option<String>,
+    },
+    /// Hide a task from `list` until a date (YYYY-MM-DD, tomorrow, mon, 3d, 2w)
+    Snooze {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Date the task shows up again
+        until: String,
     },
+    /// Move a task's due date later, counting the slip in `postponed:`
+    Postpone {
+        /// Task ID
+        id: String,
+        /// Shift such as +3d or +1w, or a new date: YYYY-MM-DD, tomorrow, fri, "next monday"
+        #[arg(required = true)]
+        when: Vec<String>,
+    },
+    /// Summarize tasks by status and priority, with overdue and most-postponed tasks
+    Stats,
     /// Show task details
     Show {
-        /// Task ID to show
-        id: String,
+        /// Task IDs, or ranges such as 012-015 (default: the focused task)
+        ids: Vec<String>,
+
+        /// Print the body as raw markdown instead of rendering it
+        #[arg(long)]
+        raw: bool,
     },
     /// Add a new task
     Add {
         /// Task title/description
         title: String,
 
-        /// Task priority (low, medium, high)
-        #[arg(short = 'r', long)]
-        priority: Option<String>,
+        /// Task priority
+        #[arg(short = 'r', long, value_enum)]
+        priority: Option<Priority>,
 
-        /// Task status (pending, active, done)
+        /// Task status (pending, active, done, or a status from [workflow])
         #[arg(short, long)]
         status: Option<String>,
 
@@ -125,63 +1033,468 @@ enum Commands {
         /// Additional notes/content
         #[arg(short, long)]
         notes: Option<String>,
+
+        /// Keep the title as typed instead of reading !priority, #tag, @project and due: from it
+        #[arg(long)]
+        raw: bool,
+
+        /// Store the body encrypted (see [encryption])
+        #[arg(long)]
+        encrypted: bool,
     },
-    /// Mark a task as done
-    Done {
-        /// Task ID to mark as done
+    /// Encrypt a task's body and mark it `encrypted: true`
+    Encrypt {
+        /// Task ID
         id: String,
     },
-    /// Mark a task as started/active
-    Start {
-        /// Task ID to mark as started
+    /// Store a task's body as plain text again
+    Decrypt {
+        /// Task ID
+        id: String,
+    },
+    /// Copy a task to a new ID as a fresh pending task
+    Clone {
+        /// Task ID to copy
         id: String,
+        /// Title for the copy (defaults to the original title)
+        #[arg(long)]
+        title: Option<String>,
+    },
+    /// Mark tasks as done
+    Done {
+        /// Task IDs, or ranges such as 012-015 (default: the focused task)
+        ids: Vec<String>,
+        /// Completion date or timestamp to record instead of now (YYYY-MM-DD, "YYYY-MM-DD HH:MM", or RFC 3339)
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// Mark tasks as started/active
+    Start {
+        /// Task IDs, or ranges such as 012-015 (default: the focused task)
+        ids: Vec<String>,
     },
     /// Manage subtasks for a task
     Subtasks {
         #[command(subcommand)]
         action: SubtaskAction,
     },
-    /// Set task title
-    SetTitle {
-        /// Task ID to update
+    /// Check off a subtask by its number in `subtasks list`
+    Check {
+        /// Task ID
         id: String,
-        /// New title
-        title: String,
+        /// Subtask index (1-based)
+        index: usize,
     },
-    /// Set task priority
-    SetPriority {
-        /// Task ID to update
+    /// Uncheck a subtask by its number in `subtasks list`
+    Uncheck {
+        /// Task ID
         id: String,
-        /// New priority
-        priority: String,
+        /// Subtask index (1-based)
+        index: usize,
     },
-    /// Set task tags
-    SetTags {
+    /// Remove a subtask by its number in `subtasks list`
+    ChecklistRm {
+        /// Task ID
+        id: String,
+        /// Subtask index (1-based)
+        index: usize,
+    },
+    /// Replace the text of a subtask, keeping its checkbox
+    ChecklistEdit {
+        /// Task ID
+        id: String,
+        /// Subtask index (1-based)
+        index: usize,
+        /// New subtask text
+        text: String,
+    },
+    /// Set task title
+    SetTitle {
         /// Task ID to update
         id: String,
+        /// New title
+        title: String,
+    },
+    /// Set task priority
+    SetPriority {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// New priority
+        #[arg(value_enum)]
+        priority: Priority,
+    },
+    /// Set task tags
+    SetTags {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
         /// New tags (comma-separated)
         tags: String,
     },
+    /// Set task effort estimate (e.g. 30m, 2h, 3d)
+    SetEstimate {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Estimate in minutes (m), hours (h, the default), or 8-hour days (d)
+        estimate: String,
+    },
+    /// Assign a task to a team member (`me` is your git config user.name)
+    Assign {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Assignee name
+        user: String,
+    },
+    /// Plan a task for a sprint or milestone
+    SetMilestone {
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Milestone name
+        milestone: String,
+    },
     /// Set task due date
     SetDue {
-        /// Task ID to update
-        id: String,
+        /// Task IDs, or ranges such as 012-015
+        #[arg(required = true)]
+        ids: Vec<String>,
         /// New due date (YYYY-MM-DD)
         due: String,
     },
     /// Add note to task
+    #[command(override_usage = "mdtasks add-note [ID] <NOTE>")]
     AddNote {
-        /// Task ID to add note to
+        /// Task ID (default: the focused task), then the note to add
+        #[arg(required = true, num_args = 1..=2, value_names = ["ID", "NOTE"])]
+        args: Vec<String>,
+    },
+    /// Append a timestamped comment to the task's log
+    #[command(override_usage = "mdtasks comment [OPTIONS] [ID] <TEXT>")]
+    Comment {
+        /// Task ID (default: the focused task), then the comment text
+        #[arg(required = true, num_args = 1..=2, value_names = ["ID", "TEXT"])]
+        args: Vec<String>,
+        /// Author to record (defaults to git config user.name)
+        #[arg(long)]
+        author: Option<String>,
+    },
+    /// Show a task's comment log in chronological order
+    Log {
+        /// Task ID (default: the focused task)
+        id: Option<String>,
+    },
+    /// Show who changed a task's fields and when, from the git history of its file
+    History {
+        /// Task ID (default: the focused task)
+        id: Option<String>,
+    },
+    /// Run a focus timer on a task and record the finished interval in its time log
+    Pomodoro {
+        /// Task ID (default: the focused task)
+        id: Option<String>,
+        /// Interval length, e.g. 25m, 50m, 1h (plain numbers are minutes)
+        #[arg(long, default_value = "25m")]
+        length: String,
+        /// Show a desktop notification when the interval ends
+        #[arg(long)]
+        notify: bool,
+    },
+    /// Copy a file into the task's attachments directory
+    Attach {
+        /// Task ID to attach the file to
+        id: String,
+        /// File to attach
+        path: String,
+    },
+    /// List a task's attachments
+    Attachments {
+        /// Task ID
         id: String,
-        /// Note to add
-        note: String,
+        /// Open the attachment with this name or number
+        #[arg(long)]
+        open: Option<String>,
+    },
+    /// List tasks whose body links to this one with `[[id]]`
+    Backlinks {
+        /// Task ID
+        id: String,
+    },
+    /// Open a task's markdown file with the OS default application
+    Open {
+        /// Task ID (default: the focused task)
+        id: Option<String>,
+    },
+    /// Set the task you are working on, which commands given no ID act on
+    Focus {
+        /// Task ID
+        #[arg(required_unless_present = "clear")]
+        id: Option<String>,
+
+        /// Stop focusing on a task
+        #[arg(long, conflicts_with = "id")]
+        clear: bool,
+    },
+    /// Show the focused task
+    Current {
+        /// Print only its ID, for scripts
+        #[arg(long)]
+        id_only: bool,
+    },
+    /// Choose a task with a fuzzy finder and run a command on it
+    Pick {
+        /// Command to run on the chosen task
+        #[arg(default_value = "show", value_parser = ["show", "start", "done", "edit"])]
+        command: String,
+
+        /// Initial search text
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Include done, archived, and cancelled tasks
+        #[arg(long)]
+        all: bool,
+    },
+    /// Print the path of a task's markdown file
+    Path {
+        /// Task ID (default: the focused task)
+        id: Option<String>,
+        /// Print an absolute path
+        #[arg(long)]
+        absolute: bool,
+    },
+    /// Apply field changes to every task matching the filters
+    Modify {
+        /// Filter by status (pending, active, done, partial)
+        #[arg(short, long)]
+        status: Option<String>,
+
+        /// Filter by tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Filter by priority (low, medium, high)
+        #[arg(short, long)]
+        priority: Option<String>,
+
+        /// Field change as field=value (title, status, priority, tags, project, due, scheduled, estimate)
+        #[arg(long = "set", required = true)]
+        set: Vec<String>,
+    },
+    /// Create or update tasks from TODO/FIXME comments in source files
+    Scan {
+        /// File or directory to scan (repeatable)
+        #[arg(long, default_value = ".")]
+        path: Vec<String>,
+    },
+    /// Import tasks from another tool's export
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// List all tags with open and done task counts
+    Tags,
+    /// Rename or remove a tag across all tasks
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    /// Chart open tasks (or remaining estimate) per day over the last weeks
+    Burndown {
+        /// Number of weeks to chart, ending today
+        #[arg(long, default_value_t = 4)]
+        weeks: i64,
+
+        /// Only count tasks in this project
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only count tasks in this milestone
+        #[arg(long)]
+        milestone: Option<String>,
+
+        /// Chart the remaining estimate in hours instead of the task count
+        #[arg(long)]
+        estimate: bool,
+    },
+    /// Group tasks into sprints or milestones via the `milestone:` field
+    Milestone {
+        #[command(subcommand)]
+        action: MilestoneAction,
+    },
+    /// Send queued chat messages or test the webhook
+    Webhook {
+        #[command(subcommand)]
+        action: WebhookAction,
+    },
+    /// Export tasks in another tool's format
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+    /// Create a local task from a virtual task
+    Adopt {
+        /// Virtual task ID (as shown in list)
+        virtual_id: String,
+    },
+    /// Show open tasks in an urgent/important (Eisenhower) matrix
+    Matrix {
+        /// Tasks due within this many days count as urgent
+        #[arg(long, default_value_t = 3)]
+        urgent_days: i64,
+    },
+    /// End-of-day review: notes on active tasks, tomorrow's schedule, and a summary
+    Shutdown,
+    /// Weekly review: walk through overdue, stale, and untagged tasks one at a time
+    Review {
+        /// Open tasks nobody has touched for this many days count as stale
+        #[arg(long, default_value_t = 30)]
+        stale_days: i64,
+    },
+    /// Show overdue, active, and today's tasks, most important first
+    Today,
+    /// Suggest the most urgent open task that isn't blocked
+    Next,
+    /// Show overdue and active tasks, then what is due or scheduled each day
+    Agenda {
+        /// Number of days to show, starting today
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+    /// Distribute open tasks across Monday–Friday and print the week agenda
+    PlanWeek {
+        /// Task IDs to plan (prompts for a selection when omitted)
+        ids: Vec<String>,
+    },
+    /// Work with recurrence rules (the `recur` field)
+    Recur {
+        #[command(subcommand)]
+        action: RecurAction,
+    },
+    /// Save and compare backlog snapshots
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Reports on past work
+    Report {
+        #[command(subcommand)]
+        kind: ReportKind,
+    },
+    /// Resolve git conflict markers in task files, merging front matter field by field
+    Resolve {
+        /// Side to keep when a field cannot be merged automatically, instead of asking
+        #[arg(long, value_parser = ["ours", "theirs"])]
+        prefer: Option<String>,
+    },
+    /// Share tasks through git: commit task changes, pull with rebase, and push
+    Sync {
+        #[command(subcommand)]
+        source: Option<SyncSource>,
+
+        /// Commit message for local task changes
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// Print tasks as an outline of projects and milestones
+    Roadmap {
+        /// Print markdown for sharing (implied by --output)
+        #[arg(long)]
+        markdown: bool,
+
+        /// Write the markdown roadmap to this file
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Leave out done, archived, and cancelled tasks
+        #[arg(long)]
+        open: bool,
+    },
+    /// Print the task dependency and parent graph for Graphviz or Mermaid
+    Graph {
+        /// Output format
+        #[arg(long, default_value = "dot", value_parser = ["dot", "mermaid"])]
+        format: String,
+
+        /// Leave out done, archived, and cancelled tasks
+        #[arg(long)]
+        open: bool,
+    },
+    /// Show parent tasks with their child tasks and checklist items
+    Tree {
+        /// Task to show the tree below (default: every open top-level task)
+        id: Option<String>,
+    },
+    /// Reassign sequential IDs, renaming files and updating parent/depends_on references
+    Renumber,
+    /// Time loading and querying a synthetic tasks directory, with and without the index
+    Bench {
+        /// Number of synthetic tasks to generate
+        #[arg(long, default_value_t = 1000)]
+        tasks: usize,
+
+        /// Runs per measurement (the fastest is reported)
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+    },
+    /// Print due-date reminders that are due according to the escalation chain
+    Notify,
+    /// Watch the tasks directory, validating files and printing a summary on change
+    Watch,
+    /// Check every task file for problems (missing fields, duplicate IDs, bad dates, ...)
+    Doctor {
+        /// Repair the problems that can be fixed automatically
+        #[arg(long)]
+        fix: bool,
+
+        /// Also give the newer of two files sharing an ID a fresh ID (implies --fix)
+        #[arg(long)]
+        fix_duplicates: bool,
+    },
+    /// Serve a local REST API over the task files
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 7777)]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
     },
     /// Start Git branch for task
     GitStart {
         /// Task ID to create branch for
         id: String,
+
+        /// Branch to start from, instead of git.base_branch or origin's HEAD
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Only continue on the task's existing branch, failing if there is none
+        #[arg(long)]
+        resume: bool,
+
+        /// Don't pull or fetch the base branch first (work offline)
+        #[arg(long)]
+        no_pull: bool,
+    },
+    /// Check out an existing task branch, leaving a note on the task being left
+    GitSwitch {
+        /// Task ID to switch to
+        id: String,
+        /// "Where I left off" note for the current task (prompted for when omitted)
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Link the current (manually created) branch to a task
+    GitAdoptBranch {
+        /// Task ID (defaults to the number in the branch name)
+        id: Option<String>,
     },
-    /// Finish Git branch, create PR, and optionally merge to main
+    /// Finish Git branch, create PR, or merge it into the base branch
+    #[command(visible_alias = "git-finish")]
     GitDone {
         /// Optional commit message (defaults to task title)
         message: Option<String>,
@@ -202,9 +1515,24 @@ enum Commands {
         #[arg(long)]
         labels: Option<String>,
 
-        /// Switch back to main after PR creation
+        /// Switch back to the base branch after PR creation
         #[arg(long)]
         switch_to_main: bool,
+
+        #[command(flatten)]
+        merge: MergeArgs,
+
+        /// Commit (and merge) locally without pushing; no PR is created
+        #[arg(long)]
+        no_push: bool,
+
+        /// Don't pull or fetch the base branch before merging
+        #[arg(long)]
+        no_pull: bool,
+
+        /// Branch to merge into or return to, instead of git.base_branch or origin's HEAD
+        #[arg(long)]
+        base: Option<String>,
     },
     /// Show Git status and current task
     GitStatus,
@@ -214,6 +1542,31 @@ enum Commands {
         #[arg(short, long)]
         yes: bool,
     },
+    /// Revert the most recent modification to task files
+    Undo {
+        /// List recorded modifications instead of undoing
+        #[arg(short, long)]
+        list: bool,
+    },
+    /// Exit successfully if a task with this ID exists (prints nothing)
+    Exists {
+        /// Task ID to look up
+        id: String,
+    },
+    /// Print the ID the next added task would get
+    NextId {
+        /// Project of the new task, for per-project ID prefixes
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Generate shell completion script (bash, zsh, fish, elvish, powershell)
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// Print task IDs and titles for shell completion
+    #[command(name = "_complete-ids", hide = true)]
+    CompleteIds,
     /// Initialize configuration file
     ConfigInit {
         /// Path to create config file (default: ./mdtasks.toml)
@@ -222,7 +1575,76 @@ enum Commands {
     },
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Commands {
+    /// The local task IDs a command was given, for resolving abbreviations;
+    /// `exists` keeps its exact lookup
+    fn task_ids_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Commands::Delegate { id, .. }
+            | Commands::Postpone { id, .. }
+            | Commands::Encrypt { id, .. }
+            | Commands::Decrypt { id, .. }
+            | Commands::Clone { id, .. }
+            | Commands::Check { id, .. }
+            | Commands::Uncheck { id, .. }
+            | Commands::ChecklistRm { id, .. }
+            | Commands::ChecklistEdit { id, .. }
+            | Commands::SetTitle { id, .. }
+            | Commands::Attach { id, .. }
+            | Commands::Attachments { id, .. }
+            | Commands::Backlinks { id, .. }
+            | Commands::GitStart { id, .. }
+            | Commands::GitSwitch { id, .. } => vec![id],
+            Commands::Tree { id: Some(id) }
+            | Commands::GitAdoptBranch { id: Some(id) }
+            | Commands::Focus { id: Some(id), .. }
+            | Commands::Log { id: Some(id) }
+            | Commands::History { id: Some(id) }
+            | Commands::Pomodoro { id: Some(id), .. }
+            | Commands::Open { id: Some(id) }
+            | Commands::Path { id: Some(id), .. } => vec![id],
+            // With only the text given, the focused task is used
+            Commands::Comment { args, .. } | Commands::AddNote { args } => {
+                match args.as_mut_slice() {
+                    [id, _] => vec![id],
+                    _ => Vec::new(),
+                }
+            }
+            Commands::Subtasks {
+                action:
+                    SubtaskAction::Add { id, .. }
+                    | SubtaskAction::List { id }
+                    | SubtaskAction::Complete { id, .. }
+                    | SubtaskAction::Incomplete { id, .. },
+            } => vec![id],
+            Commands::PlanWeek { ids, .. } => ids.iter_mut().collect(),
+            _ => match self.task_id_list_mut() {
+                Some(ids) => ids.iter_mut().collect(),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// The ID list of commands that take several tasks, for expanding ranges
+    fn task_id_list_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            Commands::Show { ids, .. }
+            | Commands::Done { ids, .. }
+            | Commands::Start { ids, .. }
+            | Commands::SetPriority { ids, .. }
+            | Commands::SetTags { ids, .. }
+            | Commands::SetEstimate { ids, .. }
+            | Commands::Assign { ids, .. }
+            | Commands::SetMilestone { ids, .. }
+            | Commands::SetDue { ids, .. }
+            | Commands::Snooze { ids, .. }
+            | Commands::PlanWeek { ids, .. } => Some(ids),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct Task {
     id: String,
     title: String,
@@ -234,58 +1656,406 @@ struct Task {
     due: Option<String>,
     completed: Option<String>,
     started: Option<String>,
+    branch: Option<String>,
+    scheduled: Option<String>,
+    estimate: Option<String>,
+    parent: Option<String>,
+    depends_on: Option<Vec<String>>,
+    recur: Option<String>,
+    /// Hidden from `list` until this date
+    wait_until: Option<String>,
+    /// Number of times `postpone` moved the due date later
+    postponed: Option<u32>,
+    /// Sprint or milestone the task is planned for
+    milestone: Option<String>,
+    /// Team member responsible for the task
+    assignee: Option<String>,
+    /// Person a `waiting` task was handed off to
+    delegated_to: Option<String>,
+    /// Date to check back with the delegate
+    follow_up: Option<String>,
+    /// When mdtasks last wrote the task file
+    updated: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct TaskFile {
     task: Task,
     file_path: String,
     content: String,
 }
 
-fn load_config() -> Result<Config> {
-    // Look for config file in current directory or home directory
-    let config_paths = [
-        "./mdtasks.toml",
-        "./.mdtasks.toml",
-        "~/.config/mdtasks/config.toml",
-        "~/.mdtasks.toml",
-    ];
+/// Point the config at a named context's task directory; its state
+/// (undo journal, index, lock) lives in `.mdtasks` next to that directory
+fn select_context(name: &str, config: &mut Config, quiet: bool) -> Result<()> {
+    let dir = config.contexts.get(name).context(format!(
+        "Unknown context '{}' (configured: {})",
+        name,
+        config
+            .contexts
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))?;
+    let dir = shellexpand::tilde(dir).to_string();
+
+    if !quiet {
+        println!("🗂️  Context: {} ({})", name, dir);
+    }
+    use_tasks_dir(dir, config);
 
-    for path_str in &config_paths {
-        let expanded_path = shellexpand::tilde(path_str).to_string();
-        let path = Path::new(&expanded_path);
+    Ok(())
+}
+
+/// Point the config at one of the monorepo task roots from `[roots]`
+fn select_root(name: &str, config: &mut Config, quiet: bool) -> Result<()> {
+    let dir = config.roots.get(name).cloned().context(format!(
+        "Unknown root '{}' (configured: {})",
+        name,
+        config.roots.keys().cloned().collect::<Vec<_>>().join(", ")
+    ))?;
+
+    if !quiet {
+        println!("🗂️  Root: {} ({})", name, dir);
+    }
+    use_tasks_dir(dir, config);
+
+    Ok(())
+}
+
+/// Read tasks from `dir` only, keeping state (undo journal, index, lock) in
+/// `.mdtasks` next to it
+fn use_tasks_dir(dir: String, config: &mut Config) {
+    let state = match Path::new(&dir).parent() {
+        Some(parent) => parent.join(".mdtasks"),
+        None => PathBuf::from(".mdtasks"),
+    };
+    let _ = CONTEXT_STATE_DIR.set(state);
+    config.tasks.dir = dir;
+    config.roots.clear();
+}
+
+/// The current directory and each of its parents as relative paths
+/// (`.`, `..`, `../..`), paired with their absolute location
+fn ancestor_dirs() -> Vec<(PathBuf, PathBuf)> {
+    let Ok(cwd) = std::env::current_dir() else {
+        return vec![(PathBuf::from("."), PathBuf::from("."))];
+    };
+    cwd.ancestors()
+        .enumerate()
+        .map(|(level, absolute)| {
+            let relative = match level {
+                0 => PathBuf::from("."),
+                _ => std::iter::repeat_n("..", level).collect(),
+            };
+            (relative, absolute.to_path_buf())
+        })
+        .collect()
+}
+
+fn load_config(quiet: bool) -> Result<Config> {
+    let ancestors = ancestor_dirs();
+    let home = home_dir();
 
+    // Like git finds `.git`, use the nearest directory with a project config,
+    // then fall back to the config in the home directory
+    let mut config_paths: Vec<(Option<&Path>, PathBuf)> = Vec::new();
+    for (level, (relative, absolute)) in ancestors.iter().enumerate() {
+        // Config files directly in the home directory are the global ones
+        if level > 0 && home.as_deref() == Some(absolute.as_path()) {
+            continue;
+        }
+        for name in ["mdtasks.toml", ".mdtasks.toml"] {
+            config_paths.push((Some(relative), relative.join(name)));
+        }
+    }
+    for path_str in ["~/.config/mdtasks/config.toml", "~/.mdtasks.toml"] {
+        config_paths.push((None, PathBuf::from(shellexpand::tilde(path_str).as_ref())));
+    }
+
+    let mut config = Config::default();
+    let mut project_root = None;
+    for (root, path) in &config_paths {
         if path.exists() {
             let content = std::fs::read_to_string(path)
                 .context(format!("Failed to read config file: {}", path.display()))?;
 
-            let config: Config = toml::from_str(&content)
+            config = toml::from_str(&content)
                 .context(format!("Failed to parse config file: {}", path.display()))?;
 
-            println!("📁 Loaded config from: {}", path.display());
-            return Ok(config);
+            if !quiet {
+                println!("📁 Loaded config from: {}", path.display());
+            }
+            project_root = *root;
+            break;
+        }
+    }
+
+    // Without a project config, the nearest directory with a tasks folder is the project
+    let tasks_dir = PathBuf::from(shellexpand::tilde(&config.tasks.dir).as_ref());
+    if tasks_dir.is_relative() {
+        if project_root.is_none() {
+            project_root = ancestors
+                .iter()
+                .map(|(relative, _)| relative.as_path())
+                .find(|relative| relative.join(&tasks_dir).is_dir());
+        }
+        if let Some(root) = project_root.filter(|root| *root != Path::new(".")) {
+            config.tasks.dir = root.join(&tasks_dir).to_string_lossy().into_owned();
+            let _ = PROJECT_ROOT.set(root.to_path_buf());
+        }
+    }
+
+    // Task roots are relative to the project, like the tasks directory
+    for dir in config.roots.values_mut() {
+        let expanded = PathBuf::from(shellexpand::tilde(dir.as_str()).as_ref());
+        *dir = match project_root {
+            Some(root) if expanded.is_relative() && root != Path::new(".") => {
+                root.join(expanded).to_string_lossy().into_owned()
+            }
+            _ => expanded.to_string_lossy().into_owned(),
+        };
+    }
+
+    Ok(config)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let home = shellexpand::tilde("~");
+    (home != "~").then(|| PathBuf::from(home.as_ref()))
+}
+
+/// Split an alias definition into arguments, honouring quotes and backslashes
+fn split_alias_args(definition: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = definition.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow::anyhow!(
+            "Unterminated quote in alias: {}",
+            definition
+        ));
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Replace a user-defined alias from `[alias]` with its definition before
+/// clap sees the arguments; built-in commands always take precedence
+fn expand_alias(mut args: Vec<String>) -> Result<Vec<String>> {
+    // A broken config is reported once the command runs
+    let Ok(config) = load_config(true) else {
+        return Ok(args);
+    };
+    if config.alias.is_empty() {
+        return Ok(args);
+    }
+
+    // Skip global flags to find the command name
+    let mut position = 1;
+    while let Some(arg) = args.get(position) {
+        match arg.as_str() {
+            "--force" | "--dry-run" => position += 1,
+            "--file" | "--context" | "--tasks-dir" | "--root" => position += 2,
+            arg if arg.starts_with("--file=")
+                || arg.starts_with("--context=")
+                || arg.starts_with("--tasks-dir=")
+                || arg.starts_with("--root=") =>
+            {
+                position += 1
+            }
+            _ => break,
         }
     }
 
-    // Return default config if no config file found
-    Ok(Config::default())
+    let Some(name) = args.get(position).filter(|arg| !arg.starts_with('-')) else {
+        return Ok(args);
+    };
+    let Some(definition) = config.alias.get(name) else {
+        return Ok(args);
+    };
+    if Cli::command().find_subcommand(name).is_some() {
+        eprintln!(
+            "⚠️  Alias '{}' is ignored because it is a built-in command",
+            name
+        );
+        return Ok(args);
+    }
+
+    let expansion = split_alias_args(definition)?;
+    if expansion.is_empty() {
+        return Err(anyhow::anyhow!("Alias '{}' is empty", name));
+    }
+    args.splice(position..=position, expansion);
+    Ok(args)
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let config = load_config()?;
+    let mut cli = Cli::parse_from(expand_alias(std::env::args().collect())?);
+
+    // Completion helpers must not print anything besides their output
+    let quiet = matches!(
+        cli.command,
+        Commands::Completions { .. }
+            | Commands::CompleteIds
+            | Commands::Exists { .. }
+            | Commands::NextId { .. }
+            | Commands::Export {
+                format: ExportFormat::Todotxt { output: None }
+                    | ExportFormat::Taskwarrior { output: None }
+                    | ExportFormat::Jsonl { output: None }
+            }
+    );
+    let mut config = load_config(quiet)?;
+    init_timezone(&config)?;
+
+    let context = cli
+        .context
+        .clone()
+        .or_else(|| std::env::var("MDTASKS_CONTEXT").ok())
+        .filter(|name| !name.is_empty());
+    let tasks_dir = cli
+        .tasks_dir
+        .clone()
+        .or_else(|| std::env::var("MDTASKS_DIR").ok())
+        .filter(|dir| !dir.is_empty());
+    if let Some(dir) = tasks_dir {
+        use_tasks_dir(shellexpand::tilde(&dir).into_owned(), &mut config);
+    } else if let Some(ref name) = cli.root {
+        select_root(name, &mut config, quiet)?;
+    } else if let Some(name) = context {
+        select_context(&name, &mut config, quiet)?;
+    }
+
+    if cli.dry_run {
+        let _ = DRY_RUN.set(true);
+        if !quiet {
+            println!("🔍 Dry run: showing what would change, nothing is written");
+        }
+    }
+
+    if let Some(ref file) = cli.file {
+        let path = Path::new(file)
+            .canonicalize()
+            .context(format!("Task file not found: {}", file))?;
+        let _ = TARGET_FILE.set(path);
+    }
+
+    // Ranges are expanded, and abbreviated IDs and title words become the full
+    // ID before any command runs, so branch names, attachment folders, and
+    // messages use the real one
+    if !cli.command.task_ids_mut().is_empty() {
+        if let Ok(tasks) = load_tasks(&config) {
+            if let Some(ids) = cli.command.task_id_list_mut() {
+                *ids = expand_id_ranges(std::mem::take(ids), &tasks)?;
+            }
+            for id in cli.command.task_ids_mut() {
+                if let Some(task_file) = matching_tasks(tasks.clone(), id)?.first() {
+                    *id = task_file.task.id.clone();
+                }
+            }
+        }
+    }
 
     match cli.command {
         Commands::List {
             status,
             tag,
             priority,
+            sort,
+            reverse,
+            columns,
+            long,
+            assignee,
+            all,
+            overdue,
+            stale,
+            days,
+            sum_estimates,
+            group_by,
+            dates,
+            paging,
         } => {
-            list_tasks(status, tag, priority)?;
+            let columns = if long {
+                "id,status,priority,title,due,project,tags,subtasks".to_string()
+            } else {
+                columns
+            };
+            list_tasks(
+                status,
+                tag,
+                priority,
+                assignee,
+                &sort,
+                reverse,
+                &columns,
+                all,
+                overdue,
+                stale.then_some(days),
+                sum_estimates,
+                group_by.as_deref(),
+                &dates,
+                &paging,
+                &config,
+            )?;
         }
-        Commands::Show { id } => {
-            show_task(id)?;
+        Commands::Postpone { id, when } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            postpone_task(id, &when.join(" "), &config)?;
+        }
+        Commands::Stats => {
+            show_stats(&config)?;
+        }
+        Commands::Delegate { id, to, follow_up } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            delegate_task(id, &to, follow_up.as_deref(), &config)?;
+        }
+        Commands::Snooze { ids, until } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                snooze_task(id, &until, &config)
+            })?;
+        }
+        Commands::Show { ids, raw } => {
+            let mut first = true;
+            for_each_task(or_focused_tasks(ids)?, |id| {
+                if !std::mem::take(&mut first) {
+                    println!("\n{}\n", "=".repeat(80));
+                }
+                show_task(id, raw, &config)
+            })?;
         }
         Commands::Add {
             title,
@@ -295,55 +2065,350 @@ fn main() -> Result<()> {
             project,
             due,
             notes,
+            raw,
+            encrypted,
         } => {
-            add_task(title, priority, status, tags, project, due, notes)?;
+            let mut capture = QuickCapture::from_title(title.clone());
+            if !raw {
+                capture = parse_quick_capture(&title)?;
+            }
+            // Explicit options win over words in the title; tags are combined
+            let mut tags = tags.unwrap_or_default();
+            for tag in capture.tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            add_task(
+                capture.title,
+                priority
+                    .or(capture.priority)
+                    .map(|p| p.as_str().to_string()),
+                status,
+                (!tags.is_empty()).then_some(tags),
+                project.or(capture.project),
+                due.or(capture.due),
+                notes,
+                encrypted,
+                &config,
+            )?;
+        }
+        Commands::Encrypt { id } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            set_task_encryption(id, true, &config)?;
+        }
+        Commands::Decrypt { id } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            set_task_encryption(id, false, &config)?;
         }
-        Commands::Done { id } => {
-            mark_task_done(id)?;
+        Commands::Clone { id, title } => {
+            clone_task(id, title, &config)?;
         }
-        Commands::Start { id } => {
-            mark_task_start(id)?;
+        Commands::Done { ids, at } => {
+            for_each_task(or_focused_tasks(ids)?, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                mark_task_done(id, at.clone(), &config)
+            })?;
+        }
+        Commands::Start { ids } => {
+            for_each_task(or_focused_tasks(ids)?, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                check_wip_limit(&id, cli.force, &config)?;
+                mark_task_start(id, &config)
+            })?;
         }
         Commands::Subtasks { action } => match action {
-            SubtaskAction::Add { id, item } => {
-                add_subtask(id, item)?;
+            SubtaskAction::Add { id, item, due } => {
+                ensure_task_editable(&id, cli.force, &config)?;
+                add_subtask(id, item, due, &config)?;
             }
             SubtaskAction::List { id } => {
-                list_subtasks(id)?;
+                list_subtasks(id, &config)?;
             }
             SubtaskAction::Complete { id, index } => {
-                complete_subtask(id, index)?;
+                ensure_task_editable(&id, cli.force, &config)?;
+                complete_subtask(id, index, &config)?;
             }
             SubtaskAction::Incomplete { id, index } => {
-                incomplete_subtask(id, index)?;
+                ensure_task_editable(&id, cli.force, &config)?;
+                incomplete_subtask(id, index, &config)?;
             }
         },
-        Commands::SetTitle { id, title } => {
-            set_task_field(id, "title", title)?;
+        Commands::Check { id, index } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            check_subtask(id, index, &config)?;
         }
-        Commands::SetPriority { id, priority } => {
-            set_task_field(id, "priority", priority)?;
+        Commands::Uncheck { id, index } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            incomplete_subtask(id, index, &config)?;
         }
-        Commands::SetTags { id, tags } => {
-            set_task_field(id, "tags", tags)?;
+        Commands::ChecklistRm { id, index } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            change_subtask(id, index, SubtaskEdit::Remove, &config)?;
         }
-        Commands::SetDue { id, due } => {
-            set_task_field(id, "due", due)?;
+        Commands::ChecklistEdit { id, index, text } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            change_subtask(id, index, SubtaskEdit::Rename(text), &config)?;
         }
-        Commands::AddNote { id, note } => {
-            add_task_note(id, note)?;
+        Commands::SetTitle { id, title } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            set_task_field(id, "title", title, &config)?;
         }
-        Commands::GitStart { id } => {
-            git_start_branch(id, &config)?;
+        Commands::SetPriority { ids, priority } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "priority", priority.as_str().to_string(), &config)
+            })?;
         }
-        Commands::GitDone {
+        Commands::SetTags { ids, tags } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "tags", tags.clone(), &config)
+            })?;
+        }
+        Commands::SetEstimate { ids, estimate } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "estimate", estimate.clone(), &config)
+            })?;
+        }
+        Commands::Assign { ids, user } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "assignee", resolve_assignee(&user)?, &config)
+            })?;
+        }
+        Commands::SetMilestone { ids, milestone } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "milestone", milestone.clone(), &config)
+            })?;
+        }
+        Commands::SetDue { ids, due } => {
+            for_each_task(ids, |id| {
+                ensure_task_editable(&id, cli.force, &config)?;
+                set_task_field(id, "due", due.clone(), &config)
+            })?;
+        }
+        Commands::AddNote { args } => {
+            let (id, note) = focused_task_and_text(args)?;
+            ensure_task_editable(&id, cli.force, &config)?;
+            add_task_note(id, note, &config)?;
+        }
+        Commands::Comment { args, author } => {
+            let (id, text) = focused_task_and_text(args)?;
+            ensure_task_editable(&id, cli.force, &config)?;
+            add_task_comment(id, text, author, &config)?;
+        }
+        Commands::Pomodoro { id, length, notify } => {
+            run_pomodoro(or_focused_task(id)?, &length, notify, &config)?;
+        }
+        Commands::Log { id } => {
+            show_task_log(or_focused_task(id)?, &config)?;
+        }
+        Commands::History { id } => {
+            show_task_history(or_focused_task(id)?, &config)?;
+        }
+        Commands::Attach { id, path } => {
+            ensure_task_editable(&id, cli.force, &config)?;
+            attach_file(id, path, &config)?;
+        }
+        Commands::Attachments { id, open } => {
+            list_attachments(id, open, &config)?;
+        }
+        Commands::Backlinks { id } => {
+            show_backlinks(id, &config)?;
+        }
+        Commands::Open { id } => {
+            open_task_file(or_focused_task(id)?, &config)?;
+        }
+        Commands::Focus { id, clear } => match id {
+            Some(id) if !clear => focus_task(&id, &config)?,
+            _ => clear_focus()?,
+        },
+        Commands::Current { id_only } => {
+            show_current_task(id_only, &config)?;
+        }
+        Commands::Pick {
+            command,
+            query,
+            all,
+        } => {
+            pick_task(&command, query.as_deref(), all, cli.force, &config)?;
+        }
+        Commands::Path { id, absolute } => {
+            print_task_path(or_focused_task(id)?, absolute, &config)?;
+        }
+        Commands::Modify {
+            status,
+            tag,
+            priority,
+            set,
+        } => {
+            modify_tasks(status, tag, priority, set, cli.dry_run, cli.force, &config)?;
+        }
+        Commands::Scan { path } => {
+            scan_code_todos(&path, &config)?;
+        }
+        Commands::Import { source } => match source {
+            ImportSource::Notion { dir } => {
+                import_notion(&dir, cli.dry_run, &config)?;
+            }
+            ImportSource::Todotxt { file } => {
+                import_todotxt(&file, cli.dry_run, &config)?;
+            }
+            ImportSource::Taskwarrior { file } => {
+                import_taskwarrior(file.as_deref(), cli.dry_run, &config)?;
+            }
+        },
+        Commands::Tags => {
+            list_tags(&config)?;
+        }
+        Commands::Burndown {
+            weeks,
+            project,
+            milestone,
+            estimate,
+        } => {
+            show_burndown(weeks, project, milestone, estimate, &config)?;
+        }
+        Commands::Roadmap {
+            markdown,
+            output,
+            open,
+        } => {
+            print_roadmap(markdown, output.as_deref(), open, &config)?;
+        }
+        Commands::Milestone { action } => match action {
+            MilestoneAction::List => list_milestones(&config)?,
+            MilestoneAction::Show { name } => show_milestone(name, &config)?,
+            MilestoneAction::New { name, start, due } => new_milestone(&name, start, due, &config)?,
+            MilestoneAction::Close { name, yes } => close_milestone(&name, yes, &config)?,
+        },
+        Commands::Tag { action } => match action {
+            TagAction::Rename { old, new } => retag_tasks(&old, Some(&new), cli.dry_run, &config)?,
+            TagAction::Rm { tag } => retag_tasks(&tag, None, cli.dry_run, &config)?,
+        },
+        Commands::Webhook { action } => match action {
+            WebhookAction::Flush => flush_webhook_queue(&config, false)?,
+            WebhookAction::Test => test_webhook(&config)?,
+        },
+        Commands::Export { format } => match format {
+            ExportFormat::Todotxt { output } => export_todotxt(output.as_deref(), &config)?,
+            ExportFormat::Taskwarrior { output } => export_taskwarrior(output.as_deref(), &config)?,
+            ExportFormat::Jsonl { output } => export_jsonl(output.as_deref(), &config)?,
+            ExportFormat::Html { out } => export_html(&out, &config)?,
+        },
+        Commands::Adopt { virtual_id } => {
+            adopt_virtual_task(virtual_id, &config)?;
+        }
+        Commands::Matrix { urgent_days } => {
+            show_priority_matrix(urgent_days, &config)?;
+        }
+        Commands::Shutdown => {
+            shutdown_ritual(&config)?;
+        }
+        Commands::Review { stale_days } => {
+            weekly_review(stale_days, &config)?;
+        }
+        Commands::Today => {
+            show_agenda(1, &config)?;
+        }
+        Commands::Next => {
+            suggest_next_task(&config)?;
+        }
+        Commands::Agenda { days } => {
+            show_agenda(days, &config)?;
+        }
+        Commands::PlanWeek { ids } => {
+            plan_week(ids, &config)?;
+        }
+        Commands::Recur { action } => match action {
+            RecurAction::Explain { expr } => explain_recurrence(&expr.join(" "), 5)?,
+        },
+        Commands::Report { kind } => match kind {
+            ReportKind::Accuracy { all } => print_accuracy_report(all, &config)?,
+        },
+        Commands::Snapshot { action } => match action {
+            SnapshotAction::Save { label } => save_snapshot(label, &config)?,
+            SnapshotAction::Diff { from, to } => diff_snapshots(&from, to.as_deref(), &config)?,
+            SnapshotAction::List => list_snapshots()?,
+        },
+        Commands::Resolve { prefer } => {
+            resolve_conflicts(prefer.as_deref(), &config)?;
+        }
+        Commands::Sync { source, message } => match source {
+            Some(SyncSource::Commits { since }) => {
+                sync_commit_trailers(since, cli.dry_run, &config)?;
+            }
+            Some(SyncSource::Caldav) => sync_caldav(cli.dry_run, &config)?,
+            Some(SyncSource::GoogleTasks) => sync_google_tasks(cli.dry_run, &config)?,
+            None => sync_tasks_with_remote(message, &config)?,
+        },
+        Commands::Graph { format, open } => {
+            print_task_graph(&format, open, &config)?;
+        }
+        Commands::Tree { id } => {
+            print_task_tree(id.as_deref(), &config)?;
+        }
+        Commands::Renumber => {
+            renumber_tasks(cli.dry_run, &config)?;
+        }
+        Commands::Bench { tasks, runs } => {
+            run_benchmark(tasks, runs)?;
+        }
+        Commands::Notify => {
+            notify_due_tasks(cli.dry_run, &config)?;
+        }
+        Commands::Watch => {
+            watch_tasks(&config)?;
+        }
+        Commands::Doctor {
+            fix,
+            fix_duplicates,
+        } => {
+            run_doctor(fix, fix_duplicates, &config)?;
+        }
+        Commands::Serve { port, host } => {
+            serve_api(&host, port, &config)?;
+        }
+        Commands::GitStart {
+            id,
+            base,
+            resume,
+            no_pull,
+        } => {
+            if base.is_some() {
+                config.git.base_branch = base;
+            }
+            config.git.no_pull |= no_pull;
+            ensure_task_editable(&id, cli.force, &config)?;
+            check_wip_limit(&id, cli.force, &config)?;
+            git_start_branch(id, resume, &config)?;
+        }
+        Commands::GitSwitch { id, note } => {
+            git_switch_task(id, note, &config)?;
+        }
+        Commands::GitAdoptBranch { id } => {
+            git_adopt_branch(id, &config)?;
+        }
+        Commands::GitDone {
             message,
             no_pr,
             draft,
             reviewers,
             labels,
             switch_to_main,
+            merge,
+            no_push,
+            no_pull,
+            base,
         } => {
+            if base.is_some() {
+                config.git.base_branch = base;
+            }
+            config.git.no_push |= no_push;
+            config.git.no_pull |= no_pull;
             git_done_branch(
                 message,
                 no_pr,
@@ -351,6 +2416,7 @@ fn main() -> Result<()> {
                 reviewers,
                 labels,
                 switch_to_main,
+                &merge,
                 &config,
             )?;
         }
@@ -358,1584 +2424,14174 @@ fn main() -> Result<()> {
             git_status(&config)?;
         }
         Commands::Cleanup { yes } => {
-            cleanup_done_tasks(yes)?;
+            cleanup_done_tasks(yes, &config)?;
+        }
+        Commands::Undo { list } => {
+            if list {
+                list_undo_entries()?;
+            } else {
+                undo_last_change(&config)?;
+            }
         }
         Commands::ConfigInit { path } => {
             init_config_file(path)?;
         }
+        Commands::Exists { id } => {
+            let tasks = load_tasks(&config)?;
+            if !tasks.iter().any(|tf| tf.task.id == id) {
+                std::process::exit(1);
+            }
+        }
+        Commands::NextId { project } => {
+            println!("{}", get_next_task_id(project.as_deref(), &config)?);
+        }
+        Commands::Completions { shell } => {
+            print_completions(shell)?;
+        }
+        Commands::CompleteIds => {
+            print_completion_ids(&config)?;
+        }
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn list_tasks(
     status_filter: Option<String>,
     tag_filter: Option<String>,
     priority_filter: Option<String>,
+    assignee_filter: Option<String>,
+    sort: &str,
+    reverse: bool,
+    columns: &str,
+    show_waiting: bool,
+    overdue_only: bool,
+    stale_days: Option<i64>,
+    sum_estimates: bool,
+    group_by: Option<&str>,
+    dates: &DateRangeArgs,
+    paging: &PagingArgs,
+    config: &Config,
 ) -> Result<()> {
-    let tasks = load_tasks()?;
+    use std::io::IsTerminal;
+
+    let columns = select_list_columns(columns, config)?;
+    let now = local_now();
+    let today = now.date_naive();
+    let assignee_filter = assignee_filter.map(|a| resolve_assignee(&a)).transpose()?;
+    let in_date_range = dates.matcher(today)?;
+    let selected = |task: &Task| {
+        assignee_filter.as_ref().is_none_or(|assignee| {
+            task.assignee
+                .as_deref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(assignee))
+        }) && (!overdue_only || is_overdue(task, &now))
+            && in_date_range(task)
+    };
+    let touched = stale_days.map(|_| LastTouched::new(config));
+    let untouched = |task_file: &TaskFile| match (&touched, stale_days) {
+        (Some(touched), Some(days)) => {
+            !is_finished(&task_file.task) && touched.is_stale(task_file, days, today)
+        }
+        _ => true,
+    };
+    let (tasks, waiting): (Vec<_>, Vec<_>) = load_root_tasks(config)?
+        .into_iter()
+        .filter(|task_file| selected(&task_file.task) && untouched(task_file))
+        .partition(|task_file| show_waiting || !is_waiting(&task_file.task, today));
+    let tasks: Vec<_> = tasks.into_iter().map(with_checklist_status).collect();
 
     // Filter tasks
-    let filtered_tasks: Vec<_> = tasks
+    let mut filtered_tasks: Vec<_> = tasks
         .into_iter()
         .filter(|task_file| {
-            let task = &task_file.task;
-
-            // Status filter
-            if let Some(ref status) = status_filter {
-                if let Some(ref task_status) = task.status {
-                    if !task_status.to_lowercase().contains(&status.to_lowercase()) {
-                        return false;
-                    }
-                } else {
-                    return false;
-                }
-            }
-
-            // Tag filter
-            if let Some(ref tag) = tag_filter {
-                if let Some(ref tags) = task.tags {
-                    if !tags
-                        .iter()
-                        .any(|t| t.to_lowercase().contains(&tag.to_lowercase()))
-                    {
-                        return false;
-                    }
-                } else {
-                    return false;
-                }
-            }
+            task_matches_filters(
+                &task_file.task,
+                &status_filter,
+                &tag_filter,
+                &priority_filter,
+            )
+        })
+        .collect();
+    sort_task_files(&mut filtered_tasks, sort, reverse, config);
 
-            // Priority filter
-            if let Some(ref priority) = priority_filter {
-                if let Some(ref task_priority) = task.priority {
-                    if !task_priority
-                        .to_lowercase()
-                        .contains(&priority.to_lowercase())
-                    {
-                        return false;
-                    }
-                } else {
-                    return false;
-                }
-            }
+    // Overlay repositories' tasks follow the local ones
+    let mut overlay_tasks: Vec<_> = load_overlay_tasks(config)
+        .into_iter()
+        .filter(|task_file| selected(&task_file.task) && untouched(task_file))
+        .filter(|task_file| {
+            task_matches_filters(
+                &task_file.task,
+                &status_filter,
+                &tag_filter,
+                &priority_filter,
+            )
+        })
+        .collect();
+    sort_task_files(&mut overlay_tasks, sort, reverse, config);
 
-            true
+    // Virtual tasks from external sources are shown after local ones
+    // Virtual tasks have no file history to judge staleness by
+    let virtual_tasks: Vec<_> = load_virtual_tasks(config)
+        .into_iter()
+        .filter(|_| stale_days.is_none())
+        .filter(|virtual_task| selected(&virtual_task.as_task()))
+        .filter(|virtual_task| {
+            task_matches_filters(
+                &virtual_task.as_task(),
+                &status_filter,
+                &tag_filter,
+                &priority_filter,
+            )
         })
         .collect();
 
+    let hidden = waiting
+        .iter()
+        .filter(|task_file| {
+            task_matches_filters(
+                &task_file.task,
+                &status_filter,
+                &tag_filter,
+                &priority_filter,
+            )
+        })
+        .count();
+
     // Display tasks
-    if filtered_tasks.is_empty() {
+    if filtered_tasks.is_empty() && overlay_tasks.is_empty() && virtual_tasks.is_empty() {
         println!("No tasks found matching the criteria.");
+        if hidden > 0 {
+            println!(
+                "💤 {} snoozed task(s) hidden (use --all to show them)",
+                hidden
+            );
+        }
         return Ok(());
     }
 
-    println!(
-        "{:<4} {:<12} {:<8} {:<50}",
-        "ID", "STATUS", "PRIORITY", "TITLE"
-    );
-    println!("{}", "-".repeat(80));
+    // With task roots configured, each row starts with its root's name
+    let root_width = config
+        .roots
+        .keys()
+        .map(|name| name.chars().count().max(4))
+        .max();
+    let row_in = |root: &str, values: Vec<String>| {
+        let row = values
+            .iter()
+            .zip(&columns)
+            .map(|(value, column)| format!("{:<width$}", value, width = column.width()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        match root_width {
+            Some(width) => format!("{:<width$} {}", root, row),
+            None => row,
+        }
+    };
+    let row = |values: Vec<String>| row_in("", values);
+
+    let effort = sum_estimates.then(|| {
+        let tasks: Vec<&Task> = filtered_tasks.iter().map(|tf| &tf.task).collect();
+        effort_summary_lines(&tasks)
+    });
+
+    let mut virtual_tasks = virtual_tasks;
+    if let Some(top) = paging.top {
+        filtered_tasks.truncate(top);
+        overlay_tasks.truncate(top - filtered_tasks.len());
+        virtual_tasks.truncate(top - filtered_tasks.len() - overlay_tasks.len());
+    }
 
-    for task_file in filtered_tasks {
-        let task = &task_file.task;
-        let status = task.status.as_deref().unwrap_or("unknown");
-        let priority = task.priority.as_deref().unwrap_or("medium");
-        let title = &task.title;
+    // Overdue tasks are marked, and shown in red on a terminal
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let task_row = |task_file: &TaskFile| {
+        let line = row_in(
+            task_root_name(&task_file.file_path, config),
+            columns
+                .iter()
+                .map(|c| c.value(&task_file.task, &task_file.content))
+                .collect(),
+        );
+        if !is_overdue(&task_file.task, &now) {
+            line
+        } else if color {
+            format!("\x1b[31m{}  ⏰ overdue\x1b[0m", line.trim_end())
+        } else {
+            format!("{}  ⏰ overdue", line.trim_end())
+        }
+    };
 
-        println!(
-            "{:<4} {:<12} {:<8} {:<50}",
-            task.id, status, priority, title
+    let mut lines = vec![
+        row_in(
+            "ROOT",
+            columns.iter().map(|c| c.name().to_uppercase()).collect(),
+        ),
+        "-".repeat(80),
+    ];
+
+    // Each row is kept with its task so it can be grouped
+    let mut rows: Vec<(Task, String)> = filtered_tasks
+        .iter()
+        .chain(&overlay_tasks)
+        .map(|task_file| (task_file.task.clone(), task_row(task_file)))
+        .collect();
+    for virtual_task in &virtual_tasks {
+        let task = virtual_task.as_task();
+        let mut shown = task.clone();
+        shown.priority = Some("-".to_string());
+        rows.push((
+            task,
+            row(columns.iter().map(|c| c.value(&shown, "")).collect()),
+        ));
+    }
+    match group_by {
+        Some(field) => lines.extend(grouped_list_lines(field, rows, config)),
+        None => lines.extend(rows.into_iter().map(|(_, line)| line)),
+    }
+
+    if !virtual_tasks.is_empty() {
+        lines.push(String::new());
+        lines.push(
+            "☁️  Virtual tasks are read-only; run `mdtasks adopt <id>` to create a local task"
+                .to_string(),
         );
     }
+    if !overlay_tasks.is_empty() {
+        let prefixes: Vec<String> = config
+            .overlays
+            .iter()
+            .map(|o| format!("{}-* ({})", o.prefix, o.repo))
+            .collect();
+        lines.push(String::new());
+        lines.push(format!(
+            "🔗 Overlay tasks are read-only: {}",
+            prefixes.join(", ")
+        ));
+    }
+    if hidden > 0 {
+        lines.push(String::new());
+        lines.push(format!(
+            "💤 {} snoozed task(s) hidden (use --all to show them)",
+            hidden
+        ));
+    }
+    if let Some(effort) = effort {
+        lines.push(String::new());
+        lines.extend(effort);
+    }
 
-    Ok(())
+    print_paged(&lines, 2, paging)
 }
 
-fn show_task(id: String) -> Result<()> {
-    let tasks = load_tasks()?;
+/// Split listed rows into `--group-by` sections, each headed by its subtotal
+fn grouped_list_lines(field: &str, rows: Vec<(Task, String)>, config: &Config) -> Vec<String> {
+    const NONE: &str = "(none)";
+    let keys = |task: &Task| -> Vec<String> {
+        let value = match field {
+            "status" => task.status.clone(),
+            "project" => task.project.clone(),
+            "priority" => task.priority.as_ref().map(|p| p.to_lowercase()),
+            // A task with several tags appears under each of them
+            _ => {
+                let tags: Vec<String> = task.tags.iter().flatten().cloned().collect();
+                if !tags.is_empty() {
+                    return tags;
+                }
+                None
+            }
+        };
+        vec![value
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| NONE.to_string())]
+    };
 
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+    let mut groups: std::collections::BTreeMap<String, Vec<String>> =
+        std::collections::BTreeMap::new();
+    for (task, line) in rows {
+        for key in keys(&task) {
+            groups.entry(key).or_default().push(line.clone());
+        }
+    }
 
-    let task = &task_file.task;
+    // Statuses follow the workflow and priorities run high to low; other
+    // groups are alphabetical, with tasks lacking a value last
+    let status_order = config.workflow.status_order();
+    let rank = |key: &str| -> (u8, usize) {
+        if key == NONE {
+            return (2, 0);
+        }
+        let position = match field {
+            "status" => status_order.iter().position(|s| *s == key),
+            "priority" => Priority::parse(key).map(|p| p as usize),
+            _ => None,
+        };
+        match position {
+            Some(position) => (0, position),
+            None => (1, 0),
+        }
+    };
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|(a, _), (b, _)| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
 
-    println!("Task: {}", task.title);
-    println!("ID: {}", task.id);
-    println!("Status: {}", task.status.as_deref().unwrap_or("unknown"));
-    println!("Priority: {}", task.priority.as_deref().unwrap_or("medium"));
+    let mut lines = Vec::new();
+    for (name, rows) in groups {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("▸ {} ({})", name, rows.len()));
+        lines.extend(rows);
+    }
+    lines
+}
 
-    if let Some(ref tags) = task.tags {
-        println!("Tags: {}", tags.join(", "));
+/// When task files were last changed: the later of their `updated` (or
+/// `created`) date and their last git commit, or their modification time for
+/// files git does not track, since a fresh clone resets modification times
+struct LastTouched {
+    commits: std::collections::HashMap<PathBuf, chrono::NaiveDate>,
+}
+
+impl LastTouched {
+    fn new(config: &Config) -> Self {
+        let mut dirs = vec![config.tasks.dir.as_str()];
+        dirs.extend(config.roots.values().map(|dir| dir.as_str()));
+        LastTouched {
+            commits: git_last_commit_dates(&dirs),
+        }
     }
 
-    if let Some(ref project) = task.project {
-        println!("Project: {}", project);
+    fn date(&self, task_file: &TaskFile) -> Option<chrono::NaiveDate> {
+        let task = &task_file.task;
+        let edited = task
+            .updated
+            .as_ref()
+            .or(task.created.as_ref())
+            .and_then(|date| parse_task_date(date));
+        let path = Path::new(&task_file.file_path);
+        let changed = match path.canonicalize().ok().and_then(|p| self.commits.get(&p)) {
+            Some(date) => Some(*date),
+            None => std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(|modified| to_local_time(modified.into()).date_naive()),
+        };
+        edited.max(changed)
     }
 
-    if let Some(ref created) = task.created {
-        println!("Created: {}", created);
+    /// Days since the task was last touched, if that is known
+    fn days_since(&self, task_file: &TaskFile, today: chrono::NaiveDate) -> Option<i64> {
+        self.date(task_file).map(|date| (today - date).num_days())
     }
 
-    if let Some(ref due) = task.due {
-        println!("Due: {}", due);
+    fn is_stale(&self, task_file: &TaskFile, days: i64, today: chrono::NaiveDate) -> bool {
+        self.days_since(task_file, today)
+            .is_some_and(|age| age >= days)
     }
+}
 
-    println!("\nContent:");
-    println!("{}", task_file.content);
+/// Whether an open task is snoozed until after `today`
+fn is_waiting(task: &Task, today: chrono::NaiveDate) -> bool {
+    !is_finished(task)
+        && task
+            .wait_until
+            .as_deref()
+            .and_then(parse_task_date)
+            .is_some_and(|date| date > today)
+}
 
+fn snooze_task(id: String, until: &str, config: &Config) -> Result<()> {
+    let today = local_today();
+    let date = parse_due_shorthand(until, today).context(format!(
+        "Could not understand date '{}' (try YYYY-MM-DD, tomorrow, mon, or 3d)",
+        until
+    ))?;
+    set_task_field(
+        id.clone(),
+        "wait_until",
+        date.format("%Y-%m-%d").to_string(),
+        config,
+    )?;
+    if date > today {
+        println!(
+            "💤 Task {} is hidden from `list` until {}",
+            id,
+            date.format("%a %Y-%m-%d")
+        );
+    }
     Ok(())
 }
 
-fn load_tasks() -> Result<Vec<TaskFile>> {
+fn delegate_task(id: String, to: &str, follow_up: Option<&str>, config: &Config) -> Result<()> {
+    let today = local_today();
+    let follow_up = follow_up
+        .map(|when| {
+            parse_due_shorthand(when, today).context(format!(
+                "Could not understand date '{}' (try YYYY-MM-DD, tomorrow, fri, or 3d)",
+                when
+            ))
+        })
+        .transpose()?;
+
+    let _lock = lock_tasks(config)?;
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
     let matter = Matter::<gray_matter::engine::YAML>::new();
-    let mut tasks = Vec::new();
+    let parsed = matter.parse(&content);
+    let front_matter = parsed
+        .data
+        .context("Could not parse front-matter from task file")?;
+    let mut task = extract_task_from_pod(&front_matter)?;
+
+    task.status = Some("waiting".to_string());
+    task.delegated_to = Some(to.to_string());
+    task.follow_up = follow_up.map(|date| date.format("%Y-%m-%d").to_string());
+
+    let timestamp = local_now().format("%Y-%m-%dT%H:%M:%S%:z");
+    let entry = match &task.follow_up {
+        Some(date) => format!("- {} Delegated to {}, follow up {}", timestamp, to, date),
+        None => format!("- {} Delegated to {}", timestamp, to),
+    };
+    let mut new_content = rewrite_front_matter(&content, &task);
+    new_content.push_str(&append_to_section(&parsed.content, "Log", &entry));
+    write_task_file(&task_file.file_path, &new_content, config)?;
+
+    match follow_up {
+        Some(date) => println!(
+            "📨 Task {} is waiting for {}; follow up {}",
+            id,
+            to,
+            date.format("%a %Y-%m-%d")
+        ),
+        None => println!("📨 Task {} is waiting for {}", id, to),
+    }
+    Ok(())
+}
 
-    // Look for markdown files in tasks/ directory
-    let tasks_dir = Path::new("tasks");
-    if !tasks_dir.exists() {
-        return Ok(tasks);
+/// Resolve a `postpone` target: `+Nd`/`+Nw` shift the current due date,
+/// anything else is a date as understood by `parse_due_shorthand`
+fn postpone_target(
+    when: &str,
+    due: Option<chrono::NaiveDate>,
+    today: chrono::NaiveDate,
+) -> Option<chrono::NaiveDate> {
+    let when = when.trim().to_lowercase();
+    if let Some(shift) = when.strip_prefix('+') {
+        let from = due.unwrap_or(today);
+        return Some(from + (parse_due_shorthand(shift, from)? - from));
+    }
+    if let Some(day) = when.strip_prefix("next ") {
+        // "next friday" on a Friday means a week from today
+        let date = parse_due_shorthand(day.trim(), today)?;
+        return Some(if date == today {
+            date + chrono::Duration::weeks(1)
+        } else {
+            date
+        });
     }
+    parse_due_shorthand(&when, today)
+}
 
-    for entry in WalkDir::new(tasks_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-    {
-        let file_path = entry.path();
-        let content = std::fs::read_to_string(file_path)
-            .context(format!("Failed to read file: {}", file_path.display()))?;
+fn postpone_task(id: String, when: &str, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
 
-        let parsed = matter.parse(&content);
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+    let front_matter = parsed
+        .data
+        .context("Could not parse front-matter from task file")?;
+    let mut task = extract_task_from_pod(&front_matter)?;
+
+    let today = local_today();
+    let old_due = task.due.as_deref().and_then(parse_task_date);
+    let new_due = postpone_target(when, old_due, today).context(format!(
+        "Could not understand '{}' (try +3d, +1w, tomorrow, fri, next monday, or YYYY-MM-DD)",
+        when
+    ))?;
+
+    // A due time carries over to the new date
+    let time = task
+        .due
+        .as_deref()
+        .and_then(|due| due.get(10..))
+        .unwrap_or("");
+    task.due = Some(format!("{}{}", new_due.format("%Y-%m-%d"), time));
+    // Only a later date counts as a slip; pulling a date in does not
+    if old_due.is_some_and(|old| new_due > old) {
+        task.postponed = Some(task.postponed.unwrap_or(0) + 1);
+    }
 
-        if let Some(front_matter) = parsed.data {
-            // Try to extract fields manually from Pod
-            match extract_task_from_pod(&front_matter) {
-                Ok(task) => {
-                    tasks.push(TaskFile {
-                        task,
-                        file_path: file_path.to_string_lossy().to_string(),
-                        content: parsed.content,
-                    });
-                }
-                Err(_) => {
-                    // Skip files that don't have valid task data
+    let mut new_content = rewrite_front_matter(&content, &task);
+    new_content.push_str(&parsed.content);
+    write_task_file(&task_file.file_path, &new_content, config)?;
+
+    match task.postponed {
+        Some(count) if count > 1 => println!(
+            "⏭️  Postponed task {} to {} (postponed {} times)",
+            id,
+            new_due.format("%a %Y-%m-%d"),
+            count
+        ),
+        _ => println!(
+            "⏭️  Postponed task {} to {}",
+            id,
+            new_due.format("%a %Y-%m-%d")
+        ),
+    }
+    Ok(())
+}
+
+/// Print output lines, paging them per `--page-size`, or through $PAGER when
+/// writing to a terminal (like git, `less` quits right away if it fits)
+fn print_paged(lines: &[String], header_lines: usize, paging: &PagingArgs) -> Result<()> {
+    use std::io::{self, IsTerminal, Write};
+
+    let interactive = io::stdout().is_terminal();
+
+    if let Some(page_size) = paging.page_size.filter(|&n| n > 0) {
+        let (header, rows) = lines.split_at(header_lines.min(lines.len()));
+        for line in header {
+            println!("{}", line);
+        }
+        for (i, page) in rows.chunks(page_size).enumerate() {
+            // Without a terminal there is nobody to answer the prompt
+            if i > 0 && interactive && io::stdin().is_terminal() {
+                print!(
+                    "-- {} more, Enter for next page, q to quit -- ",
+                    rows.len() - i * page_size
+                );
+                io::stdout().flush()?;
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                if input.trim().eq_ignore_ascii_case("q") {
+                    return Ok(());
                 }
             }
+            for line in page {
+                println!("{}", line);
+            }
         }
+        return Ok(());
     }
 
-    // Sort by ID
-    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+    let pager = std::env::var("MDTASKS_PAGER")
+        .or_else(|_| std::env::var("PAGER"))
+        .unwrap_or_else(|_| "less".to_string());
+    if paging.no_pager || !interactive || cfg!(windows) || pager.is_empty() || pager == "cat" {
+        for line in lines {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
 
-    Ok(tasks)
-}
+    let mut command = std::process::Command::new("sh");
+    command
+        .args(["-c", &pager])
+        .stdin(std::process::Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(_) => {
+            for line in lines {
+                println!("{}", line);
+            }
+            return Ok(());
+        }
+    };
 
-fn extract_task_from_pod(pod: &gray_matter::Pod) -> Result<Task> {
-    use gray_matter::Pod;
+    if let Some(mut stdin) = child.stdin.take() {
+        for line in lines {
+            // The pager was closed early (e.g. q in less)
+            if writeln!(stdin, "{}", line).is_err() {
+                break;
+            }
+        }
+    }
+    child.wait().context("Failed to wait for pager")?;
 
-    let mut task = Task {
-        id: String::new(),
-        title: String::new(),
-        status: None,
-        priority: None,
-        tags: None,
-        project: None,
-        created: None,
-        due: None,
-        completed: None,
-        started: None,
+    Ok(())
+}
+
+/// Sort tasks for `list --sort`; tasks without a value for the sort field come
+/// last in either direction, and ties are broken by ID
+fn sort_task_files(tasks: &mut [TaskFile], sort: &str, reverse: bool, config: &Config) {
+    let today = local_today();
+
+    let time_key =
+        |value: Option<chrono::DateTime<chrono::FixedOffset>>| value.map(|time| time.timestamp());
+    // Statuses outside the workflow sort after the known ones
+    let status_order = config.workflow.status_order();
+    let status_rank = |status: Option<&str>| {
+        status
+            .and_then(|status| status_order.iter().position(|s| *s == status))
+            .unwrap_or(status_order.len()) as i64
+    };
+    let key = |task: &Task| -> Option<i64> {
+        match sort {
+            "due" => time_key(task.due.as_deref().and_then(due_deadline)),
+            "created" => time_key(task.created.as_deref().and_then(parse_task_datetime)),
+            "priority" => Some(priority_rank(task.priority.as_deref()) as i64),
+            "status" => Some(status_rank(task.status.as_deref())),
+            // Most urgent first
+            "urgency" => Some(-(urgency_score(task, &config.urgency, today).0 * 100.0) as i64),
+            _ => None,
+        }
     };
 
-    if let Pod::Hash(hash) = pod {
-        for (key, value) in hash {
-            match key.as_str() {
-                "id" => match value {
-                    Pod::String(s) => task.id = s.clone(),
-                    Pod::Integer(i) => task.id = i.to_string(),
-                    _ => {}
-                },
-                "title" => {
-                    if let Pod::String(s) = value {
-                        task.title = s.clone();
-                    }
-                }
-                "status" => {
-                    if let Pod::String(s) = value {
-                        task.status = Some(s.clone());
-                    }
-                }
-                "priority" => {
-                    if let Pod::String(s) = value {
-                        task.priority = Some(s.clone());
-                    }
-                }
-                "tags" => {
-                    if let Pod::Array(arr) = value {
-                        let mut tags = Vec::new();
-                        for item in arr {
-                            if let Pod::String(s) = item {
-                                tags.push(s.clone());
-                            }
-                        }
-                        task.tags = Some(tags);
-                    }
-                }
-                "project" => {
-                    if let Pod::String(s) = value {
-                        task.project = Some(s.clone());
-                    }
-                }
-                "created" => {
-                    if let Pod::String(s) = value {
-                        task.created = Some(s.clone());
-                    }
-                }
-                "due" => {
-                    if let Pod::String(s) = value {
-                        task.due = Some(s.clone());
-                    }
-                }
-                _ => {}
+    tasks.sort_by(|a, b| {
+        let (a, b) = (&a.task, &b.task);
+        let ordering = match (key(a), key(b)) {
+            (Some(x), Some(y)) if reverse => y.cmp(&x),
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        ordering.then_with(|| {
+            if sort == "id" && reverse {
+                b.id.cmp(&a.id)
+            } else {
+                a.id.cmp(&b.id)
+            }
+        })
+    });
+}
+
+/// Check whether a task matches the status, tag, and priority filters
+fn task_matches_filters(
+    task: &Task,
+    status_filter: &Option<String>,
+    tag_filter: &Option<String>,
+    priority_filter: &Option<String>,
+) -> bool {
+    // Status filter
+    if let Some(ref status) = status_filter {
+        if let Some(ref task_status) = task.status {
+            if !task_status.to_lowercase().contains(&status.to_lowercase()) {
+                return false;
             }
+        } else {
+            return false;
         }
     }
 
-    if task.id.is_empty() || task.title.is_empty() {
-        return Err(anyhow::anyhow!("Missing required fields: id or title"));
+    // Tag filter
+    if let Some(ref tag) = tag_filter {
+        if let Some(ref tags) = task.tags {
+            if !tags
+                .iter()
+                .any(|t| t.to_lowercase().contains(&tag.to_lowercase()))
+            {
+                return false;
+            }
+        } else {
+            return false;
+        }
     }
 
-    Ok(task)
+    // Priority filter
+    if let Some(ref priority) = priority_filter {
+        if let Some(ref task_priority) = task.priority {
+            if !task_priority
+                .to_lowercase()
+                .contains(&priority.to_lowercase())
+            {
+                return false;
+            }
+        } else {
+            return false;
+        }
+    }
+
+    true
 }
 
-fn add_task(
-    title: String,
-    priority: Option<String>,
-    status: Option<String>,
-    tags: Option<Vec<String>>,
-    project: Option<String>,
-    due: Option<String>,
-    notes: Option<String>,
-) -> Result<()> {
-    // Generate next ID
-    let next_id = get_next_task_id()?;
+/// Parse a task date (YYYY-MM-DD, optionally followed by a time)
+fn parse_task_date(value: &str) -> Option<chrono::NaiveDate> {
+    let date_part = value.get(..10).unwrap_or(value);
+    chrono::NaiveDate::parse_from_str(date_part, "%Y-%m-%d").ok()
+}
 
-    // Create task struct
-    let task = Task {
-        id: next_id.clone(),
-        title: title.clone(),
-        status: status.or(Some("pending".to_string())),
-        priority: priority.or(Some("medium".to_string())),
-        tags,
-        project,
-        created: Some(chrono::Utc::now().format("%Y-%m-%d").to_string()),
-        due,
-        completed: None,
-        started: None,
-    };
+/// Parse a task date, a local "YYYY-MM-DD HH:MM", or an RFC 3339 timestamp;
+/// bare dates count as local midnight
+fn parse_task_datetime(value: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let value = value.trim();
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp);
+    }
+    ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| parse_task_date(value)?.and_hms_opt(0, 0, 0))
+        .and_then(from_local_time)
+}
 
-    // Create markdown content
-    let mut content = String::new();
+/// When a due date passes: at its time if it has one, else at the end of the day
+fn due_deadline(due: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    if due.trim().len() > 10 {
+        return parse_task_datetime(due);
+    }
+    let next_day = parse_task_date(due)?.succ_opt()?;
+    from_local_time(next_day.and_hms_opt(0, 0, 0)?)
+}
 
-    // Add front-matter
-    content.push_str("---\n");
-    content.push_str(&format!("id: {}\n", task.id));
-    content.push_str(&format!("title: \"{}\"\n", task.title));
+/// Whether an open task's due date or time has passed
+fn is_overdue(task: &Task, now: &chrono::DateTime<chrono::FixedOffset>) -> bool {
+    !is_finished(task)
+        && task
+            .due
+            .as_deref()
+            .and_then(due_deadline)
+            .is_some_and(|deadline| deadline <= *now)
+}
 
-    if let Some(ref status) = task.status {
-        content.push_str(&format!("status: {}\n", status));
-    }
+/// Resolve a due date with an optional time ("fri 17:00", "2024-06-01T09:30")
+/// to its front-matter form, "YYYY-MM-DD" or "YYYY-MM-DD HH:MM"
+fn parse_due_input(value: &str, today: chrono::NaiveDate) -> Option<String> {
+    let value = value.trim();
+    let (day, time) = match value.rsplit_once([' ', 'T']) {
+        Some((day, time)) if time.contains(':') => (
+            day.trim(),
+            Some(chrono::NaiveTime::parse_from_str(time, "%H:%M").ok()?),
+        ),
+        _ => (value, None),
+    };
+    let date = parse_due_shorthand(day, today)?.format("%Y-%m-%d");
+    Some(match time {
+        Some(time) => format!("{} {}", date, time.format("%H:%M")),
+        None => date.to_string(),
+    })
+}
 
-    if let Some(ref priority) = task.priority {
-        content.push_str(&format!("priority: {}\n", priority));
+/// The current date or timestamp for `created`/`started`/`completed`, per `tasks.timestamps`
+fn task_timestamp(config: &Config) -> String {
+    let now = local_now();
+    if config.tasks.timestamps == "rfc3339" {
+        now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    } else {
+        now.format("%Y-%m-%d").to_string()
     }
+}
 
-    if let Some(ref tags) = task.tags {
-        content.push_str("tags: [");
-        for (i, tag) in tags.iter().enumerate() {
-            if i > 0 {
-                content.push_str(", ");
-            }
-            content.push_str(&format!("\"{}\"", tag));
+/// Parse `done --at`: a date, a local "YYYY-MM-DD HH:MM", or an RFC 3339 timestamp
+fn parse_completed_at(value: &str) -> Result<String> {
+    let value = value.trim();
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(value, format) {
+            let local = from_local_time(naive).ok_or_else(|| {
+                anyhow::anyhow!("'{}' does not exist in the local timezone", value)
+            })?;
+            return Ok(local.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
         }
-        content.push_str("]\n");
     }
+    let today = local_today();
+    parse_due_shorthand(value, today)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid completion time '{}': use YYYY-MM-DD, \"YYYY-MM-DD HH:MM\", or RFC 3339",
+                value
+            )
+        })
+}
 
-    if let Some(ref project) = task.project {
-        content.push_str(&format!("project: {}\n", project));
-    }
+fn show_priority_matrix(urgent_days: i64, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let today = local_today();
 
-    if let Some(ref created) = task.created {
-        content.push_str(&format!("created: {}\n", created));
-    }
+    // Quadrants: do first, schedule, delegate, eliminate
+    let mut quadrants: [Vec<String>; 4] = Default::default();
 
-    if let Some(ref due) = task.due {
-        content.push_str(&format!("due: {}\n", due));
-    }
+    for task_file in &tasks {
+        let task = &task_file.task;
+        if task.status.as_deref() == Some("done") {
+            continue;
+        }
 
-    content.push_str("---\n\n");
+        let due = task.due.as_deref().and_then(parse_task_date);
+        let urgent = due.is_some_and(|d| (d - today).num_days() <= urgent_days);
+        let important = task.priority.as_deref() == Some("high");
 
-    // Add markdown content
-    content.push_str("# Task Details\n\n");
+        let mut entry = format!("{} {}", task.id, task.title);
+        if let Some(due) = due {
+            entry.push_str(&format!(" ({})", due.format("%m-%d")));
+        }
 
-    if let Some(ref notes) = notes {
-        content.push_str("## Notes\n");
-        content.push_str(&format!("{}\n\n", notes));
+        let quadrant = match (urgent, important) {
+            (true, true) => 0,
+            (false, true) => 1,
+            (true, false) => 2,
+            (false, false) => 3,
+        };
+        quadrants[quadrant].push(entry);
     }
 
-    content.push_str("## Subtasks\n");
-    content.push('\n');
-
-    // Create filename
-    let filename = format!(
-        "tasks/{}-{}.md",
-        next_id,
-        title
-            .to_lowercase()
-            .replace(" ", "-")
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '-')
-            .collect::<String>()
+    const WIDTH: usize = 38;
+    let cell = |text: &str| -> String {
+        let truncated: String = text.chars().take(WIDTH).collect();
+        format!("{:<width$}", truncated, width = WIDTH)
+    };
+    let separator = format!(
+        "{}+{}+{}+",
+        " ".repeat(14),
+        "-".repeat(WIDTH + 2),
+        "-".repeat(WIDTH + 2)
     );
 
-    // Ensure tasks directory exists
-    std::fs::create_dir_all("tasks")?;
-
-    // Write file
-    std::fs::write(&filename, content)
-        .context(format!("Failed to write task file: {}", filename))?;
-
-    println!("✅ Created task {}: {}", next_id, title);
-    println!("📁 File: {}", filename);
+    println!(
+        "{:<14}  {}   {}",
+        "",
+        cell(&format!("URGENT (due ≤ {}d)", urgent_days)),
+        cell("NOT URGENT")
+    );
+    println!("{}", separator);
+
+    for (row, label, titles) in [
+        (0, "IMPORTANT", ["DO FIRST", "SCHEDULE"]),
+        (2, "NOT IMPORTANT", ["DELEGATE", "ELIMINATE"]),
+    ] {
+        let left = &quadrants[row];
+        let right = &quadrants[row + 1];
+        let height = left.len().max(right.len()).max(1);
+
+        println!("{:<14}| {} | {} |", label, cell(titles[0]), cell(titles[1]));
+        for i in 0..height {
+            println!(
+                "{:<14}| {} | {} |",
+                "",
+                cell(left.get(i).map(|s| s.as_str()).unwrap_or("")),
+                cell(right.get(i).map(|s| s.as_str()).unwrap_or(""))
+            );
+        }
+        println!("{}", separator);
+    }
 
     Ok(())
 }
 
-fn get_next_task_id() -> Result<String> {
-    let tasks = load_tasks()?;
+/// Parse an estimate such as "2h", "30m", "1.5" (hours) into hours
+fn parse_estimate_hours(value: &str) -> Option<f64> {
+    let value = value.trim().to_lowercase();
+    if let Some(minutes) = value.strip_suffix('m') {
+        return minutes.trim().parse::<f64>().ok().map(|m| m / 60.0);
+    }
+    if let Some(days) = value.strip_suffix('d') {
+        return days.trim().parse::<f64>().ok().map(|d| d * 8.0);
+    }
+    value.trim_end_matches('h').trim().parse().ok()
+}
+
+fn priority_rank(priority: Option<&str>) -> Priority {
+    priority
+        .and_then(Priority::parse)
+        .unwrap_or(Priority::Medium)
+}
 
-    let mut max_id = 0;
-    for task_file in tasks {
-        if let Ok(id_num) = task_file.task.id.parse::<u32>() {
-            max_id = max_id.max(id_num);
+/// Parse a selection such as "1,3,5-7" into 1-based indexes
+fn parse_selection(input: &str, max: usize) -> Result<Vec<usize>> {
+    let mut indexes = Vec::new();
+    for part in input.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim().parse::<usize>()?, end.trim().parse::<usize>()?),
+            None => {
+                let index = part.parse::<usize>()?;
+                (index, index)
+            }
+        };
+        if start == 0 || end > max || start > end {
+            return Err(anyhow::anyhow!("Selection out of range: {}", part));
         }
+        indexes.extend(start..=end);
     }
-
-    Ok(format!("{:03}", max_id + 1))
+    Ok(indexes)
 }
 
-fn mark_task_done(id: String) -> Result<()> {
-    // Find the task file
-    let tasks = load_tasks()?;
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+fn plan_week(ids: Vec<String>, config: &Config) -> Result<()> {
+    use chrono::Datelike;
 
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
+    let today = local_today();
+    let weekday = today.weekday().num_days_from_monday() as i64;
+    // On weekends the coming week is planned
+    let monday = if weekday >= 5 {
+        today + chrono::Duration::days(7 - weekday)
+    } else {
+        today - chrono::Duration::days(weekday)
+    };
+    let days: Vec<chrono::NaiveDate> = (0..5).map(|i| monday + chrono::Duration::days(i)).collect();
+    let friday = days[4];
 
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
+    let tasks = load_tasks(config)?;
+    let open: Vec<&TaskFile> = tasks
+        .iter()
+        .filter(|tf| tf.task.status.as_deref() != Some("done"))
+        .collect();
 
-    if let Some(front_matter) = parsed.data {
-        // Extract the task data
-        let mut task = extract_task_from_pod(&front_matter)?;
+    // Unfinished tasks scheduled up to the end of this week are re-planned by default
+    let is_replannable = |tf: &&TaskFile| {
+        tf.task
+            .scheduled
+            .as_deref()
+            .and_then(parse_task_date)
+            .is_some_and(|d| d <= friday)
+    };
 
-        // Update the status to "done"
-        task.status = Some("done".to_string());
+    let selected: Vec<&TaskFile> = if !ids.is_empty() {
+        let mut selected = Vec::new();
+        for id in &ids {
+            let task_file = open
+                .iter()
+                .find(|tf| same_task_id(&tf.task.id, id))
+                .context(format!("Open task with ID '{}' not found", id))?;
+            selected.push(*task_file);
+        }
+        selected
+    } else {
+        if open.is_empty() {
+            println!("No open tasks to plan.");
+            return Ok(());
+        }
 
-        // Rebuild the file content
-        let mut new_content = String::new();
+        println!("📋 Open tasks:");
+        for (i, tf) in open.iter().enumerate() {
+            let mut details = Vec::new();
+            if let Some(ref estimate) = tf.task.estimate {
+                details.push(format!("est {}", estimate));
+            }
+            if let Some(ref scheduled) = tf.task.scheduled {
+                details.push(format!("scheduled {}", scheduled));
+            }
+            if let Some(ref due) = tf.task.due {
+                details.push(format!("due {}", due));
+            }
+            println!(
+                "  {:>2}. {} {}{}",
+                i + 1,
+                tf.task.id,
+                tf.task.title,
+                if details.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", details.join(", "))
+                }
+            );
+        }
 
-        // Add updated front-matter
-        new_content.push_str("---\n");
-        new_content.push_str(&format!("id: {}\n", task.id));
-        new_content.push_str(&format!("title: \"{}\"\n", task.title));
+        print!("❓ Tasks to plan (e.g. 1,3,5-7, 'all', Enter to re-plan this week's unfinished tasks): ");
+        use std::io::{self, Write};
+        io::stdout().flush()?;
 
-        if let Some(ref status) = task.status {
-            new_content.push_str(&format!("status: {}\n", status));
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
+
+        match input {
+            "" => open.iter().copied().filter(is_replannable).collect(),
+            "all" => open.clone(),
+            _ => parse_selection(input, open.len())?
+                .into_iter()
+                .map(|i| open[i - 1])
+                .collect(),
         }
+    };
+
+    if selected.is_empty() {
+        println!("Nothing selected to plan.");
+        return Ok(());
+    }
+
+    let estimate_of = |task: &Task| {
+        task.estimate
+            .as_deref()
+            .and_then(parse_estimate_hours)
+            .unwrap_or(config.planning.default_estimate_hours)
+    };
 
-        if let Some(ref priority) = task.priority {
-            new_content.push_str(&format!("priority: {}\n", priority));
+    // Tasks staying where they are still use up their day's capacity
+    let mut remaining = [config.planning.day_capacity_hours; 5];
+    for tf in &open {
+        if selected.iter().any(|s| s.task.id == tf.task.id) {
+            continue;
+        }
+        if let Some(date) = tf.task.scheduled.as_deref().and_then(parse_task_date) {
+            if let Some(i) = days.iter().position(|d| *d == date) {
+                remaining[i] -= estimate_of(&tf.task);
+            }
         }
+    }
 
-        if let Some(ref tags) = task.tags {
-            new_content.push_str("tags: [");
-            for (i, tag) in tags.iter().enumerate() {
-                if i > 0 {
-                    new_content.push_str(", ");
+    let mut ordered = selected;
+    ordered.sort_by(|a, b| {
+        priority_rank(a.task.priority.as_deref())
+            .cmp(&priority_rank(b.task.priority.as_deref()))
+            .then_with(|| {
+                let due_a = a.task.due.as_deref().and_then(parse_task_date);
+                let due_b = b.task.due.as_deref().and_then(parse_task_date);
+                due_a
+                    .unwrap_or(chrono::NaiveDate::MAX)
+                    .cmp(&due_b.unwrap_or(chrono::NaiveDate::MAX))
+            })
+            .then_with(|| a.task.id.cmp(&b.task.id))
+    });
+
+    let lock = lock_tasks(config)?;
+    let mut unplaced = Vec::new();
+    for tf in ordered {
+        let estimate = estimate_of(&tf.task);
+        // Past days of the week are not planned into
+        let slot = (0..5).find(|&i| days[i] >= today && remaining[i] >= estimate);
+        match slot {
+            Some(i) => {
+                remaining[i] -= estimate;
+                let date = days[i].format("%Y-%m-%d").to_string();
+                if tf.task.scheduled.as_deref() != Some(date.as_str()) {
+                    set_task_field(tf.task.id.clone(), "scheduled", date, config)?;
                 }
-                new_content.push_str(&format!("\"{}\"", tag));
             }
-            new_content.push_str("]\n");
+            None => unplaced.push(tf),
         }
+    }
 
-        if let Some(ref project) = task.project {
-            new_content.push_str(&format!("project: {}\n", project));
-        }
+    drop(lock);
+
+    // Print the resulting agenda from the updated files
+    let tasks = load_tasks(config)?;
+    println!("\n📅 Week of {}", monday.format("%Y-%m-%d"));
+    for day in &days {
+        let scheduled: Vec<&TaskFile> = tasks
+            .iter()
+            .filter(|tf| tf.task.scheduled.as_deref().and_then(parse_task_date) == Some(*day))
+            .collect();
+        let planned: f64 = scheduled
+            .iter()
+            .filter(|tf| tf.task.status.as_deref() != Some("done"))
+            .map(|tf| estimate_of(&tf.task))
+            .fold(0.0, |total, hours| total + hours);
 
-        if let Some(ref created) = task.created {
-            new_content.push_str(&format!("created: {}\n", created));
+        println!(
+            "\n{} ({:.1}h / {:.1}h)",
+            day.format("%a %Y-%m-%d"),
+            planned,
+            config.planning.day_capacity_hours
+        );
+        if scheduled.is_empty() {
+            println!("  -");
         }
-
-        if let Some(ref due) = task.due {
-            new_content.push_str(&format!("due: {}\n", due));
+        for tf in scheduled {
+            let marker = if tf.task.status.as_deref() == Some("done") {
+                "✅"
+            } else {
+                "•"
+            };
+            println!(
+                "  {} {} {} ({:.1}h)",
+                marker,
+                tf.task.id,
+                tf.task.title,
+                estimate_of(&tf.task)
+            );
         }
+    }
 
-        // Add completed date
-        new_content.push_str(&format!(
-            "completed: {}\n",
-            chrono::Utc::now().format("%Y-%m-%d")
-        ));
-
-        new_content.push_str("---\n\n");
-
-        // Process the markdown content to mark all checklist items as complete
-        let processed_content = mark_all_subtasks_complete(&parsed.content);
-        new_content.push_str(&processed_content);
-
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
-        ))?;
-
-        println!("✅ Marked task {} as done: {}", id, task.title);
-    } else {
-        return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
-        ));
+    if !unplaced.is_empty() {
+        println!("\n⚠️  Did not fit into the remaining capacity this week:");
+        for tf in unplaced {
+            println!(
+                "  - {} {} ({:.1}h)",
+                tf.task.id,
+                tf.task.title,
+                estimate_of(&tf.task)
+            );
+        }
     }
 
     Ok(())
 }
 
-fn mark_task_start(id: String) -> Result<()> {
-    // Find the task file
-    let tasks = load_tasks()?;
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
-
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
-
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
-
-    if let Some(front_matter) = parsed.data {
-        // Extract the task data
-        let mut task = extract_task_from_pod(&front_matter)?;
-
-        // Update the status to "active"
-        task.status = Some("active".to_string());
-
-        // Rebuild the file content
-        let mut new_content = String::new();
+/// Read one trimmed line of input after printing `question`
+fn ask(question: &str) -> Result<String> {
+    use std::io::{self, Write};
 
-        // Add updated front-matter
-        new_content.push_str("---\n");
-        new_content.push_str(&format!("id: {}\n", task.id));
-        new_content.push_str(&format!("title: \"{}\"\n", task.title));
-
-        if let Some(ref status) = task.status {
-            new_content.push_str(&format!("status: {}\n", status));
-        }
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
 
-        if let Some(ref priority) = task.priority {
-            new_content.push_str(&format!("priority: {}\n", priority));
+/// Weekly review: offer quick actions on each overdue, stale, or untagged open task
+fn weekly_review(stale_days: i64, config: &Config) -> Result<()> {
+    let interactive = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    let now = local_now();
+    let today = now.date_naive();
+
+    let tasks = load_tasks(config)?;
+    let touched = LastTouched::new(config);
+    let mut queue: Vec<(&Task, Vec<&str>)> = tasks
+        .iter()
+        .filter(|tf| !is_finished(&tf.task) && !is_waiting(&tf.task, today))
+        .map(|tf| {
+            let task = &tf.task;
+            let mut reasons = Vec::new();
+            if is_overdue(task, &now) {
+                reasons.push("overdue");
+            }
+            if touched.is_stale(tf, stale_days, today) {
+                reasons.push("stale");
+            }
+            if task.tags.as_ref().is_none_or(|tags| tags.is_empty()) {
+                reasons.push("untagged");
+            }
+            (task, reasons)
+        })
+        .filter(|(_, reasons)| !reasons.is_empty())
+        .collect();
+    if queue.is_empty() {
+        println!("🎉 Nothing to review: no overdue, stale, or untagged tasks");
+        return Ok(());
+    }
+    // Overdue first, then stale, then untagged
+    queue.sort_by_key(|(task, reasons)| {
+        let rank = ["overdue", "stale", "untagged"]
+            .iter()
+            .position(|reason| reasons.contains(reason));
+        (rank, priority_rank(task.priority.as_deref()))
+    });
+
+    println!("🔎 Weekly review: {} task(s)", queue.len());
+    let mut actions: std::collections::BTreeMap<&str, Vec<String>> =
+        std::collections::BTreeMap::new();
+    for (i, (task, reasons)) in queue.iter().enumerate() {
+        let due = task
+            .due
+            .as_deref()
+            .map(|due| format!(", due {}", due))
+            .unwrap_or_default();
+        println!(
+            "\n[{}/{}] {}: {} ({}{}) — {}",
+            i + 1,
+            queue.len(),
+            task.id,
+            task.title,
+            task.priority.as_deref().unwrap_or("medium"),
+            due,
+            reasons.join(", ")
+        );
+        if !interactive {
+            continue;
         }
 
-        if let Some(ref tags) = task.tags {
-            new_content.push_str("tags: [");
-            for (i, tag) in tags.iter().enumerate() {
-                if i > 0 {
-                    new_content.push_str(", ");
+        let id = task.id.clone();
+        let action = loop {
+            let answer =
+                ask("    [p]ostpone, [r]eprioritize, [d]one, [a]rchive, [s]kip, [q]uit? ")?;
+            match answer.to_lowercase().as_str() {
+                "p" => {
+                    let when = ask("    Postpone to (+1w, fri, YYYY-MM-DD): ")?;
+                    if when.is_empty() {
+                        continue;
+                    }
+                    match postpone_task(id.clone(), &when, config) {
+                        Ok(()) => break "postponed",
+                        Err(e) => println!("    ⚠️  {:#}", e),
+                    }
+                }
+                "r" => {
+                    let priority = ask("    New priority (low, medium, high): ")?;
+                    match set_task_field(id.clone(), "priority", priority.to_lowercase(), config) {
+                        Ok(()) => break "reprioritized",
+                        Err(e) => println!("    ⚠️  {:#}", e),
+                    }
+                }
+                "d" => {
+                    mark_task_done(id.clone(), None, config)?;
+                    break "done";
                 }
-                new_content.push_str(&format!("\"{}\"", tag));
+                "a" => {
+                    set_task_field(id.clone(), "status", "archived".to_string(), config)?;
+                    break "archived";
+                }
+                "s" | "" => break "skipped",
+                "q" => break "quit",
+                _ => {}
             }
-            new_content.push_str("]\n");
+        };
+        if action == "quit" {
+            break;
         }
+        actions.entry(action).or_default().push(id);
+    }
 
-        if let Some(ref project) = task.project {
-            new_content.push_str(&format!("project: {}\n", project));
+    if interactive {
+        println!("\n📊 Review summary");
+        for (action, ids) in &actions {
+            println!("  {}: {} ({})", action, ids.len(), ids.join(", "));
         }
+    }
+    Ok(())
+}
 
-        if let Some(ref created) = task.created {
-            new_content.push_str(&format!("created: {}\n", created));
-        }
+/// End-of-day review: leave notes on active tasks, optionally pause them,
+/// confirm tomorrow's scheduled tasks, and summarize the day
+fn shutdown_ritual(config: &Config) -> Result<()> {
+    let interactive = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    let today = local_today();
+    let tomorrow = today + chrono::Duration::days(1);
 
-        if let Some(ref due) = task.due {
-            new_content.push_str(&format!("due: {}\n", due));
-        }
+    let tasks = load_tasks(config)?;
+    let on = |date: &Option<String>, day: chrono::NaiveDate| {
+        date.as_deref().and_then(parse_task_date) == Some(day)
+    };
+    let completed: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| is_finished(task) && on(&task.completed, today))
+        .collect();
+    let active: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| task.status.as_deref() == Some("active"))
+        .collect();
+    let upcoming: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| !is_finished(task) && on(&task.scheduled, tomorrow))
+        .collect();
 
-        // Add started date
-        new_content.push_str(&format!(
-            "started: {}\n",
-            chrono::Utc::now().format("%Y-%m-%d")
-        ));
+    // 1. Active tasks: where did I leave off, and keep them active?
+    let mut notes = 0;
+    let mut paused = Vec::new();
+    if !active.is_empty() {
+        println!("🌇 Active tasks");
+        for task in &active {
+            println!("  {}: {}", task.id, task.title);
+            if !interactive {
+                continue;
+            }
+            let note = ask("    📝 Where did you leave off (Enter to skip)? ")?;
+            if !note.is_empty() {
+                add_task_comment(task.id.clone(), format!("Left off: {}", note), None, config)?;
+                notes += 1;
+            }
+            let answer = ask("    ⏸️  Keep it active for tomorrow? [Y/n] ")?;
+            if answer.eq_ignore_ascii_case("n") {
+                set_task_field(task.id.clone(), "status", "partial".to_string(), config)?;
+                paused.push(task.id.clone());
+            }
+        }
+        println!();
+    }
 
-        new_content.push_str("---\n\n");
+    // 2. Tomorrow's scheduled tasks: keep them or move them to another day
+    let mut moved = Vec::new();
+    if !upcoming.is_empty() {
+        println!(
+            "📅 Scheduled for tomorrow ({})",
+            tomorrow.format("%a %Y-%m-%d")
+        );
+        for task in &upcoming {
+            println!("  {}: {}", task.id, task.title);
+            if !interactive {
+                continue;
+            }
+            let answer = ask("    Keep (Enter) or move to date (YYYY-MM-DD)? ")?;
+            if answer.is_empty() {
+                continue;
+            }
+            match chrono::NaiveDate::parse_from_str(&answer, "%Y-%m-%d") {
+                Ok(date) => {
+                    let date = date.format("%Y-%m-%d").to_string();
+                    set_task_field(task.id.clone(), "scheduled", date.clone(), config)?;
+                    moved.push((task.id.clone(), date));
+                }
+                Err(_) => println!("    ⚠️  Not a date, keeping it for tomorrow"),
+            }
+        }
+        println!();
+    }
 
-        // Add the original markdown content
-        new_content.push_str(&parsed.content);
+    // 3. Summary
+    println!("📊 Shutdown summary for {}", today.format("%a %Y-%m-%d"));
+    println!("  ✅ Completed today: {}", completed.len());
+    for task in &completed {
+        println!("     {}: {}", task.id, task.title);
+    }
+    println!("  🚀 Still active: {}", active.len() - paused.len());
+    if !paused.is_empty() {
+        println!("  ⏸️  Paused: {}", paused.join(", "));
+    }
+    if notes > 0 {
+        println!("  📝 Notes left: {}", notes);
+    }
+    println!(
+        "  📅 Tomorrow: {} scheduled task(s)",
+        upcoming.len() - moved.len()
+    );
+    for (id, date) in &moved {
+        println!("     {} moved to {}", id, date);
+    }
+    println!("🌙 Shutdown complete");
 
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
-        ))?;
+    Ok(())
+}
 
-        println!("🚀 Started task {}: {}", id, task.title);
-    } else {
-        return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
-        ));
+/// Overdue and active tasks followed by what is due or scheduled on each of
+/// the next `days` days, most important first
+fn show_agenda(days: i64, config: &Config) -> Result<()> {
+    let today = local_today();
+    let tasks = load_tasks(config)?;
+
+    let mut open: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| !is_finished(task))
+        .collect();
+    open.sort_by_key(|task| {
+        (
+            priority_rank(task.priority.as_deref()),
+            task.due.as_deref().and_then(parse_task_date),
+            task.id.clone(),
+        )
+    });
+
+    let due = |task: &Task| task.due.as_deref().and_then(parse_task_date);
+    let scheduled = |task: &Task| task.scheduled.as_deref().and_then(parse_task_date);
+    let line = |task: &Task| {
+        let mut details = vec![task
+            .priority
+            .clone()
+            .unwrap_or_else(|| "medium".to_string())];
+        if let Some(date) = due(task) {
+            match (date - today).num_days() {
+                d if d < 0 => details.push(format!("due {} ({}d overdue)", date, -d)),
+                0 => match task.due.as_deref().and_then(|due| due.get(11..)) {
+                    Some(time) => details.push(format!("due today {}", time)),
+                    None => details.push("due today".to_string()),
+                },
+                _ => details.push(format!("due {}", task.due.as_deref().unwrap_or_default())),
+            }
+        }
+        format!("  {}: {} [{}]", task.id, task.title, details.join(", "))
+    };
+
+    // Each task is shown once, in the first section it belongs to
+    let mut shown = std::collections::HashSet::new();
+    let mut section = |title: String, matches: &dyn Fn(&Task) -> bool| {
+        let items: Vec<&&Task> = open
+            .iter()
+            .filter(|task| !shown.contains(&task.id) && matches(task))
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+        println!("{}", title);
+        for task in items {
+            println!("{}", line(task));
+            shown.insert(task.id.clone());
+        }
+        println!();
+    };
+
+    let now = local_now();
+    section("⚠️  Overdue".to_string(), &|task| {
+        is_overdue(task, &now)
+    });
+    section("🚀 Active".to_string(), &|task| {
+        task.status.as_deref() == Some("active")
+    });
+    for offset in 0..days.max(1) {
+        let day = today + chrono::Duration::days(offset);
+        let title = match offset {
+            0 => format!("📅 Today ({})", day.format("%a %Y-%m-%d")),
+            1 => format!("📅 Tomorrow ({})", day.format("%a %Y-%m-%d")),
+            _ => format!("📅 {}", day.format("%a %Y-%m-%d")),
+        };
+        section(title, &|task| {
+            due(task) == Some(day) || scheduled(task) == Some(day)
+        });
+    }
+
+    if shown.is_empty() {
+        println!(
+            "🎉 Nothing overdue, active, or due in the next {} day(s)",
+            days.max(1)
+        );
     }
 
     Ok(())
 }
 
-fn complete_subtask(id: String, index: usize) -> Result<()> {
-    toggle_subtask_status(id, index, true)
-}
+/// Suggest the open, unblocked task with the highest urgency
+fn suggest_next_task(config: &Config) -> Result<()> {
+    let today = local_today();
+    let tasks = load_tasks(config)?;
+
+    // Tasks waiting on unfinished dependencies can't be picked up yet
+    let is_blocked = |task: &Task| {
+        task.depends_on.iter().flatten().any(|dependency| {
+            tasks
+                .iter()
+                .any(|tf| same_task_id(&tf.task.id, dependency) && !is_finished(&tf.task))
+        })
+    };
 
-fn incomplete_subtask(id: String, index: usize) -> Result<()> {
-    toggle_subtask_status(id, index, false)
-}
+    let best = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| !is_finished(task) && !is_blocked(task) && !is_waiting(task, today))
+        .filter(|task| task.status.as_deref() != Some("waiting"))
+        .map(|task| (task, urgency_score(task, &config.urgency, today)))
+        .max_by(|(a, (x, _)), (b, (y, _))| x.total_cmp(y).then_with(|| b.id.cmp(&a.id)));
 
-fn toggle_subtask_status(id: String, index: usize, complete: bool) -> Result<()> {
-    // Find the task file
-    let tasks = load_tasks()?;
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+    let Some((task, (score, parts))) = best else {
+        println!("🎉 No open, unblocked tasks that aren't snoozed");
+        return Ok(());
+    };
 
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
+    println!(
+        "👉 Next: {}: {} (urgency {:.1})",
+        task.id, task.title, score
+    );
+    for (name, value) in parts.iter().filter(|(_, value)| *value > 0.0) {
+        println!("   {:<9} +{:.1}", name, value);
+    }
+    if task.status.as_deref() != Some("active") {
+        println!("💡 Run `mdtasks start {}` to begin", task.id);
+    }
 
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
+    Ok(())
+}
 
-    if let Some(_front_matter) = parsed.data {
-        // Rebuild the content with the subtask status updated
-        let mut new_content = String::new();
+fn show_stats(config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    if tasks.is_empty() {
+        println!("No tasks found.");
+        return Ok(());
+    }
+    let open: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| !is_finished(task))
+        .collect();
 
-        // Add the front-matter section
-        let lines: Vec<&str> = content.lines().collect();
-        let mut front_matter_end = 0;
+    println!("📊 {} task(s), {} open", tasks.len(), open.len());
 
-        for (i, line) in lines.iter().enumerate() {
-            if i > 0 && line == &"---" {
-                front_matter_end = i;
-                break;
-            }
+    println!("\nBy status:");
+    let mut statuses: Vec<&str> = config.workflow.status_order();
+    for task_file in &tasks {
+        let status = task_file.task.status.as_deref().unwrap_or("pending");
+        if !statuses.contains(&status) {
+            statuses.push(status);
         }
-
-        // Add front-matter
-        for line in lines.iter().take(front_matter_end + 1) {
-            new_content.push_str(&format!("{}\n", line));
+    }
+    for status in statuses {
+        let count = tasks
+            .iter()
+            .filter(|tf| tf.task.status.as_deref().unwrap_or("pending") == status)
+            .count();
+        if count > 0 {
+            println!("  {:<12} {}", status, count);
         }
+    }
 
-        // Process the content to update the specific subtask
-        let processed_content = update_subtask_status(&parsed.content, index, complete);
-        new_content.push_str(&processed_content);
+    println!("\nOpen by priority:");
+    for priority in [Priority::High, Priority::Medium, Priority::Low] {
+        let count = open
+            .iter()
+            .filter(|task| priority_rank(task.priority.as_deref()) == priority)
+            .count();
+        println!("  {:<12} {}", priority.as_str(), count);
+    }
 
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
-        ))?;
+    let now = local_now();
+    let overdue = open.iter().filter(|task| is_overdue(task, &now)).count();
+    println!("\n⏰ Overdue: {}", overdue);
+    let touched = LastTouched::new(config);
+    let mut stale: Vec<(i64, &Task)> = tasks
+        .iter()
+        .filter(|tf| !is_finished(&tf.task))
+        .filter_map(|tf| {
+            let age = touched.days_since(tf, now.date_naive())?;
+            (age >= 30).then_some((age, &tf.task))
+        })
+        .collect();
+    println!("🕸️  Stale (untouched for 30+ days): {}", stale.len());
+    stale.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+    for (age, task) in stale.iter().take(5) {
+        println!("  {}  {}d {}", task.id, age, task.title);
+    }
 
-        let status = if complete { "completed" } else { "incomplete" };
-        println!("✅ Marked subtask #{} as {} for task {}", index, status, id);
-    } else {
-        return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
-        ));
+    let mut postponed: Vec<&Task> = open
+        .iter()
+        .copied()
+        .filter(|task| task.postponed.unwrap_or(0) > 0)
+        .collect();
+    if !postponed.is_empty() {
+        postponed.sort_by(|a, b| b.postponed.cmp(&a.postponed).then_with(|| a.id.cmp(&b.id)));
+        let slips: u32 = postponed.iter().filter_map(|task| task.postponed).sum();
+        println!(
+            "\n⏭️  Postponed: {} open task(s), {} slip(s) in total",
+            postponed.len(),
+            slips
+        );
+        for task in postponed.iter().take(5) {
+            println!(
+                "  {}  {}× {}",
+                task.id,
+                task.postponed.unwrap_or(0),
+                task.title
+            );
+        }
+    }
+
+    let completed: Vec<&Task> = tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| task.status.as_deref() == Some("done"))
+        .collect();
+    let lead_times = durations_hours(&completed, |task| task.created.as_deref());
+    let cycle_times = durations_hours(&completed, |task| task.started.as_deref());
+    if !lead_times.is_empty() || !cycle_times.is_empty() {
+        println!("\n⏱️  Completed tasks:");
+        for (label, hours) in [("Lead time", &lead_times), ("Cycle time", &cycle_times)] {
+            if hours.is_empty() {
+                continue;
+            }
+            let average = hours.iter().sum::<f64>() / hours.len() as f64;
+            println!(
+                "  {:<12} avg {}, median {} ({} task(s))",
+                label,
+                format_hours(average),
+                format_hours(hours[hours.len() / 2]),
+                hours.len()
+            );
+        }
+    }
+
+    println!();
+    for line in effort_summary_lines(&open) {
+        println!("{}", line);
     }
 
     Ok(())
 }
 
-fn update_subtask_status(content: &str, target_index: usize, complete: bool) -> String {
-    let mut result = String::new();
-    let mut current_index = 0;
+/// Sorted hours from the timestamp picked by `from` to `completed`, for tasks that have both
+fn durations_hours(tasks: &[&Task], from: impl Fn(&Task) -> Option<&str>) -> Vec<f64> {
+    let mut hours: Vec<f64> = tasks
+        .iter()
+        .filter_map(|task| {
+            let start = parse_task_datetime(from(task)?)?;
+            let end = parse_task_datetime(task.completed.as_deref()?)?;
+            Some((end - start).num_minutes().max(0) as f64 / 60.0)
+        })
+        .collect();
+    hours.sort_by(|a, b| a.total_cmp(b));
+    hours
+}
 
-    // Find the subtask section (preferring Subtasks over Checklist)
-    let (_section_name, section_start) = match find_subtask_section(content) {
-        Some((name, start)) => (name, start),
-        None => {
-            // No subtask section found, return original content
-            return content.to_string();
-        }
-    };
+/// Format a duration in hours as "5.5h" below a day and "3.2d" above
+fn format_hours(hours: f64) -> String {
+    if hours < 24.0 {
+        format!("{:.1}h", hours)
+    } else {
+        format!("{:.1}d", hours / 24.0)
+    }
+}
 
-    for (i, line) in content.lines().enumerate() {
-        // Check if we're entering the subtasks section
-        if i == section_start {
-            result.push_str(line);
-            result.push('\n');
-            continue;
+/// Remaining estimated effort of the open tasks among `tasks`, in total and per project
+fn effort_summary_lines(tasks: &[&Task]) -> Vec<String> {
+    // Per project: estimated hours, open tasks, and tasks without an estimate
+    let mut projects: std::collections::BTreeMap<&str, (f64, usize, usize)> =
+        std::collections::BTreeMap::new();
+    for task in tasks.iter().filter(|task| !is_finished(task)) {
+        let entry = projects
+            .entry(task.project.as_deref().unwrap_or("(no project)"))
+            .or_default();
+        entry.1 += 1;
+        match task.estimate.as_deref().and_then(parse_estimate_hours) {
+            Some(hours) => entry.0 += hours,
+            None => entry.2 += 1,
         }
+    }
 
-        // Check if we're leaving the subtasks section
-        if i > section_start && is_leaving_subtask_section(line) {
-            result.push_str(line);
-            result.push('\n');
-            continue;
+    let hours: f64 = projects.values().map(|(hours, _, _)| hours).sum();
+    let count: usize = projects.values().map(|(_, count, _)| count).sum();
+    let missing: usize = projects.values().map(|(_, _, missing)| missing).sum();
+    let mut lines = vec![format!(
+        "⏱️  Remaining estimate: {:.1}h across {} open task(s){}",
+        hours,
+        count,
+        unestimated_note(missing)
+    )];
+    if projects.len() > 1 || !projects.contains_key("(no project)") {
+        for (project, (hours, count, missing)) in &projects {
+            lines.push(format!(
+                "  {:<16} {:>7.1}h  {} task(s){}",
+                project,
+                hours,
+                count,
+                unestimated_note(*missing)
+            ));
         }
+    }
+    lines
+}
 
-        // If we're in the subtasks section, look for subtask items
-        if i > section_start && !is_leaving_subtask_section(line) {
-            let trimmed = line.trim();
-            if trimmed.starts_with("- [") {
-                current_index += 1;
-                if current_index == target_index {
-                    // This is the subtask we want to update
-                    let item_text = if trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]")
-                    {
-                        trimmed
-                            .strip_prefix("- [x]")
-                            .or_else(|| trimmed.strip_prefix("- [X]"))
-                            .unwrap_or(trimmed)
-                            .trim()
-                    } else if trimmed.starts_with("- [ ]") {
-                        trimmed.strip_prefix("- [ ]").unwrap_or(trimmed).trim()
-                    } else {
-                        trimmed
-                    };
+fn unestimated_note(missing: usize) -> String {
+    match missing {
+        0 => String::new(),
+        n => format!(", {} without estimate", n),
+    }
+}
 
-                    let new_checkbox = if complete { "- [x]" } else { "- [ ]" };
-                    result.push_str(&format!("{} {}\n", new_checkbox, item_text));
-                } else {
-                    result.push_str(line);
-                    result.push('\n');
-                }
+fn show_task(id: String, raw: bool, config: &Config) -> Result<()> {
+    let others = load_tasks(config)?;
+
+    let task_file = find_task(others.clone(), &id)?;
+
+    let task = &task_file.task;
+
+    println!("Task: {}", task.title);
+    println!("ID: {}", task.id);
+    println!("Status: {}", task.status.as_deref().unwrap_or("unknown"));
+    println!("Priority: {}", task.priority.as_deref().unwrap_or("medium"));
+
+    if let Some(ref tags) = task.tags {
+        println!("Tags: {}", tags.join(", "));
+    }
+
+    if let Some(ref project) = task.project {
+        println!("Project: {}", project);
+    }
+
+    if let Some(ref created) = task.created {
+        println!("Created: {}", created);
+    }
+
+    if let Some(ref due) = task.due {
+        if is_overdue(task, &local_now()) {
+            use std::io::IsTerminal;
+            let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            let due = format!("{} ⏰ overdue", due);
+            if color {
+                println!("Due: \x1b[31m{}\x1b[0m", due);
             } else {
-                result.push_str(line);
-                result.push('\n');
+                println!("Due: {}", due);
             }
         } else {
-            result.push_str(line);
-            result.push('\n');
+            println!("Due: {}", due);
         }
     }
 
-    result
-}
+    println!("\nContent:");
+    let body = if is_encrypted_body(&task_file.content) {
+        match decrypt_body(&task_file.content, config) {
+            Ok(body) => {
+                println!("🔓 (decrypted)");
+                body
+            }
+            Err(e) => {
+                println!("🔒 Encrypted, could not decrypt: {:#}", e);
+                return Ok(());
+            }
+        }
+    } else {
+        task_file.content.clone()
+    };
+    if raw {
+        println!("{}", body);
+    } else {
+        use std::io::IsTerminal;
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        println!("{}", render_markdown(&body, color));
+    }
 
-fn add_subtask(id: String, item: String) -> Result<()> {
-    // Find the task file
-    let tasks = load_tasks()?;
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+    let links = wiki_links(&body);
+    if !links.is_empty() {
+        println!("🔗 Links:");
+        for link in &links {
+            match others.iter().find(|tf| same_task_id(&tf.task.id, link)) {
+                Some(tf) => println!("  → {}", link_summary(&tf.task)),
+                None => println!("  → {} (no such task)", link),
+            }
+        }
+    }
+    let backlinks = tasks_linking_to(&others, task);
+    if !backlinks.is_empty() {
+        println!("↩️  Backlinks:");
+        for linking in backlinks {
+            println!("  ← {}", link_summary(linking));
+        }
+    }
 
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
+    Ok(())
+}
 
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
+fn load_tasks(config: &Config) -> Result<Vec<TaskFile>> {
+    let mut tasks = Vec::new();
 
-    if let Some(_front_matter) = parsed.data {
-        // Rebuild the content with the checklist item added
-        let mut new_content = String::new();
+    // Look for markdown files in the tasks directory
+    let (task_paths, _skipped) = scan_task_paths(config)?;
 
-        // Add the front-matter section
-        let lines: Vec<&str> = content.lines().collect();
-        let mut front_matter_end = 0;
+    // Files whose modification time and size match the index are not re-parsed
+    let mut index = if config.tasks.index {
+        read_task_index()
+    } else {
+        TaskIndex::default()
+    };
+    let mut entries = std::collections::HashMap::new();
+    let mut changed = false;
+
+    for file_path in &task_paths {
+        let key = file_path.to_string_lossy().to_string();
+        let stamp = file_stamp(file_path);
+        let entry = match index.entries.remove(&key) {
+            Some(entry) if stamp.is_some() && Some(entry.stamp) == stamp => entry,
+            _ => {
+                changed = true;
+                parse_index_entry(file_path, stamp.unwrap_or_default())?
+            }
+        };
 
-        for (i, line) in lines.iter().enumerate() {
-            if i > 0 && line == &"---" {
-                front_matter_end = i;
-                break;
+        match (&entry.task, &entry.error) {
+            (Some(task), _) => tasks.push(TaskFile {
+                task: task.clone(),
+                file_path: key.clone(),
+                content: entry.content.clone(),
+            }),
+            (None, Some(error)) => {
+                // Skip files that don't have valid task data
+                eprintln!(
+                    "⚠️  Skipping {}: {} (run `mdtasks doctor`)",
+                    file_path.display(),
+                    error
+                );
             }
+            (None, None) => {}
         }
+        entries.insert(key, entry);
+    }
 
-        // Add front-matter
-        for line in lines.iter().take(front_matter_end + 1) {
-            new_content.push_str(&format!("{}\n", line));
+    // Entries left over belong to files that were removed
+    if config.tasks.index && (changed || !index.entries.is_empty()) {
+        write_task_index(&TaskIndex {
+            version: task_index_version(),
+            entries,
+        });
+    }
+
+    // Sort by ID
+    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+    warn_duplicate_ids(&tasks);
+
+    Ok(tasks)
+}
+
+/// Whether duplicate IDs were already reported by this process
+static DUPLICATES_WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Warn once per command about IDs shared by several files; expects `tasks`
+/// sorted by ID
+fn warn_duplicate_ids(tasks: &[TaskFile]) {
+    let mut warnings = Vec::new();
+    for group in tasks.chunk_by(|a, b| a.task.id == b.task.id) {
+        if group.len() > 1 && !group[0].task.id.is_empty() {
+            let paths: Vec<&str> = group.iter().map(|tf| tf.file_path.as_str()).collect();
+            warnings.push(format!(
+                "⚠️  Task ID {} is used by {} files: {}",
+                group[0].task.id,
+                group.len(),
+                paths.join(", ")
+            ));
         }
+    }
+    if warnings.is_empty() || DUPLICATES_WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+    eprintln!("   Run `mdtasks doctor --fix-duplicates` to give the newer files fresh IDs");
+}
 
-        // Find the subtask section (preferring Subtasks over Checklist)
-        let (_section_name, section_start) = match find_subtask_section(&parsed.content) {
-            Some((name, start)) => (name, start),
-            None => {
-                // If no subtask section exists, add one at the end
-                new_content.push_str(&parsed.content);
-                new_content.push_str("\n## Subtasks\n\n");
-                new_content.push_str(&format!("- [ ] {}\n", item));
+/// Cached parse result of one task file
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    /// Modification time (nanoseconds since the epoch) and size of the parsed file
+    stamp: (u128, u64),
+    task: Option<Task>,
+    content: String,
+    /// Why a file with front-matter is not a valid task
+    error: Option<String>,
+}
 
-                // Write the updated file
-                std::fs::write(&task_file.file_path, new_content).context(format!(
-                    "Failed to write updated task file: {}",
-                    task_file.file_path
-                ))?;
+/// On-disk cache of parsed task files, keyed by path
+#[derive(Default, Serialize, Deserialize)]
+struct TaskIndex {
+    version: String,
+    entries: std::collections::HashMap<String, IndexEntry>,
+}
 
-                println!("✅ Added subtask to task {}: {}", id, item);
-                return Ok(());
-            }
-        };
+/// Bump when the cached data changes shape or task parsing changes
+const TASK_INDEX_FORMAT: u32 = 9;
 
-        // Find the subtasks section and add the item
-        let mut in_subtasks = false;
-        let mut subtask_added = false;
+fn task_index_version() -> String {
+    format!("{}-{}", env!("CARGO_PKG_VERSION"), TASK_INDEX_FORMAT)
+}
 
-        for (i, line) in parsed.content.lines().enumerate() {
-            new_content.push_str(&format!("{}\n", line));
+fn task_index_path() -> PathBuf {
+    state_dir().join("index.json")
+}
 
-            // Check if we're in the subtasks section
-            if i == section_start {
-                in_subtasks = true;
-            } else if in_subtasks && is_leaving_subtask_section(line) {
-                // We've moved to the next section, add the item before this line
-                new_content.push_str(&format!("- [ ] {}\n", item));
-                subtask_added = true;
-                in_subtasks = false;
-            } else if in_subtasks && line.trim().is_empty() && !subtask_added {
-                // Empty line in subtasks section, add the item
-                new_content.push_str(&format!("- [ ] {}\n", item));
-                subtask_added = true;
+/// Read the index, starting over when it is missing, unreadable, or from another version
+fn read_task_index() -> TaskIndex {
+    std::fs::read_to_string(task_index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<TaskIndex>(&content).ok())
+        .filter(|index| index.version == task_index_version())
+        .unwrap_or_default()
+}
+
+/// Save the index; failures only cost a re-parse next time, so they are ignored
+fn write_task_index(index: &TaskIndex) {
+    if dry_run() {
+        return;
+    }
+    let path = task_index_path();
+    if let Ok(content) = serde_json::to_string(index) {
+        let _ = std::fs::create_dir_all(state_dir());
+        // Write to a temporary file first so readers never see a partial index
+        let temp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        if std::fs::write(&temp, content).is_err() || std::fs::rename(&temp, &path).is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(u128, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some((modified, metadata.len()))
+}
+
+fn parse_index_entry(path: &Path, stamp: (u128, u64)) -> Result<IndexEntry> {
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read file: {}", path.display()))?;
+
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    let (task, error) = match parsed.data {
+        Some(front_matter) => match extract_task_from_pod(&front_matter) {
+            Ok(task) => (Some(task), None),
+            Err(e) => (None, Some(e.to_string())),
+        },
+        None => (None, None),
+    };
+
+    Ok(IndexEntry {
+        stamp,
+        task,
+        content: parsed.content,
+        error,
+    })
+}
+
+/// Task directory entry that was not loaded, with the reason
+type SkippedEntry = (PathBuf, String);
+
+/// Collect the markdown files inside the tasks directory, along with entries
+/// that were skipped because they are symlinks, escape the tasks root, or
+/// resolve to a file that was already seen
+fn scan_task_paths(config: &Config) -> Result<(Vec<PathBuf>, Vec<SkippedEntry>)> {
+    let mut paths = Vec::new();
+    let mut skipped = Vec::new();
+
+    let tasks_dir = Path::new(&config.tasks.dir);
+    if !tasks_dir.exists() {
+        return Ok((paths, skipped));
+    }
+
+    let follow_symlinks = match config.tasks.symlinks.as_str() {
+        "skip" => false,
+        "follow" => true,
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unknown tasks.symlinks '{}' (expected skip or follow)",
+                other
+            ))
+        }
+    };
+    let root = tasks_dir.canonicalize().context(format!(
+        "Failed to resolve tasks directory: {}",
+        tasks_dir.display()
+    ))?;
+    let mut seen = std::collections::HashSet::new();
+
+    // Attached files live next to the tasks but are never task files themselves
+    let attachments = tasks_dir.join("attachments");
+
+    for entry in WalkDir::new(tasks_dir)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|e| e.path() != attachments)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+    {
+        let path = entry.path().to_path_buf();
+
+        if entry.path_is_symlink() && !follow_symlinks {
+            skipped.push((
+                path,
+                "symlink (skipped by tasks.symlinks policy)".to_string(),
+            ));
+            continue;
+        }
+
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                skipped.push((path, format!("cannot be resolved: {}", e)));
+                continue;
             }
+        };
+
+        if !canonical.starts_with(&root) {
+            skipped.push((
+                path,
+                format!(
+                    "resolves outside the tasks directory: {}",
+                    canonical.display()
+                ),
+            ));
+            continue;
         }
 
-        // If we never found a place to add it, add it at the end
-        if !subtask_added {
-            new_content.push_str(&format!("- [ ] {}\n", item));
+        if !seen.insert(canonical.clone()) {
+            skipped.push((
+                path,
+                format!("duplicate of already loaded file: {}", canonical.display()),
+            ));
+            continue;
         }
 
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
+        paths.push(path);
+    }
+
+    Ok((paths, skipped))
+}
+
+/// Refuse to write through symlinks or to paths outside the tasks directory
+fn ensure_inside_tasks_dir(path: &str, config: &Config) -> Result<()> {
+    let target = Path::new(path);
+    if target.is_symlink() {
+        return Err(anyhow::anyhow!(
+            "Refusing to modify symlinked task file: {}",
+            path
+        ));
+    }
+
+    let root = Path::new(&config.tasks.dir)
+        .canonicalize()
+        .context(format!(
+            "Failed to resolve tasks directory: {}",
+            config.tasks.dir
         ))?;
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .context(format!("Failed to resolve directory of: {}", path))?;
 
-        println!("✅ Added subtask to task {}: {}", id, item);
-    } else {
+    if !parent.starts_with(&root) {
         return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
+            "Refusing to write outside the tasks directory: {}",
+            path
         ));
     }
 
-    Ok(())
+    Ok(())
+}
+
+/// Read and parse a single task file, reporting why it is not a valid task
+fn validate_task_file(path: &Path) -> Result<Task> {
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read file: {}", path.display()))?;
+
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    match parsed.data {
+        Some(front_matter) => extract_task_from_pod(&front_matter),
+        None => Err(anyhow::anyhow!("Missing or unparsable front-matter")),
+    }
+}
+
+fn extract_task_from_pod(pod: &gray_matter::Pod) -> Result<Task> {
+    use gray_matter::Pod;
+
+    let mut task = Task {
+        id: String::new(),
+        title: String::new(),
+        status: None,
+        priority: None,
+        tags: None,
+        project: None,
+        created: None,
+        due: None,
+        completed: None,
+        started: None,
+        branch: None,
+        scheduled: None,
+        estimate: None,
+        parent: None,
+        depends_on: None,
+        recur: None,
+        wait_until: None,
+        postponed: None,
+        milestone: None,
+        assignee: None,
+        delegated_to: None,
+        follow_up: None,
+        updated: None,
+    };
+
+    if let Pod::Hash(hash) = pod {
+        for (key, value) in hash {
+            match key.as_str() {
+                "id" => match value {
+                    Pod::String(s) => task.id = s.clone(),
+                    Pod::Integer(i) => task.id = i.to_string(),
+                    _ => {}
+                },
+                "title" => {
+                    if let Pod::String(s) = value {
+                        task.title = s.clone();
+                    }
+                }
+                "status" => {
+                    if let Pod::String(s) = value {
+                        task.status = Some(s.clone());
+                    }
+                }
+                "priority" => {
+                    if let Pod::String(s) = value {
+                        task.priority = Some(s.clone());
+                    }
+                }
+                "tags" => {
+                    if let Pod::Array(arr) = value {
+                        let mut tags = Vec::new();
+                        for item in arr {
+                            if let Pod::String(s) = item {
+                                tags.push(s.clone());
+                            }
+                        }
+                        task.tags = Some(tags);
+                    }
+                }
+                "project" => {
+                    if let Pod::String(s) = value {
+                        task.project = Some(s.clone());
+                    }
+                }
+                "created" => {
+                    if let Pod::String(s) = value {
+                        task.created = Some(s.clone());
+                    }
+                }
+                "due" => {
+                    if let Pod::String(s) = value {
+                        task.due = Some(s.clone());
+                    }
+                }
+                "started" => {
+                    if let Pod::String(s) = value {
+                        task.started = Some(s.clone());
+                    }
+                }
+                "completed" => {
+                    if let Pod::String(s) = value {
+                        task.completed = Some(s.clone());
+                    }
+                }
+                "branch" => {
+                    if let Pod::String(s) = value {
+                        task.branch = Some(s.clone());
+                    }
+                }
+                "scheduled" => {
+                    if let Pod::String(s) = value {
+                        task.scheduled = Some(s.clone());
+                    }
+                }
+                "estimate" => match value {
+                    Pod::String(s) => task.estimate = Some(s.clone()),
+                    Pod::Integer(i) => task.estimate = Some(i.to_string()),
+                    Pod::Float(f) => task.estimate = Some(f.to_string()),
+                    _ => {}
+                },
+                "parent" => task.parent = pod_scalar(value),
+                "recur" => task.recur = pod_scalar(value),
+                "wait_until" => task.wait_until = pod_scalar(value),
+                "milestone" => task.milestone = pod_scalar(value),
+                "assignee" => task.assignee = pod_scalar(value),
+                "delegated_to" => task.delegated_to = pod_scalar(value),
+                "follow_up" => task.follow_up = pod_scalar(value),
+                "updated" => task.updated = pod_scalar(value),
+                "postponed" => {
+                    if let Pod::Integer(n) = value {
+                        task.postponed = u32::try_from(*n).ok();
+                    }
+                }
+                "depends_on" => match value {
+                    Pod::Array(items) => {
+                        task.depends_on = Some(items.iter().filter_map(pod_scalar).collect())
+                    }
+                    value => task.depends_on = pod_scalar(value).map(|id| vec![id]),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    if task.id.is_empty() || task.title.is_empty() {
+        return Err(anyhow::anyhow!("Missing required fields: id or title"));
+    }
+
+    Ok(task)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_task(
+    title: String,
+    priority: Option<String>,
+    status: Option<String>,
+    tags: Option<Vec<String>>,
+    project: Option<String>,
+    due: Option<String>,
+    notes: Option<String>,
+    encrypted: bool,
+    config: &Config,
+) -> Result<String> {
+    if let Some(ref status) = status {
+        validate_field_value("status", status, config)?;
+    }
+    if let Some(ref priority) = priority {
+        validate_field_value("priority", priority, config)?;
+    }
+    let due = due
+        .map(|due| {
+            parse_due_input(&due, local_today()).context(format!(
+                "Could not understand due date '{}' (try YYYY-MM-DD, tomorrow, fri, or 3d, optionally followed by HH:MM)",
+                due
+            ))
+        })
+        .transpose()?;
+
+    let _lock = lock_tasks(config)?;
+
+    // Generate next ID
+    let next_id = get_next_task_id(project.as_deref(), config)?;
+
+    // Create task struct
+    let task = Task {
+        id: next_id.clone(),
+        title: title.clone(),
+        status: status.or(Some("pending".to_string())),
+        priority: priority.or(Some("medium".to_string())),
+        tags,
+        project,
+        created: Some(task_timestamp(config)),
+        due,
+        completed: None,
+        started: None,
+        branch: None,
+        scheduled: None,
+        estimate: None,
+        parent: None,
+        depends_on: None,
+        recur: None,
+        wait_until: None,
+        postponed: None,
+        milestone: None,
+        assignee: None,
+        delegated_to: None,
+        follow_up: None,
+        updated: None,
+    };
+
+    // Create markdown content
+    let mut content = String::new();
+
+    // Add front-matter
+    content.push_str(&format_front_matter(&task));
+
+    // Add markdown content
+    content.push_str(&format_task_body(&config.body, notes.as_deref()));
+    if encrypted {
+        content = set_front_matter_value(&content, "encrypted", "true");
+    }
+
+    // Create filename
+    let filename = format!(
+        "{}/{}-{}.md",
+        config.tasks.dir,
+        next_id,
+        slugify(&title, &config.slug)
+    );
+
+    // Ensure tasks directory exists
+    if !dry_run() {
+        std::fs::create_dir_all(&config.tasks.dir)?;
+    }
+
+    // Write file
+    write_task_file(&filename, &content, config)
+        .context(format!("Failed to write task file: {}", filename))?;
+
+    println!("✅ Created task {}: {}", next_id, title);
+    println!("📁 File: {}", filename);
+
+    Ok(next_id)
+}
+
+/// Task fields picked out of a one-line `add` title
+struct QuickCapture {
+    title: String,
+    priority: Option<Priority>,
+    tags: Vec<String>,
+    project: Option<String>,
+    due: Option<String>,
+}
+
+impl QuickCapture {
+    fn from_title(title: String) -> Self {
+        Self {
+            title,
+            priority: None,
+            tags: Vec::new(),
+            project: None,
+            due: None,
+        }
+    }
+}
+
+/// Read `!high`, `#tag`, `@project` and `due:<date>` words out of a title,
+/// e.g. "Fix login redirect !high #auth @webapp due:fri"
+fn parse_quick_capture(title: &str) -> Result<QuickCapture> {
+    let mut capture = QuickCapture::from_title(String::new());
+    let mut words = Vec::new();
+
+    for word in title.split_whitespace() {
+        if let Some(value) = word.strip_prefix('!') {
+            let priority = match value.to_lowercase().as_str() {
+                "h" => Some(Priority::High),
+                "m" => Some(Priority::Medium),
+                "l" => Some(Priority::Low),
+                value => Priority::parse(value),
+            };
+            if let Some(priority) = priority {
+                capture.priority = Some(priority);
+                continue;
+            }
+        } else if let Some(tag) = word.strip_prefix('#') {
+            // "#123" is more likely an issue reference than a tag
+            if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                if !capture.tags.iter().any(|t| t == tag) {
+                    capture.tags.push(tag.to_string());
+                }
+                continue;
+            }
+        } else if let Some(project) = word.strip_prefix('@') {
+            if !project.is_empty() {
+                capture.project = Some(project.to_string());
+                continue;
+            }
+        } else if let Some(value) = word.strip_prefix("due:") {
+            let today = local_today();
+            let due = parse_due_input(value, today).context(format!(
+                "Could not understand due date '{}' (try YYYY-MM-DD, today, tomorrow, fri, or 3d)",
+                value
+            ))?;
+            capture.due = Some(due);
+            continue;
+        }
+        words.push(word);
+    }
+
+    capture.title = words.join(" ");
+    if capture.title.is_empty() {
+        return Err(anyhow::anyhow!("Task title is empty"));
+    }
+    Ok(capture)
+}
+
+/// Resolve a due date written as YYYY-MM-DD, `today`, `tomorrow`, a weekday
+/// (the next one, today included), or an offset such as `3d` or `2w`
+fn parse_due_shorthand(value: &str, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+    use chrono::Datelike;
+
+    let value = value.to_lowercase();
+    if let Some(date) = parse_task_date(&value) {
+        return Some(date);
+    }
+    match value.as_str() {
+        "today" | "tod" => return Some(today),
+        "yesterday" => return Some(today - chrono::Duration::days(1)),
+        "tomorrow" | "tom" => return Some(today + chrono::Duration::days(1)),
+        _ => {}
+    }
+    if let Some(count) = value.strip_suffix('d').and_then(|n| n.parse::<i64>().ok()) {
+        return Some(today + chrono::Duration::days(count));
+    }
+    if let Some(count) = value.strip_suffix('w').and_then(|n| n.parse::<i64>().ok()) {
+        return Some(today + chrono::Duration::weeks(count));
+    }
+    let weekday = recur::weekday_from_word(&value)?;
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    Some(today + chrono::Duration::days(ahead as i64))
+}
+
+/// Copy a task's front-matter and body to a new ID, resetting its progress
+fn clone_task(id: String, title: Option<String>, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+    let front_matter = parsed
+        .data
+        .context("Could not parse front-matter from task file")?;
+
+    let original = extract_task_from_pod(&front_matter)?;
+    let next_id = get_next_task_id(original.project.as_deref(), config)?;
+    let task = Task {
+        id: next_id.clone(),
+        title: title.unwrap_or_else(|| original.title.clone()),
+        status: Some("pending".to_string()),
+        created: Some(task_timestamp(config)),
+        completed: None,
+        started: None,
+        branch: None,
+        postponed: None,
+        milestone: None,
+        assignee: None,
+        delegated_to: None,
+        follow_up: None,
+        updated: None,
+        ..original
+    };
+
+    // Custom fields carry over, except another tool's identity for the original
+    let content = remove_front_matter_value(&content, "uuid");
+    let mut new_content = rewrite_front_matter(&content, &task);
+    new_content.push_str(&uncheck_all_subtasks(&parsed.content));
+
+    let filename = format!(
+        "{}/{}-{}.md",
+        config.tasks.dir,
+        next_id,
+        slugify(&task.title, &config.slug)
+    );
+    write_task_file(&filename, &new_content, config)
+        .context(format!("Failed to write task file: {}", filename))?;
+
+    println!("✅ Cloned task {} as {}: {}", id, next_id, task.title);
+    println!("📁 File: {}", filename);
+
+    Ok(())
+}
+
+/// Render the configured body skeleton, placing `notes` in the Notes section
+fn format_task_body(body: &BodyConfig, notes: Option<&str>) -> String {
+    let mut content = String::new();
+
+    if !body.heading.is_empty() {
+        content.push_str(&format!("# {}\n\n", body.heading));
+    }
+
+    let has_notes_section = body.sections.iter().any(|section| section.name == "Notes");
+    if let (Some(notes), false) = (notes, has_notes_section) {
+        content.push_str(&format!("## Notes\n{}\n\n", notes));
+    }
+
+    for section in &body.sections {
+        content.push_str(&format!("## {}\n", section.name));
+        if !section.content.trim().is_empty() {
+            content.push_str(&format!("{}\n", section.content.trim_end()));
+        }
+        if let (Some(notes), "Notes") = (notes, section.name.as_str()) {
+            content.push_str(&format!("{}\n", notes));
+        }
+        content.push('\n');
+    }
+
+    content
+}
+
+/// Render the YAML front-matter block (including delimiters) for a task
+fn format_front_matter(task: &Task) -> String {
+    let mut content = String::new();
+
+    content.push_str("---\n");
+    for (_, line) in front_matter_lines(task) {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content.push_str("---\n\n");
+    content
+}
+
+/// Front-matter fields managed by mdtasks, in the order they are written
+const FRONT_MATTER_FIELDS: [&str; 23] = [
+    "id",
+    "title",
+    "status",
+    "priority",
+    "tags",
+    "project",
+    "created",
+    "due",
+    "started",
+    "completed",
+    "branch",
+    "scheduled",
+    "estimate",
+    "parent",
+    "depends_on",
+    "recur",
+    "wait_until",
+    "postponed",
+    "milestone",
+    "assignee",
+    "delegated_to",
+    "follow_up",
+    "updated",
+];
+
+/// Front-matter lines for the fields mdtasks manages, keyed by field name
+fn front_matter_lines(task: &Task) -> Vec<(&'static str, String)> {
+    let mut lines = vec![
+        ("id", format!("id: {}", task.id)),
+        ("title", format!("title: {}", yaml_quote(&task.title))),
+    ];
+
+    if let Some(ref tags) = task.tags {
+        let tags: Vec<String> = tags.iter().map(|tag| yaml_quote(tag)).collect();
+        lines.push(("tags", format!("tags: [{}]", tags.join(", "))));
+    }
+    if let Some(ref depends_on) = task.depends_on {
+        lines.push((
+            "depends_on",
+            format!("depends_on: [{}]", depends_on.join(", ")),
+        ));
+    }
+
+    if let Some(ref recur) = task.recur {
+        lines.push(("recur", format!("recur: {}", yaml_quote(recur))));
+    }
+    if let Some(postponed) = task.postponed {
+        lines.push(("postponed", format!("postponed: {}", postponed)));
+    }
+    // Names come from users and TODO(author) comments and may hold `:` or `#`
+    if let Some(ref milestone) = task.milestone {
+        lines.push(("milestone", format!("milestone: {}", yaml_quote(milestone))));
+    }
+    if let Some(ref assignee) = task.assignee {
+        lines.push(("assignee", format!("assignee: {}", yaml_quote(assignee))));
+    }
+    if let Some(ref delegated_to) = task.delegated_to {
+        lines.push((
+            "delegated_to",
+            format!("delegated_to: {}", yaml_quote(delegated_to)),
+        ));
+    }
+
+    let optional = [
+        ("status", &task.status),
+        ("priority", &task.priority),
+        ("project", &task.project),
+        ("created", &task.created),
+        ("due", &task.due),
+        ("started", &task.started),
+        ("completed", &task.completed),
+        ("branch", &task.branch),
+        ("scheduled", &task.scheduled),
+        ("estimate", &task.estimate),
+        ("parent", &task.parent),
+        ("wait_until", &task.wait_until),
+        ("follow_up", &task.follow_up),
+        ("updated", &task.updated),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            lines.push((key, format!("{}: {}", key, value)));
+        }
+    }
+
+    lines.sort_by_key(|(key, _)| FRONT_MATTER_FIELDS.iter().position(|k| k == key));
+    lines
+}
+
+/// Double-quote a value for YAML, escaping backslashes and quotes
+fn yaml_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Rebuild the front-matter block of an existing task file for an updated task.
+/// Fields mdtasks does not manage, comments, and unchanged fields are kept
+/// verbatim; only changed fields are rewritten, added, or removed.
+fn rewrite_front_matter(content: &str, task: &Task) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let end = match lines.first() {
+        Some(first) if first.trim_end() == "---" => lines
+            .iter()
+            .skip(1)
+            .position(|line| line.trim_end() == "---")
+            .map(|i| i + 1),
+        _ => None,
+    };
+    let end = match end {
+        Some(end) => end,
+        None => return format_front_matter(task),
+    };
+
+    let original = Matter::<gray_matter::engine::YAML>::new()
+        .parse(content)
+        .data
+        .and_then(|pod| extract_task_from_pod(&pod).ok());
+    let original_lines = original
+        .as_ref()
+        .map(front_matter_lines)
+        .unwrap_or_default();
+    let new_lines = front_matter_lines(task);
+
+    // Split the block into entries: a top-level key with its indented continuation lines
+    let mut entries: Vec<(Option<&str>, Vec<&str>)> = Vec::new();
+    for line in &lines[1..end] {
+        let is_continuation = line.starts_with([' ', '\t']) || line.starts_with("- ");
+        match entries.last_mut() {
+            Some((Some(_), entry_lines)) if is_continuation => entry_lines.push(line),
+            _ => {
+                let key = line
+                    .split_once(':')
+                    .map(|(key, _)| key.trim())
+                    .filter(|key| !key.is_empty() && !key.starts_with('#'));
+                entries.push((key, vec![line]));
+            }
+        }
+    }
+
+    let mut result = String::from("---\n");
+    let mut written = Vec::new();
+    for (key, entry_lines) in &entries {
+        let managed = key.and_then(|key| FRONT_MATTER_FIELDS.iter().find(|k| **k == key).copied());
+        let key = match managed {
+            Some(key) => key,
+            None => {
+                for line in entry_lines {
+                    result.push_str(line);
+                    result.push('\n');
+                }
+                continue;
+            }
+        };
+
+        let old_line = original_lines.iter().find(|(k, _)| *k == key);
+        let new_line = new_lines.iter().find(|(k, _)| *k == key);
+        match (old_line, new_line) {
+            (Some((_, old)), Some((_, new))) if old != new => {
+                result.push_str(new);
+                result.push('\n');
+            }
+            (None, Some((_, new))) => {
+                result.push_str(new);
+                result.push('\n');
+            }
+            // The field was cleared
+            (Some(_), None) => {}
+            // Unchanged (or unreadable) values keep their original formatting
+            _ => {
+                for line in entry_lines {
+                    result.push_str(line);
+                    result.push('\n');
+                }
+            }
+        }
+        written.push(key);
+    }
+
+    for (key, line) in &new_lines {
+        if !written.contains(key) {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    result.push_str("---\n\n");
+    result
+}
+
+/// Turn a title into a file/branch name slug according to the slug config
+fn slugify(title: &str, slug: &SlugConfig) -> String {
+    let separator = if slug.case == "snake" { "_" } else { "-" };
+
+    let mut text = String::new();
+    for c in title.chars() {
+        // Apostrophes join words ("don't" → "dont") instead of splitting them
+        if c == '\'' || c == '’' {
+            continue;
+        }
+
+        if slug.transliterate && !c.is_ascii() {
+            // Unmapped non-ASCII letters are dropped when transliterating
+            match transliterate_char(c) {
+                Some(ascii) => text.push_str(ascii),
+                None if c.is_alphanumeric() => {}
+                None => text.push(' '),
+            }
+        } else if c.is_alphanumeric() {
+            text.push(c);
+        } else {
+            text.push(' ');
+        }
+    }
+
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .filter(|word| {
+            !slug
+                .stop_words
+                .iter()
+                .any(|stop| stop.eq_ignore_ascii_case(word))
+        })
+        .collect();
+
+    let mut result = String::new();
+    for word in &words {
+        let extra = if result.is_empty() { 0 } else { 1 };
+        let length = result.chars().count() + extra + word.chars().count();
+        if slug.max_length > 0 && length > slug.max_length {
+            // Keep whole words; only cut a single overlong first word
+            if result.is_empty() {
+                result = word.chars().take(slug.max_length).collect();
+            }
+            break;
+        }
+        if !result.is_empty() {
+            result.push_str(separator);
+        }
+        result.push_str(word);
+    }
+
+    result
+}
+
+/// ASCII replacement for common accented Latin, Greek, and Cyrillic letters
+fn transliterate_char(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à' | 'á' | 'â' | 'ã' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'Ä' | 'Æ' => "Ae",
+        'ä' | 'æ' => "ae",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ç' | 'ć' | 'č' => "c",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'ď' | 'đ' | 'ð' => "d",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'Ğ' => "G",
+        'ğ' => "g",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'İ' => "I",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'Ł' => "L",
+        'ł' => "l",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ø' | 'ō' | 'ő' => "o",
+        'Ö' | 'Œ' => "Oe",
+        'ö' | 'œ' => "oe",
+        'Ř' => "R",
+        'ř' => "r",
+        'Ś' | 'Š' | 'Ş' => "S",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'Ť' | 'Ţ' => "T",
+        'ť' | 'ţ' => "t",
+        'Þ' => "Th",
+        'þ' => "th",
+        'Ù' | 'Ú' | 'Û' | 'Ū' | 'Ů' | 'Ű' => "U",
+        'ù' | 'ú' | 'û' | 'ū' | 'ů' | 'ű' => "u",
+        'Ü' => "Ue",
+        'ü' => "ue",
+        'Ý' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        // Greek
+        'α' | 'Α' => "a",
+        'β' | 'Β' => "b",
+        'γ' | 'Γ' => "g",
+        'δ' | 'Δ' => "d",
+        'ε' | 'Ε' | 'έ' => "e",
+        'ζ' | 'Ζ' => "z",
+        'η' | 'Η' | 'ή' => "i",
+        'θ' | 'Θ' => "th",
+        'ι' | 'Ι' | 'ί' => "i",
+        'κ' | 'Κ' => "k",
+        'λ' | 'Λ' => "l",
+        'μ' | 'Μ' => "m",
+        'ν' | 'Ν' => "n",
+        'ξ' | 'Ξ' => "x",
+        'ο' | 'Ο' | 'ό' => "o",
+        'π' | 'Π' => "p",
+        'ρ' | 'Ρ' => "r",
+        'σ' | 'ς' | 'Σ' => "s",
+        'τ' | 'Τ' => "t",
+        'υ' | 'Υ' | 'ύ' => "y",
+        'φ' | 'Φ' => "f",
+        'χ' | 'Χ' => "ch",
+        'ψ' | 'Ψ' => "ps",
+        'ω' | 'Ω' | 'ώ' => "o",
+        // Cyrillic
+        'а' | 'А' => "a",
+        'б' | 'Б' => "b",
+        'в' | 'В' => "v",
+        'г' | 'Г' => "g",
+        'д' | 'Д' => "d",
+        'е' | 'Е' | 'э' | 'Э' => "e",
+        'ё' | 'Ё' => "yo",
+        'ж' | 'Ж' => "zh",
+        'з' | 'З' => "z",
+        'и' | 'И' => "i",
+        'й' | 'Й' => "y",
+        'к' | 'К' => "k",
+        'л' | 'Л' => "l",
+        'м' | 'М' => "m",
+        'н' | 'Н' => "n",
+        'о' | 'О' => "o",
+        'п' | 'П' => "p",
+        'р' | 'Р' => "r",
+        'с' | 'С' => "s",
+        'т' | 'Т' => "t",
+        'у' | 'У' => "u",
+        'ф' | 'Ф' => "f",
+        'х' | 'Х' => "kh",
+        'ц' | 'Ц' => "ts",
+        'ч' | 'Ч' => "ch",
+        'ш' | 'Ш' => "sh",
+        'щ' | 'Щ' => "shch",
+        'ы' | 'Ы' => "y",
+        'ю' | 'Ю' => "yu",
+        'я' | 'Я' => "ya",
+        'ъ' | 'Ъ' | 'ь' | 'Ь' => "",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+fn get_next_task_id(project: Option<&str>, config: &Config) -> Result<String> {
+    TaskIdAllocator::load(config)?.next(project, config)
+}
+
+/// Split an ID into its prefix and number: "API-014" is ("API", 14), "014" is ("", 14)
+fn split_task_id(id: &str) -> Option<(&str, u32)> {
+    match id.rsplit_once('-') {
+        Some((prefix, number)) if !prefix.is_empty() => Some((prefix, number.parse().ok()?)),
+        _ => Some(("", id.parse().ok()?)),
+    }
+}
+
+fn format_task_id(prefix: &str, number: u32) -> String {
+    if prefix.is_empty() {
+        format!("{:03}", number)
+    } else {
+        format!("{}-{:03}", prefix, number)
+    }
+}
+
+/// ID prefix for a new task: its project's entry in `tasks.project_prefixes`,
+/// otherwise `tasks.id_prefix`
+fn id_prefix_for<'a>(project: Option<&str>, config: &'a Config) -> &'a str {
+    project
+        .and_then(|project| config.tasks.project_prefixes.get(project))
+        .unwrap_or(&config.tasks.id_prefix)
+}
+
+/// Hands out new task IDs, keeping one counter per prefix
+struct TaskIdAllocator {
+    highest: std::collections::HashMap<String, u32>,
+}
+
+impl TaskIdAllocator {
+    fn new<'a>(ids: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut highest = std::collections::HashMap::new();
+        for (prefix, number) in ids.into_iter().filter_map(split_task_id) {
+            let max = highest.entry(prefix.to_string()).or_insert(0);
+            *max = number.max(*max);
+        }
+        Self { highest }
+    }
+
+    fn load(config: &Config) -> Result<Self> {
+        let tasks = load_tasks(config)?;
+        Ok(Self::new(tasks.iter().map(|tf| tf.task.id.as_str())))
+    }
+
+    fn next(&mut self, project: Option<&str>, config: &Config) -> Result<String> {
+        let prefix = id_prefix_for(project, config);
+        let unique = match config.tasks.id_scheme.as_str() {
+            "sequential" => {
+                let number = self.highest.entry(prefix.to_string()).or_insert(0);
+                *number += 1;
+                return Ok(format_task_id(prefix, *number));
+            }
+            "ulid" => new_ulid(),
+            "uuid" => new_uuid(),
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown tasks.id_scheme '{}' (expected sequential, ulid, or uuid)",
+                    other
+                ))
+            }
+        };
+        Ok(if prefix.is_empty() {
+            unique
+        } else {
+            format!("{}-{}", prefix, unique)
+        })
+    }
+}
+
+/// 64 random bits from the standard library's randomly seeded hasher
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    hasher.write_u32(std::process::id());
+    hasher.finish()
+}
+
+/// A ULID: 48-bit millisecond timestamp and 80 random bits in Crockford base32,
+/// so IDs sort by creation time
+fn new_ulid() -> String {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    let millis = chrono::Utc::now().timestamp_millis() as u128 & ((1 << 48) - 1);
+    let random = ((random_u64() as u128) << 16 | (random_u64() & 0xffff) as u128) & ((1 << 80) - 1);
+    let value = millis << 80 | random;
+    (0..26)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// A random (version 4) UUID
+fn new_uuid() -> String {
+    let high = (random_u64() & !0xf000) | 0x4000;
+    let low = (random_u64() & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+fn mark_task_done(id: String, completed_at: Option<String>, config: &Config) -> Result<()> {
+    let completed_at = completed_at
+        .as_deref()
+        .map(parse_completed_at)
+        .transpose()?
+        .unwrap_or_else(|| task_timestamp(config));
+    let _lock = lock_tasks(config)?;
+
+    // Find the task file
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(front_matter) = parsed.data {
+        // Extract the task data
+        let mut task = extract_task_from_pod(&front_matter)?;
+
+        // Update the status to "done" and record the completion date
+        task.status = Some("done".to_string());
+        task.completed = Some(completed_at);
+
+        // Rebuild the file content
+        let mut new_content = rewrite_front_matter(&content, &task);
+
+        // Process the markdown content to mark all checklist items as complete
+        let processed_content =
+            mark_all_subtasks_complete(&parsed.content, config.body.obsidian_tasks);
+        new_content.push_str(&processed_content);
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        println!("✅ Marked task {} as done: {}", id, task.title);
+        if read_focus().is_some_and(|focused| same_task_id(&focused, &task.id)) {
+            clear_focus()?;
+        }
+        announce_status_change(&task, config);
+
+        let encrypted = wants_encryption(split_front_matter(&content).0);
+        if let Err(e) = create_next_occurrence(&task, &parsed.content, encrypted, config) {
+            eprintln!("⚠️  Could not schedule the next occurrence: {}", e);
+        }
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check `[workflow] wip_limit` before `id` becomes active: warn, or with
+/// `wip_strict` refuse unless forced
+fn check_wip_limit(id: &str, force: bool, config: &Config) -> Result<()> {
+    let Some(limit) = config.workflow.wip_limit else {
+        return Ok(());
+    };
+    let (this, active): (Vec<TaskFile>, Vec<TaskFile>) = load_tasks(config)?
+        .into_iter()
+        .filter(|tf| tf.task.status.as_deref() == Some("active"))
+        .partition(|tf| same_task_id(&tf.task.id, id));
+    // Restarting an active task adds no work in progress
+    if !this.is_empty() || active.len() < limit {
+        return Ok(());
+    }
+    let active: Vec<&str> = active.iter().map(|tf| tf.task.id.as_str()).collect();
+
+    let message = format!(
+        "WIP limit reached: {} task(s) already active ({}), limit is {}",
+        active.len(),
+        active.join(", "),
+        limit
+    );
+    if config.workflow.wip_strict && !force {
+        return Err(anyhow::anyhow!(
+            "{}; finish or pause one first, or pass --force",
+            message
+        ));
+    }
+    eprintln!("⚠️  {}", message);
+    Ok(())
+}
+
+fn mark_task_start(id: String, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    // Find the task file
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(front_matter) = parsed.data {
+        // Extract the task data
+        let mut task = extract_task_from_pod(&front_matter)?;
+
+        // Update the status to "active" and record the start date
+        task.status = Some("active".to_string());
+        task.started = Some(task_timestamp(config));
+
+        // Rebuild the file content
+        let mut new_content = rewrite_front_matter(&content, &task);
+
+        // Add the original markdown content
+        new_content.push_str(&parsed.content);
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        println!("🚀 Started task {}: {}", id, task.title);
+        announce_status_change(&task, config);
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+fn complete_subtask(id: String, index: usize, config: &Config) -> Result<()> {
+    change_subtask(id, index, SubtaskEdit::Check(true), config)
+}
+
+fn incomplete_subtask(id: String, index: usize, config: &Config) -> Result<()> {
+    change_subtask(id, index, SubtaskEdit::Check(false), config)
+}
+
+/// Complete a subtask and, with `[workflow] auto_done`, the task itself once
+/// every subtask is checked
+fn check_subtask(id: String, index: usize, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+    complete_subtask(id.clone(), index, config)?;
+    if !config.workflow.auto_done {
+        return Ok(());
+    }
+
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
+    let (done, total) = subtask_progress(&content);
+    if done == total && !is_finished(&task_file.task) {
+        mark_task_done(id, None, config)?;
+    } else {
+        println!("☑️  {}/{} subtasks done", done, total);
+    }
+    Ok(())
+}
+
+/// Show a pending task with some but not all subtasks checked as `partial`
+fn with_checklist_status(mut task_file: TaskFile) -> TaskFile {
+    let pending = matches!(task_file.task.status.as_deref(), None | Some("pending"));
+    let (done, total) = subtask_progress(&task_file.content);
+    if pending && done > 0 && done < total {
+        task_file.task.status = Some("partial".to_string());
+    }
+    task_file
+}
+
+/// Change applied to a single subtask item
+enum SubtaskEdit {
+    Check(bool),
+    Remove,
+    Rename(String),
+}
+
+fn change_subtask(id: String, index: usize, edit: SubtaskEdit, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    // Find the task file
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(_front_matter) = parsed.data {
+        // Rebuild the content with the subtask updated
+        let mut new_content = String::new();
+
+        // Add the front-matter section
+        let lines: Vec<&str> = content.lines().collect();
+        let mut front_matter_end = 0;
+
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 && line == &"---" {
+                front_matter_end = i;
+                break;
+            }
+        }
+
+        // Add front-matter
+        for line in lines.iter().take(front_matter_end + 1) {
+            new_content.push_str(&format!("{}\n", line));
+        }
+
+        // Process the content to update the specific subtask
+        let processed_content =
+            edit_subtask_item(&parsed.content, index, &edit, config.body.obsidian_tasks)
+                .context(format!("Task {} has no subtask #{}", id, index))?;
+        new_content.push_str(&processed_content);
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        match edit {
+            SubtaskEdit::Check(complete) => {
+                let status = if complete { "completed" } else { "incomplete" };
+                println!("✅ Marked subtask #{} as {} for task {}", index, status, id);
+            }
+            SubtaskEdit::Remove => println!("🗑️  Removed subtask #{} from task {}", index, id),
+            SubtaskEdit::Rename(text) => {
+                println!("✏️  Updated subtask #{} for task {}: {}", index, id, text)
+            }
+        }
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Date following an Obsidian Tasks marker (`📅`, `✅`) in a checklist item
+fn checklist_date<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(marker)?;
+    let date = rest.trim_start().get(..10)?;
+    is_iso_date(date).then_some(date)
+}
+
+/// Remove an item's `✅ <date>` and, if `done`, stamp it with today's date
+fn stamp_done_date(text: &str, done: bool) -> String {
+    let mut text = text.to_string();
+    if let Some(date) = checklist_date(&text, "✅") {
+        text = text
+            .replace(&format!("✅ {}", date), "")
+            .replace(&format!("✅{}", date), "");
+        text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    if done {
+        text = format!("{} ✅ {}", text, local_today().format("%Y-%m-%d"));
+    }
+    text
+}
+
+/// Apply an edit to the n-th (1-based) item of the subtask section, or None
+/// if there is no such item
+fn edit_subtask_item(
+    content: &str,
+    target_index: usize,
+    edit: &SubtaskEdit,
+    obsidian: bool,
+) -> Option<String> {
+    // Find the subtask section (preferring Subtasks over Checklist)
+    let (_section_name, section_start) = find_subtask_section(content)?;
+
+    let mut result = String::new();
+    let mut current_index = 0;
+    let mut in_subtasks = false;
+    let mut edited = false;
+
+    for (i, line) in content.lines().enumerate() {
+        if i == section_start {
+            in_subtasks = true;
+        } else if in_subtasks && is_leaving_subtask_section(line) {
+            in_subtasks = false;
+        } else if in_subtasks && line.trim().starts_with("- [") {
+            current_index += 1;
+            if current_index == target_index {
+                let trimmed = line.trim();
+                let indent = &line[..line.len() - line.trim_start().len()];
+                let checked = trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]");
+                let text = trimmed.get(5..).unwrap_or("").trim();
+
+                edited = true;
+                match edit {
+                    SubtaskEdit::Check(complete) => {
+                        let checkbox = if *complete { "- [x]" } else { "- [ ]" };
+                        let text = stamp_done_date(text, *complete && obsidian);
+                        result.push_str(&format!("{}{} {}\n", indent, checkbox, text));
+                    }
+                    SubtaskEdit::Remove => {}
+                    SubtaskEdit::Rename(new_text) => {
+                        // Dates stay with the item when only its text changes
+                        let checkbox = if checked { "- [x]" } else { "- [ ]" };
+                        let mut new_text = new_text.clone();
+                        for marker in ["📅", "✅"] {
+                            if let Some(date) = checklist_date(text, marker) {
+                                new_text = format!("{} {} {}", new_text, marker, date);
+                            }
+                        }
+                        result.push_str(&format!("{}{} {}\n", indent, checkbox, new_text));
+                    }
+                }
+                continue;
+            }
+        }
+
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    edited.then_some(result)
+}
+
+fn add_subtask(id: String, item: String, due: Option<String>, config: &Config) -> Result<()> {
+    let item = match due {
+        Some(due) => {
+            if !is_iso_date(&due) {
+                return Err(anyhow::anyhow!(
+                    "Invalid due date '{}', expected YYYY-MM-DD",
+                    due
+                ));
+            }
+            format!("{} 📅 {}", item, due)
+        }
+        None => item,
+    };
+
+    let _lock = lock_tasks(config)?;
+
+    // Find the task file
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(_front_matter) = parsed.data {
+        // Rebuild the content with the checklist item added
+        let mut new_content = String::new();
+
+        // Add the front-matter section
+        let lines: Vec<&str> = content.lines().collect();
+        let mut front_matter_end = 0;
+
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 && line == &"---" {
+                front_matter_end = i;
+                break;
+            }
+        }
+
+        // Add front-matter
+        for line in lines.iter().take(front_matter_end + 1) {
+            new_content.push_str(&format!("{}\n", line));
+        }
+
+        // Find the subtask section (preferring Subtasks over Checklist)
+        let (_section_name, section_start) = match find_subtask_section(&parsed.content) {
+            Some((name, start)) => (name, start),
+            None => {
+                // If no subtask section exists, add one at the end
+                new_content.push_str(&parsed.content);
+                new_content.push_str("\n## Subtasks\n\n");
+                new_content.push_str(&format!("- [ ] {}\n", item));
+
+                // Write the updated file
+                write_task_file(&task_file.file_path, &new_content, config).context(format!(
+                    "Failed to write updated task file: {}",
+                    task_file.file_path
+                ))?;
+
+                println!("✅ Added subtask to task {}: {}", id, item);
+                return Ok(());
+            }
+        };
+
+        // Find the subtasks section and add the item
+        let mut in_subtasks = false;
+        let mut subtask_added = false;
+
+        for (i, line) in parsed.content.lines().enumerate() {
+            new_content.push_str(&format!("{}\n", line));
+
+            // Check if we're in the subtasks section
+            if i == section_start {
+                in_subtasks = true;
+            } else if in_subtasks && is_leaving_subtask_section(line) {
+                // We've moved to the next section, add the item before this line
+                new_content.push_str(&format!("- [ ] {}\n", item));
+                subtask_added = true;
+                in_subtasks = false;
+            } else if in_subtasks && line.trim().is_empty() && !subtask_added {
+                // Empty line in subtasks section, add the item
+                new_content.push_str(&format!("- [ ] {}\n", item));
+                subtask_added = true;
+            }
+        }
+
+        // If we never found a place to add it, add it at the end
+        if !subtask_added {
+            new_content.push_str(&format!("- [ ] {}\n", item));
+        }
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        println!("✅ Added subtask to task {}: {}", id, item);
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+fn mark_all_subtasks_complete(content: &str, obsidian: bool) -> String {
+    let mut result = String::new();
+
+    // Find the subtask section (preferring Subtasks over Checklist)
+    let (_section_name, section_start) = match find_subtask_section(content) {
+        Some((name, start)) => (name, start),
+        None => {
+            // No subtask section found, return original content
+            return content.to_string();
+        }
+    };
+
+    for (i, line) in content.lines().enumerate() {
+        // Check if we're entering the subtasks section
+        if i == section_start {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+
+        // Check if we're leaving the subtasks section
+        if i > section_start && is_leaving_subtask_section(line) {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+
+        // If we're in the subtasks section, mark all items as complete
+        if i > section_start && !is_leaving_subtask_section(line) {
+            let trimmed = line.trim();
+            if trimmed.starts_with("- [ ]") {
+                // Replace incomplete checkbox with complete checkbox
+                let item_text = trimmed.strip_prefix("- [ ]").unwrap_or(trimmed).trim();
+                let item_text = stamp_done_date(item_text, obsidian);
+                result.push_str(&format!("- [x] {}\n", item_text));
+            } else {
+                result.push_str(line);
+                result.push('\n');
+            }
+        } else {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+/// Find the subtask section in content, preferring "## Subtasks" over "## Checklist"
+fn find_subtask_section(content: &str) -> Option<(&str, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut subtasks_start = None;
+    let mut checklist_start = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("## Subtasks") {
+            subtasks_start = Some(i);
+            break; // Prefer Subtasks over Checklist
+        } else if trimmed.starts_with("## Checklist") && checklist_start.is_none() {
+            checklist_start = Some(i);
+        }
+    }
+
+    // Return Subtasks if found, otherwise Checklist
+    if let Some(start) = subtasks_start {
+        Some(("## Subtasks", start))
+    } else {
+        checklist_start.map(|start| ("## Checklist", start))
+    }
+}
+
+/// Check if we're leaving a subtask section
+fn is_leaving_subtask_section(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with("##") && !trimmed.starts_with("###")
+}
+
+fn list_subtasks(id: String, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+
+    let task_file = find_task(tasks, &id)?;
+
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    let task = &task_file.task;
+
+    println!("📋 Subtasks for task {}: {}", id, task.title);
+    println!();
+
+    // Find the subtask section (preferring Subtasks over Checklist)
+    let (_section_name, section_start) = match find_subtask_section(&content) {
+        Some((name, start)) => (name, start),
+        None => {
+            println!("  No subtasks section found.");
+            return Ok(());
+        }
+    };
+
+    // Find and display subtask items
+    let mut in_subtasks = false;
+    let mut has_items = false;
+    let mut number = 0;
+
+    for (i, line) in content.lines().enumerate() {
+        // Check if we're entering the subtasks section
+        if i == section_start {
+            in_subtasks = true;
+            continue;
+        }
+
+        // Check if we're leaving the subtasks section
+        if in_subtasks && is_leaving_subtask_section(line) {
+            break;
+        }
+
+        // If we're in the subtasks section, look for subtask items
+        if in_subtasks {
+            let trimmed = line.trim();
+            if trimmed.starts_with("- [") {
+                has_items = true;
+                number += 1;
+                // Extract the item text (remove the checkbox part)
+                let item_text = if trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]") {
+                    // Completed item
+                    let text = trimmed
+                        .strip_prefix("- [x]")
+                        .or_else(|| trimmed.strip_prefix("- [X]"))
+                        .unwrap_or(trimmed)
+                        .trim();
+                    format!("✅ {}", text)
+                } else if trimmed.starts_with("- [ ]") {
+                    // Incomplete item
+                    let text = trimmed.strip_prefix("- [ ]").unwrap_or(trimmed).trim();
+                    format!("⏳ {}", text)
+                } else {
+                    // Fallback for other formats
+                    trimmed.to_string()
+                };
+                println!("  {}. {}", number, item_text);
+            }
+        }
+    }
+
+    if !has_items {
+        println!("  No subtasks found.");
+    }
+
+    Ok(())
+}
+fn set_task_field(id: String, field: &str, value: String, config: &Config) -> Result<()> {
+    validate_field_value(field, &value, config)?;
+
+    let _lock = lock_tasks(config)?;
+
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(front_matter) = parsed.data {
+        // Extract the task data
+        let mut task = extract_task_from_pod(&front_matter)?;
+        let old_status = task.status.clone();
+
+        // Update the specific field
+        match field {
+            "title" => task.title = value.clone(),
+            "status" => task.status = Some(value.clone()),
+            "priority" => task.priority = Some(value.clone()),
+            "project" => task.project = Some(value.clone()),
+            "tags" => {
+                let tags: Vec<String> = value.split(',').map(|s| s.trim().to_string()).collect();
+                task.tags = Some(tags);
+            }
+            "due" => task.due = Some(value.clone()),
+            "branch" => task.branch = Some(value.clone()),
+            "scheduled" => task.scheduled = Some(value.clone()),
+            "estimate" => task.estimate = Some(value.clone()),
+            "parent" => task.parent = Some(value.clone()),
+            "recur" => task.recur = Some(value.clone()),
+            "wait_until" => task.wait_until = Some(value.clone()),
+            "milestone" => task.milestone = Some(value.clone()),
+            "assignee" => task.assignee = Some(value.clone()),
+            "delegated_to" => task.delegated_to = Some(value.clone()),
+            "follow_up" => task.follow_up = Some(value.clone()),
+            "depends_on" => {
+                let ids: Vec<String> = value.split(',').map(|s| s.trim().to_string()).collect();
+                task.depends_on = Some(ids);
+            }
+            _ => return Err(anyhow::anyhow!("Unknown field: {}", field)),
+        }
+
+        // Rebuild the file content
+        let mut new_content = rewrite_front_matter(&content, &task);
+
+        // Add the original markdown content
+        new_content.push_str(&parsed.content);
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        println!("✅ Updated {} for task {}: {}", field, id, value);
+        if field == "status" && old_status != task.status {
+            announce_status_change(&task, config);
+        }
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Statuses of finished tasks, which mutating commands refuse to touch without --force
+const FINISHED_STATUSES: [&str; 4] = ["done", "archived", "cancelled", "canceled"];
+
+fn is_finished(task: &Task) -> bool {
+    task.status
+        .as_deref()
+        .is_some_and(|status| FINISHED_STATUSES.contains(&status))
+}
+
+/// Refuse to modify a finished task unless forced, so a mistyped ID does not
+/// silently change a long-closed task
+fn ensure_task_editable(id: &str, force: bool, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    // A missing task is reported by the command itself
+    let task = match find_task(tasks, id) {
+        Ok(task_file) => task_file.task,
+        Err(_) => return Ok(()),
+    };
+
+    if is_finished(&task) {
+        let status = task.status.as_deref().unwrap_or_default();
+        if !force {
+            return Err(anyhow::anyhow!(
+                "Task {} is {}: {} (use --force to modify it anyway)",
+                id,
+                status,
+                task.title
+            ));
+        }
+        eprintln!("⚠️  Modifying {} task {}: {}", status, id, task.title);
+    }
+
+    Ok(())
+}
+
+/// Fields that can be changed with `modify --set`
+const MODIFIABLE_FIELDS: [&str; 16] = [
+    "title",
+    "status",
+    "priority",
+    "tags",
+    "project",
+    "due",
+    "scheduled",
+    "estimate",
+    "parent",
+    "depends_on",
+    "recur",
+    "wait_until",
+    "milestone",
+    "assignee",
+    "delegated_to",
+    "follow_up",
+];
+
+/// Get the current value of a front-matter field for display
+fn task_field_value(task: &Task, field: &str) -> String {
+    match field {
+        "title" => task.title.clone(),
+        "status" => task.status.clone().unwrap_or_default(),
+        "priority" => task.priority.clone().unwrap_or_default(),
+        "tags" => task.tags.as_ref().map(|t| t.join(",")).unwrap_or_default(),
+        "project" => task.project.clone().unwrap_or_default(),
+        "due" => task.due.clone().unwrap_or_default(),
+        "scheduled" => task.scheduled.clone().unwrap_or_default(),
+        "estimate" => task.estimate.clone().unwrap_or_default(),
+        "created" => task.created.clone().unwrap_or_default(),
+        "started" => task.started.clone().unwrap_or_default(),
+        "completed" => task.completed.clone().unwrap_or_default(),
+        "branch" => task.branch.clone().unwrap_or_default(),
+        "parent" => task.parent.clone().unwrap_or_default(),
+        "recur" => task.recur.clone().unwrap_or_default(),
+        "wait_until" => task.wait_until.clone().unwrap_or_default(),
+        "postponed" => task.postponed.map(|n| n.to_string()).unwrap_or_default(),
+        "milestone" => task.milestone.clone().unwrap_or_default(),
+        "assignee" => task.assignee.clone().unwrap_or_default(),
+        "delegated_to" => task.delegated_to.clone().unwrap_or_default(),
+        "follow_up" => task.follow_up.clone().unwrap_or_default(),
+        "updated" => task.updated.clone().unwrap_or_default(),
+        "depends_on" => task
+            .depends_on
+            .as_ref()
+            .map(|d| d.join(","))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn modify_tasks(
+    status_filter: Option<String>,
+    tag_filter: Option<String>,
+    priority_filter: Option<String>,
+    set: Vec<String>,
+    dry_run: bool,
+    force: bool,
+    config: &Config,
+) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    // Parse and validate the requested changes before touching any file
+    let mut changes = Vec::new();
+    for assignment in &set {
+        let (field, value) = assignment.split_once('=').context(format!(
+            "Invalid --set value '{}', expected field=value",
+            assignment
+        ))?;
+        let field = field.trim();
+        if !MODIFIABLE_FIELDS.contains(&field) {
+            return Err(anyhow::anyhow!(
+                "Unknown field: {} (expected one of: {})",
+                field,
+                MODIFIABLE_FIELDS.join(", ")
+            ));
+        }
+        validate_field_value(field, value.trim(), config)?;
+        changes.push((field.to_string(), value.trim().to_string()));
+    }
+
+    let tasks = load_tasks(config)?;
+    let matching: Vec<_> = tasks
+        .into_iter()
+        .filter(|task_file| {
+            task_matches_filters(
+                &task_file.task,
+                &status_filter,
+                &tag_filter,
+                &priority_filter,
+            )
+        })
+        .collect();
+
+    if matching.is_empty() {
+        println!("No tasks found matching the criteria.");
+        return Ok(());
+    }
+
+    // Finished tasks are only changed when explicitly forced
+    let (matching, finished): (Vec<_>, Vec<_>) = matching
+        .into_iter()
+        .partition(|task_file| force || !is_finished(&task_file.task));
+    if !finished.is_empty() {
+        println!(
+            "⏭️  Skipping {} done/archived/cancelled task(s) (use --force to include them)",
+            finished.len()
+        );
+        if matching.is_empty() {
+            return Ok(());
+        }
+    }
+
+    if dry_run {
+        println!("🔍 {} task(s) would be modified:", matching.len());
+        for task_file in &matching {
+            let task = &task_file.task;
+            println!("  - {}: {}", task.id, task.title);
+            for (field, value) in &changes {
+                println!(
+                    "      {}: {} → {}",
+                    field,
+                    task_field_value(task, field),
+                    value
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    for task_file in &matching {
+        for (field, value) in &changes {
+            set_task_field(task_file.task.id.clone(), field, value.clone(), config)?;
+        }
+    }
+
+    println!("✅ Modified {} task(s)", matching.len());
+    Ok(())
+}
+
+/// List every tag with its open and finished task counts
+fn list_tags(config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+
+    let mut counts: std::collections::BTreeMap<&str, (usize, usize)> =
+        std::collections::BTreeMap::new();
+    for task_file in &tasks {
+        let finished = is_finished(&task_file.task);
+        for tag in task_file.task.tags.iter().flatten() {
+            let entry = counts.entry(tag.as_str()).or_default();
+            if finished {
+                entry.1 += 1;
+            } else {
+                entry.0 += 1;
+            }
+        }
+    }
+
+    if counts.is_empty() {
+        println!("No tags found.");
+        return Ok(());
+    }
+
+    let width = counts
+        .keys()
+        .map(|tag| tag.chars().count())
+        .max()
+        .unwrap_or(3)
+        .max(3);
+    println!(
+        "{:<width$} {:>5} {:>5}",
+        "TAG",
+        "OPEN",
+        "DONE",
+        width = width
+    );
+    println!("{}", "-".repeat(width + 12));
+    for (tag, (open, done)) in &counts {
+        println!("{:<width$} {:>5} {:>5}", tag, open, done, width = width);
+    }
+
+    Ok(())
+}
+
+/// Rename a tag on every task (or remove it when `new` is None), merging
+/// duplicates when a task already has the new tag
+fn retag_tasks(old: &str, new: Option<&str>, dry_run: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    if new == Some(old) {
+        return Err(anyhow::anyhow!("Old and new tag are the same: {}", old));
+    }
+
+    let tasks = load_tasks(config)?;
+    let affected: Vec<&TaskFile> = tasks
+        .iter()
+        .filter(|tf| tf.task.tags.iter().flatten().any(|tag| tag == old))
+        .collect();
+
+    if affected.is_empty() {
+        println!("No tasks are tagged '{}'.", old);
+        return Ok(());
+    }
+
+    for task_file in &affected {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in task_file.task.tags.iter().flatten() {
+            let tag = if tag == old {
+                match new {
+                    Some(new) => new.to_string(),
+                    None => continue,
+                }
+            } else {
+                tag.clone()
+            };
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        if dry_run {
+            println!(
+                "  - {}: {} → [{}]",
+                task_file.task.id,
+                task_file.task.title,
+                tags.join(", ")
+            );
+            continue;
+        }
+
+        let content = read_task_file(&task_file.file_path, config)?;
+        let matter = Matter::<gray_matter::engine::YAML>::new();
+        let parsed = matter.parse(&content);
+
+        let mut task = task_file.task.clone();
+        task.tags = (!tags.is_empty()).then_some(tags);
+        let mut new_content = rewrite_front_matter(&content, &task);
+        new_content.push_str(&parsed.content);
+
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+    }
+
+    let action = match new {
+        Some(new) => format!("Renamed tag '{}' to '{}' on", old, new),
+        None => format!("Removed tag '{}' from", old),
+    };
+    if dry_run {
+        println!("🔍 {} task(s) would change", affected.len());
+    } else {
+        println!("🏷️  {} {} task(s)", action, affected.len());
+    }
+
+    Ok(())
+}
+
+fn add_task_note(id: String, note: String, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    // Read the current file content
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Parse the front-matter and content
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    if let Some(front_matter) = parsed.data {
+        // Extract the task data
+        let task = extract_task_from_pod(&front_matter)?;
+
+        // Rebuild the file content (front-matter unchanged)
+        let mut new_content = rewrite_front_matter(&content, &task);
+
+        // Process the markdown content to add the note
+        let processed_content = add_note_to_content(&parsed.content, &note);
+        new_content.push_str(&processed_content);
+
+        // Write the updated file
+        write_task_file(&task_file.file_path, &new_content, config).context(format!(
+            "Failed to write updated task file: {}",
+            task_file.file_path
+        ))?;
+
+        println!("✅ Added note to task {}: {}", id, note);
+    } else {
+        return Err(anyhow::anyhow!(
+            "Could not parse front-matter from task file"
+        ));
+    }
+
+    Ok(())
+}
+
+fn add_note_to_content(content: &str, note: &str) -> String {
+    let mut result = String::new();
+    let mut in_notes = false;
+    let mut notes_added = false;
+
+    for line in content.lines() {
+        // Check if we're entering the notes section
+        if line.trim().starts_with("## Notes") {
+            in_notes = true;
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+
+        // Check if we're leaving the notes section
+        if in_notes && line.trim().starts_with("##") && !line.trim().starts_with("###") {
+            // Add the note before leaving the section
+            if !notes_added {
+                result.push_str(&format!("{}\n\n", note));
+                notes_added = true;
+            }
+            in_notes = false;
+        }
+
+        // If we're in the notes section, add the note after the first empty line
+        if in_notes && line.trim().is_empty() && !notes_added {
+            result.push_str(line);
+            result.push('\n');
+            result.push_str(&format!("{}\n", note));
+            notes_added = true;
+        } else {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    // If we never found a notes section, add it
+    if !notes_added {
+        result.push_str("\n## Notes\n");
+        result.push_str(&format!("{}\n", note));
+    }
+
+    result
+}
+/// Append an entry to the end of a `## <heading>` section, creating the section if needed
+fn append_to_section(content: &str, heading: &str, entry: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let header = format!("## {}", heading);
+
+    let start = match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => start,
+        None => {
+            let mut result = content.trim_end().to_string();
+            if !result.is_empty() {
+                result.push_str("\n\n");
+            }
+            result.push_str(&format!("{}\n\n{}\n", header, entry));
+            return result;
+        }
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim().starts_with("## "))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    // Insert after the last non-blank line of the section
+    let insert_at = (start + 1..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .map_or(start + 1, |i| i + 1);
+
+    let mut result: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    if insert_at == start + 1 {
+        result.insert(insert_at, String::new());
+        result.insert(insert_at + 1, entry.to_string());
+    } else {
+        result.insert(insert_at, entry.to_string());
+    }
+
+    let mut result = result.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Lines of a `## <heading>` section, without the heading itself
+fn section_lines<'a>(content: &'a str, heading: &str) -> Vec<&'a str> {
+    let header = format!("## {}", heading);
+    content
+        .lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.trim().starts_with("## "))
+        .collect()
+}
+
+/// Append an entry to a section of a task file, keeping the front-matter as is
+fn append_to_task_section(
+    task_file: &TaskFile,
+    heading: &str,
+    entry: &str,
+    config: &Config,
+) -> Result<()> {
+    let content = read_task_file(&task_file.file_path, config)?;
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter.parse(&content);
+
+    let mut new_content = rewrite_front_matter(&content, &task_file.task);
+    new_content.push_str(&append_to_section(&parsed.content, heading, entry));
+
+    write_task_file(&task_file.file_path, &new_content, config).context(format!(
+        "Failed to write updated task file: {}",
+        task_file.file_path
+    ))
+}
+
+fn add_task_comment(
+    id: String,
+    text: String,
+    author: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    let author = author.or_else(git_user_name);
+    let timestamp = local_now().format("%Y-%m-%dT%H:%M:%S%:z");
+    let text = text.replace('\n', " ");
+    let entry = match &author {
+        Some(author) => format!("- {} **{}**: {}", timestamp, author, text),
+        None => format!("- {} {}", timestamp, text),
+    };
+
+    append_to_task_section(&task_file, "Log", &entry, config)?;
+
+    println!("💬 Logged on task {}: {}", id, text);
+
+    Ok(())
+}
+
+fn show_task_log(id: String, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    // Entries are "- <timestamp> <text>"; anything else is kept as free text
+    let mut entries: Vec<(Option<chrono::DateTime<chrono::FixedOffset>>, String)> = Vec::new();
+    for line in section_lines(split_front_matter(&content).1, "Log") {
+        let Some(entry) = line.trim().strip_prefix("- ") else {
+            continue;
+        };
+        let (stamp, text) = entry.split_once(' ').unwrap_or((entry, ""));
+        match chrono::DateTime::parse_from_rfc3339(stamp) {
+            Ok(time) => entries.push((Some(time), text.to_string())),
+            Err(_) => entries.push((None, entry.to_string())),
+        }
+    }
+
+    if entries.is_empty() {
+        println!("No log entries for task {}", id);
+        return Ok(());
+    }
+
+    entries.sort_by_key(|(time, _)| *time);
+
+    println!("📜 Log for task {}: {}", id, task_file.task.title);
+    for (time, text) in entries {
+        match time {
+            Some(time) => {
+                // Drop the bold markup around the author
+                let text = match text.strip_prefix("**") {
+                    Some(rest) => rest.replacen("**:", ":", 1),
+                    None => text,
+                };
+                println!("  {}  {}", time.format("%Y-%m-%d %H:%M"), text)
+            }
+            None => println!("  {:16}  {}", "", text),
+        }
+    }
+
+    Ok(())
+}
+
+/// Seconds in a pomodoro length: "25m", "1h", "90s", or plain minutes
+fn parse_interval_seconds(length: &str) -> Result<u64> {
+    let length = length.trim().to_lowercase();
+    let seconds = if let Some(seconds) = length.strip_suffix('s') {
+        seconds.trim().parse::<f64>().ok()
+    } else if let Ok(minutes) = length.parse::<f64>() {
+        Some(minutes * 60.0)
+    } else {
+        parse_estimate_hours(&length).map(|hours| hours * 3600.0)
+    };
+    match seconds {
+        Some(seconds) if seconds >= 1.0 => Ok(seconds.round() as u64),
+        _ => Err(anyhow::anyhow!(
+            "Invalid length '{}' (expected e.g. 25m, 1h, or 90s)",
+            length
+        )),
+    }
+}
+
+fn format_interval(seconds: u64) -> String {
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, minutes, 0) => format!("{}m", minutes),
+        (0, 0, seconds) => format!("{}s", seconds),
+        (0, minutes, seconds) => format!("{}m{}s", minutes, seconds),
+        (hours, 0, 0) => format!("{}h", hours),
+        (hours, minutes, _) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+/// Best-effort desktop notification via notify-send or osascript
+fn desktop_notification(title: &str, message: &str) {
+    let result = if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title {:?}", message, title);
+        std::process::Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+    } else {
+        std::process::Command::new("notify-send")
+            .args([title, message])
+            .output()
+    };
+    if let Err(e) = result {
+        eprintln!("⚠️  Could not show a desktop notification: {}", e);
+    }
+}
+
+/// Count down one focus interval, then log it under the task's `## Time Log`
+/// as "- <start> <length> pomodoro"; an interrupted interval is not logged
+fn run_pomodoro(id: String, length: &str, notify: bool, config: &Config) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    let seconds = parse_interval_seconds(length)?;
+    let task = find_task(load_tasks(config)?, &id)?.task;
+    let started = local_now();
+    println!(
+        "🍅 {} focus on {}: {} (Ctrl-C to abandon)",
+        format_interval(seconds),
+        task.id,
+        task.title
+    );
+
+    let interactive = std::io::stdout().is_terminal();
+    let end = std::time::Instant::now() + std::time::Duration::from_secs(seconds);
+    loop {
+        let left = end.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        if interactive {
+            let left = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+            print!("\r⏳ {:02}:{:02} remaining ", left / 60, left % 60);
+            std::io::stdout().flush()?;
+        }
+        std::thread::sleep(left.min(std::time::Duration::from_secs(1)));
+    }
+    if interactive {
+        println!("\r⌛ Time's up!             \x07");
+    }
+
+    // The task file may have changed during the interval, so look it up again
+    let _lock = lock_tasks(config)?;
+    let task_file = find_task(load_tasks(config)?, &task.id)?;
+    let entry = format!(
+        "- {} {} pomodoro",
+        started.format("%Y-%m-%dT%H:%M:%S%:z"),
+        format_interval(seconds)
+    );
+    append_to_task_section(&task_file, "Time Log", &entry, config)?;
+    println!(
+        "✅ Logged {} on task {}: {}",
+        format_interval(seconds),
+        task.id,
+        task.title
+    );
+
+    if notify {
+        desktop_notification(
+            "🍅 Pomodoro finished",
+            &format!("{}: {}", task.id, task.title),
+        );
+    }
+    Ok(())
+}
+
+fn attachments_dir(id: &str, config: &Config) -> PathBuf {
+    Path::new(&config.tasks.dir).join("attachments").join(id)
+}
+
+fn attach_file(id: String, path: String, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let source = Path::new(&path);
+    if !source.is_file() {
+        return Err(anyhow::anyhow!("Not a file: {}", path));
+    }
+    let file_name = source
+        .file_name()
+        .context(format!("Invalid file name: {}", path))?
+        .to_string_lossy()
+        .to_string();
+
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+
+    let dir = attachments_dir(&id, config);
+    let target = dir.join(&file_name);
+    if target.exists() {
+        return Err(anyhow::anyhow!(
+            "Attachment already exists: {}",
+            target.display()
+        ));
+    }
+    if dry_run() {
+        println!("📎 Would copy {} to {}", path, target.display());
+    } else {
+        std::fs::create_dir_all(&dir)
+            .context(format!("Failed to create directory: {}", dir.display()))?;
+        std::fs::copy(source, &target).context(format!(
+            "Failed to copy {} to {}",
+            path,
+            target.display()
+        ))?;
+    }
+
+    // Link relative to the task file so it renders in git web UIs
+    let depth = Path::new(&task_file.file_path)
+        .parent()
+        .and_then(|parent| parent.strip_prefix(&config.tasks.dir).ok())
+        .map_or(0, |relative| relative.components().count());
+    let link = format!(
+        "{}attachments/{}/{}",
+        "../".repeat(depth),
+        id,
+        file_name.replace(' ', "%20")
+    );
+
+    append_to_task_section(
+        &task_file,
+        "Attachments",
+        &format!("- [{}]({})", file_name, link),
+        config,
+    )?;
+
+    println!("📎 Attached {} to task {}", target.display(), id);
+
+    Ok(())
+}
+
+fn list_attachments(id: String, open: Option<String>, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    find_task(tasks, &id)?;
+
+    let dir = attachments_dir(&id, config);
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    if let Some(selector) = open {
+        let file = selector
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| files.get(i))
+            .or_else(|| {
+                files.iter().find(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name == selector.as_str())
+                })
+            })
+            .context(format!("No attachment '{}' for task {}", selector, id))?;
+
+        let file = file.to_string_lossy().to_string();
+        open_with_default_app(&file)?;
+        println!("📂 Opened {}", file);
+        return Ok(());
+    }
+
+    if files.is_empty() {
+        println!("No attachments for task {}", id);
+        return Ok(());
+    }
+
+    println!("📎 Attachments for task {}:", id);
+    for (i, path) in files.iter().enumerate() {
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        println!("  {}. {} ({} bytes)", i + 1, path.display(), size);
+    }
+
+    Ok(())
+}
+
+/// Open a file with the OS default handler (open, start, or xdg-open)
+fn open_with_default_app(path: &str) -> Result<()> {
+    if cfg!(target_os = "macos") {
+        run_terminal_cmd_internal(&["open", path])
+    } else if cfg!(target_os = "windows") {
+        run_terminal_cmd_internal(&["cmd", "/C", "start", "", path])
+    } else {
+        run_terminal_cmd_internal(&["xdg-open", path])
+    }
+}
+
+/// Open a task's markdown file with the OS default handler
+fn open_task_file(id: String, config: &Config) -> Result<()> {
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    open_with_default_app(&task_file.file_path)?;
+    println!("📂 Opened {}", task_file.file_path);
+    Ok(())
+}
+
+/// Print a task's file path, for piping into other tools
+fn print_task_path(id: String, absolute: bool, config: &Config) -> Result<()> {
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let mut path = PathBuf::from(&task_file.file_path);
+    if absolute {
+        path = path
+            .canonicalize()
+            .context(format!("Failed to resolve {}", path.display()))?;
+    }
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Fill `{name}` placeholders in a branch or commit template; unknown
+/// placeholders are kept as written
+fn render_git_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, *value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Conventional-commit type for a task, from the first tag with a known type
+/// (`git.commit_types` first, then built-in names such as `bug` → `fix`);
+/// "feat" otherwise
+fn commit_type(task: &Task, config: &GitConfig) -> String {
+    let builtin = |tag: &str| match tag {
+        "bug" | "bugfix" | "fix" | "hotfix" => Some("fix"),
+        "docs" | "documentation" => Some("docs"),
+        "refactor" | "refactoring" => Some("refactor"),
+        "chore" | "maintenance" => Some("chore"),
+        "test" | "tests" | "testing" => Some("test"),
+        "perf" | "performance" => Some("perf"),
+        "ci" => Some("ci"),
+        "build" => Some("build"),
+        "style" => Some("style"),
+        _ => None,
+    };
+    task.tags
+        .iter()
+        .flatten()
+        .find_map(|tag| {
+            let tag = tag.to_lowercase();
+            config
+                .commit_types
+                .iter()
+                .find(|(name, _)| name.to_lowercase() == tag)
+                .map(|(_, kind)| kind.clone())
+                .or_else(|| builtin(&tag).map(str::to_string))
+        })
+        .unwrap_or_else(|| "feat".to_string())
+}
+
+/// Branch name for a task from `git.branch_template`
+fn task_branch_name(task: &Task, id: &str, config: &Config) -> String {
+    let template = config
+        .git
+        .branch_template
+        .as_deref()
+        .unwrap_or("{prefix}{id}-{slug}");
+    render_git_template(
+        template,
+        &[
+            ("prefix", &config.git.branch_prefix),
+            ("id", id),
+            ("slug", &slugify(&task.title, &config.slug)),
+            ("type", &commit_type(task, &config.git)),
+            ("project", task.project.as_deref().unwrap_or_default()),
+        ],
+    )
+}
+
+/// Commit message and pull request title for a task from `git.commit_template`
+fn commit_subject(task: &Task, config: &GitConfig) -> String {
+    let template = config
+        .commit_template
+        .as_deref()
+        .unwrap_or("{type}: {title} (task #{id})");
+    render_git_template(
+        template,
+        &[
+            ("type", &commit_type(task, config)),
+            ("title", &task.title),
+            ("id", &task.id),
+            ("project", task.project.as_deref().unwrap_or_default()),
+        ],
+    )
+}
+
+fn git_start_branch(task_id: String, resume: bool, config: &Config) -> Result<()> {
+    // First, check if we're in a git repository
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    // Get the task details
+    let tasks = load_tasks(config)?;
+    let task = find_task(tasks, &task_id)?;
+
+    // Create branch name from task
+    let branch_name = task_branch_name(&task.task, &task_id, config);
+
+    // A branch recorded on the task, or one with the expected name, is picked up again
+    let base = base_branch(config);
+    let existing = match task.task.branch.clone() {
+        Some(branch) if branch_exists(&branch)? => Some(branch),
+        _ if branch_exists(&branch_name)? => Some(branch_name.clone()),
+        _ => None,
+    };
+    if let Some(branch) = existing {
+        return resume_task_branch(&task, &branch, &base, config);
+    }
+    if resume {
+        return Err(anyhow::anyhow!(
+            "Task {} has no branch to resume; run `mdtasks git-start {}` to create one",
+            task_id,
+            task_id
+        ));
+    }
+
+    // Check if we're on the base branch
+    let current_branch = get_current_branch()?;
+    if current_branch != base {
+        return Err(anyhow::anyhow!(
+            "Must be on {} to start a task branch (use --base to start from another branch). Current branch: {}",
+            base,
+            current_branch
+        ));
+    }
+
+    // Check if there are unstaged changes and warn
+    let has_unstaged = has_uncommitted_changes()?;
+    if has_unstaged {
+        println!("⚠️  Warning: You have unstaged changes that will be auto-stashed and restored");
+    }
+
+    // Pull latest changes from the base branch with auto-stash (keeps changes)
+    if config.git.no_pull {
+        println!("⏭️  Skipping pull of {} (offline)", base);
+    } else {
+        println!("🔄 Pulling latest changes from {}...", base);
+        run_git_command(&["pull", "--rebase", "--autostash", "origin", &base])?;
+    }
+
+    // Create and checkout new branch
+    println!("🌿 Creating branch: {}", branch_name);
+    create_branch(&branch_name)?;
+
+    activate_task_branch(&task, &branch_name, config)?;
+    println!(
+        "✅ Started work on task {} in branch '{}'",
+        task_id, branch_name
+    );
+    println!("📝 Task: {}", task.task.title);
+
+    Ok(())
+}
+
+/// Check out a task's existing branch and rebase it onto the latest base branch
+fn resume_task_branch(task: &TaskFile, branch: &str, base: &str, config: &Config) -> Result<()> {
+    println!("🔁 Resuming existing branch: {}", branch);
+    if has_uncommitted_changes()? {
+        println!("⚠️  Warning: You have unstaged changes that will be auto-stashed and restored");
+    }
+
+    // Offline, the branch is rebased onto the local base branch instead
+    let onto = if config.git.no_pull {
+        base.to_string()
+    } else {
+        println!("🔄 Fetching latest changes from {}...", base);
+        run_git_command(&["fetch", "origin", base])?;
+        format!("origin/{}", base)
+    };
+    if get_current_branch()? != branch {
+        checkout_branch(branch)?;
+    }
+
+    println!("🔄 Rebasing onto {}...", onto);
+    if let Err(e) = run_git_command(&["rebase", "--autostash", &onto]) {
+        return Err(anyhow::anyhow!(
+            "{:#}\nResolve the conflicts and run `git rebase --continue` (or `git rebase --abort` to keep the branch as it was)",
+            e
+        ));
+    }
+
+    // The branch may hold a newer version of the task file
+    let task = find_task(load_tasks(config)?, &task.task.id)?;
+    activate_task_branch(&task, branch, config)?;
+    println!(
+        "✅ Resumed work on task {} in branch '{}'",
+        task.task.id, branch
+    );
+    println!("📝 Task: {}", task.task.title);
+
+    Ok(())
+}
+
+/// Mark a pending task active and record the branch it is worked on in
+fn activate_task_branch(task: &TaskFile, branch: &str, config: &Config) -> Result<()> {
+    if task.task.status.as_deref() == Some("pending") {
+        println!("🚀 Marking task {} as active", task.task.id);
+        // In-process, so --root, --tasks-dir, and --context apply
+        ensure_task_editable(&task.task.id, false, config)?;
+        check_wip_limit(&task.task.id, false, config)?;
+        mark_task_start(task.task.id.clone(), config)?;
+    }
+
+    // Record the branch so git-done and git-status find the task
+    if task.task.branch.as_deref() != Some(branch) {
+        set_task_field(task.task.id.clone(), "branch", branch.to_string(), config)?;
+    }
+    Ok(())
+}
+
+fn git_switch_task(task_id: String, note: Option<String>, config: &Config) -> Result<()> {
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    let tasks = load_tasks(config)?;
+    let task = find_task(tasks, &task_id)?;
+
+    let branch_name = task
+        .task
+        .branch
+        .clone()
+        .unwrap_or_else(|| task_branch_name(&task.task, &task_id, config));
+    if !branch_exists(&branch_name)? {
+        return Err(anyhow::anyhow!(
+            "Branch '{}' does not exist; use `mdtasks git-start {}` to create it",
+            branch_name,
+            task_id
+        ));
+    }
+
+    let current_branch = get_current_branch()?;
+    if current_branch == branch_name {
+        println!("ℹ️  Already on branch '{}'", branch_name);
+        return Ok(());
+    }
+
+    // Ask where we left off on the active task we're switching away from
+    let leaving = find_task_for_branch(&current_branch, config)
+        .ok()
+        .flatten()
+        .filter(|tf| tf.task.id != task_id && tf.task.status.as_deref() == Some("active"));
+    let note = match (&leaving, note) {
+        (Some(_), Some(note)) => Some(note),
+        (Some(leaving), None) if std::io::IsTerminal::is_terminal(&std::io::stdin()) => {
+            print!(
+                "📝 Where did you leave off on task {} (Enter to skip)? ",
+                leaving.task.id
+            );
+            use std::io::{self, Write};
+            io::stdout().flush()?;
+
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            Some(input.trim().to_string())
+        }
+        _ => None,
+    };
+
+    println!("🌿 Switching to branch: {}", branch_name);
+    checkout_branch(&branch_name)?;
+
+    // Written after the checkout so a dirty task file can't block the switch
+    if let (Some(leaving), Some(note)) = (leaving, note.filter(|n| !n.is_empty())) {
+        let text = format!("Left off: {}", note);
+        if let Err(e) = add_task_comment(leaving.task.id.clone(), text.clone(), None, config) {
+            println!(
+                "⚠️  Could not record note on task {} ({}): {}",
+                leaving.task.id, e, text
+            );
+        }
+    }
+
+    println!("✅ Now working on task {}: {}", task_id, task.task.title);
+
+    Ok(())
+}
+
+fn is_gh_cli_available() -> Result<bool> {
+    let output = std::process::Command::new("gh")
+        .args(["--version"])
+        .output();
+
+    match output {
+        Ok(output) => Ok(output.status.success()),
+        Err(_) => Ok(false),
+    }
+}
+
+fn format_pr_body(task: &Task, task_content: &str) -> String {
+    let mut body = String::new();
+
+    // Add task description
+    body.push_str(&format!("## Task: {}\n\n", task.title));
+
+    // Add task details
+    if let Some(ref status) = task.status {
+        body.push_str(&format!("**Status:** {}\n", status));
+    }
+    if let Some(ref priority) = task.priority {
+        body.push_str(&format!("**Priority:** {}\n", priority));
+    }
+    if let Some(ref tags) = task.tags {
+        body.push_str(&format!("**Tags:** {}\n", tags.join(", ")));
+    }
+    if let Some(ref project) = task.project {
+        body.push_str(&format!("**Project:** {}\n", project));
+    }
+
+    body.push('\n');
+
+    // Add task content (checklist, notes, etc.)
+    if !task_content.trim().is_empty() {
+        body.push_str("## Task Details\n\n");
+        body.push_str(task_content);
+    }
+
+    body
+}
+
+fn create_github_pr(
+    _branch_name: &str,
+    task: &Task,
+    task_content: &str,
+    config: &GitConfig,
+    draft: bool,
+    reviewers: Option<String>,
+    labels: Option<String>,
+) -> Result<String> {
+    // Check if GitHub CLI is available
+    if !is_gh_cli_available()? {
+        return Err(anyhow::anyhow!(
+            "GitHub CLI (gh) is not installed. Please install it to create PRs automatically.\n\
+            Visit: https://cli.github.com/"
+        ));
+    }
+
+    // Build PR title
+    let pr_title = commit_subject(task, config);
+
+    // Build PR body
+    let pr_body = format_pr_body(task, task_content);
+
+    // Build gh pr create command
+    let mut args = vec!["pr", "create", "--title", &pr_title, "--body", &pr_body];
+
+    // Add draft flag if requested
+    if draft || config.pr_draft {
+        args.push("--draft");
+    }
+
+    // Add reviewers
+    let reviewers_list =
+        reviewers.or_else(|| config.pr_default_reviewers.as_ref().map(|r| r.join(",")));
+    if let Some(ref reviewers_str) = reviewers_list {
+        args.extend(&["--reviewer", reviewers_str]);
+    }
+
+    // Add labels (only if explicitly provided via command line)
+    if let Some(ref labels_str) = labels {
+        args.extend(&["--label", labels_str]);
+    }
+
+    // Execute the command
+    let output = std::process::Command::new("gh")
+        .args(&args)
+        .output()
+        .context("Failed to run gh pr create command")?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("Failed to create PR: {}", error_msg));
+    }
+
+    // Extract PR URL from output
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let pr_url = output_str.trim().to_string();
+
+    Ok(pr_url)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn git_done_branch(
+    message: Option<String>,
+    no_pr: bool,
+    draft: bool,
+    reviewers: Option<String>,
+    labels: Option<String>,
+    switch_to_main: bool,
+    merge: &MergeArgs,
+    config: &Config,
+) -> Result<()> {
+    // Check if we're in a git repository
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    let strategy = if merge.squash {
+        "squash"
+    } else {
+        config.git.merge_strategy.as_deref().unwrap_or("no-ff")
+    };
+    if !["no-ff", "ff-only", "squash", "rebase"].contains(&strategy) {
+        return Err(anyhow::anyhow!(
+            "Unknown git.merge_strategy '{}' (expected no-ff, ff-only, squash, or rebase)",
+            strategy
+        ));
+    }
+
+    let current_branch = get_current_branch()?;
+
+    // Get task details (a recorded branch wins over the branch name prefix)
+    let task = find_task_for_branch(&current_branch, config)?.context(format!(
+        "Not on a task branch. Current branch: {}",
+        current_branch
+    ))?;
+    let task_id = task.task.id.as_str();
+
+    // Mark task as done first (so the task file update gets committed); a
+    // rerun after a failed push finds it done already
+    if task.task.status.as_deref() == Some("done") {
+        println!("✅ Task {} is already done", task_id);
+    } else {
+        println!("✅ Marking task {} as done", task_id);
+        ensure_task_editable(task_id, false, config)?;
+        mark_task_done(task_id.to_string(), None, config)?;
+    }
+
+    // Commit message
+    let commit_msg = message.unwrap_or_else(|| commit_subject(&task.task, &config.git));
+
+    // Add all changes and commit (only if there are changes)
+    // Under --dry-run the task file was not actually marked done
+    if dry_run() || has_uncommitted_changes()? {
+        println!("📝 Committing changes...");
+        run_git_command(&["add", "."])?;
+        run_git_command(&["commit", "-m", &commit_msg])?;
+    } else {
+        println!("📝 No changes to commit");
+    }
+
+    if merge.merge || merge.squash {
+        merge_task_branch(
+            &current_branch,
+            &base_branch(config),
+            strategy,
+            &commit_msg,
+            !merge.no_delete_branch,
+            &config.git,
+        )?;
+        println!(
+            "🎉 Successfully finished task {}: {}",
+            task_id, task.task.title
+        );
+        return Ok(());
+    }
+
+    // Push the task branch to remote
+    if config.git.no_push {
+        println!(
+            "⏭️  Not pushing (offline); run `git push -u origin {}` when you're back online",
+            current_branch
+        );
+    } else {
+        println!("🚀 Pushing task branch to remote...");
+        if let Err(e) = run_git_command(&["push", "origin", &current_branch]) {
+            return Err(anyhow::anyhow!(
+                "{:#}\nTask {} is done and committed on {}; push it with `git push -u origin {}` once the remote is reachable (or finish offline with --no-push)",
+                e,
+                task_id,
+                current_branch,
+                current_branch
+            ));
+        }
+    }
+
+    // Create PR if enabled and not skipped
+    let pr_url = if config.git.no_push {
+        None
+    } else if dry_run() && !no_pr && config.git.pr_enabled {
+        let title = commit_subject(&task.task, &config.git);
+        println!(
+            "🔧 Would run: gh {}",
+            quote_args(&["pr", "create", "--title", &title])
+        );
+        None
+    } else if !no_pr && config.git.pr_enabled {
+        println!("🔗 Creating pull request...");
+        match create_github_pr(
+            &current_branch,
+            &task.task,
+            &task.content,
+            &config.git,
+            draft || config.git.pr_draft,
+            reviewers,
+            labels,
+        ) {
+            Ok(url) => {
+                println!("✅ Pull request created: {}", url);
+                Some(url)
+            }
+            Err(e) => {
+                println!("⚠️  Failed to create PR: {}", e);
+                None
+            }
+        }
+    } else if no_pr {
+        println!("⏭️  Skipping PR creation (--no-pr flag)");
+        None
+    } else {
+        println!("⏭️  PR creation disabled in config");
+        None
+    };
+
+    // Switch back to the base branch if requested
+    if switch_to_main || config.git.pr_switch_to_main {
+        let base = base_branch(config);
+        println!("🔄 Switching back to {}...", base);
+        checkout_branch(&base)?;
+        println!("✅ Switched to {}", base);
+    }
+
+    println!(
+        "🎉 Successfully finished task {}: {}",
+        task_id, task.task.title
+    );
+    if !config.git.no_push && !dry_run() {
+        println!("✅ Changes pushed to remote repository");
+    }
+
+    if let Some(url) = pr_url {
+        println!("🔗 Pull request: {}", url);
+    }
+
+    Ok(())
+}
+
+/// Merge a finished task branch into the up-to-date base branch with the given
+/// strategy, push it, and optionally delete the task branch locally and on origin.
+/// `git.no_pull`/`git.no_push` keep it all local.
+fn merge_task_branch(
+    branch: &str,
+    base: &str,
+    strategy: &str,
+    message: &str,
+    delete: bool,
+    config: &GitConfig,
+) -> Result<()> {
+    if strategy == "rebase" {
+        println!("🔄 Rebasing {} onto {}...", branch, base);
+        if config.no_pull {
+            run_git_command(&["rebase", base])?;
+        } else {
+            run_git_command(&["fetch", "origin", base])?;
+            run_git_command(&["rebase", &format!("origin/{}", base)])?;
+        }
+    }
+
+    if config.no_pull {
+        println!("🔄 Switching to {} (offline, not pulling)...", base);
+        checkout_branch(base)?;
+    } else {
+        println!("🔄 Switching to {} and pulling latest changes...", base);
+        checkout_branch(base)?;
+        run_git_command(&["pull", "--rebase", "--autostash", "origin", base])?;
+    }
+
+    println!("🔀 Merging {} into {} ({})...", branch, base, strategy);
+    let merged = match strategy {
+        "squash" => run_git_command(&["merge", "--squash", branch])
+            .and_then(|_| run_git_command(&["commit", "-m", message])),
+        "ff-only" | "rebase" => run_git_command(&["merge", "--ff-only", branch]),
+        _ => run_git_command(&["merge", "--no-ff", "--no-edit", branch]),
+    };
+    if let Err(e) = merged {
+        return Err(anyhow::anyhow!(
+            "{:#}\nResolve the merge on {} (or `git merge --abort`), then push it yourself",
+            e,
+            base
+        ));
+    }
+
+    if config.no_push {
+        println!(
+            "⏭️  Not pushing (offline); run `git push origin {}` when you're back online",
+            base
+        );
+    } else {
+        println!("🚀 Pushing {}...", base);
+        if let Err(e) = run_git_command(&["push", "origin", base]) {
+            return Err(anyhow::anyhow!(
+                "{:#}\n{} is merged into {} locally but not pushed, and the branch was kept; run `git push origin {}` once the remote is reachable (or finish offline with --no-push)",
+                e,
+                branch,
+                base,
+                base
+            ));
+        }
+    }
+
+    if delete {
+        // A squashed branch is not an ancestor of main, so -d would refuse it
+        let flag = if strategy == "squash" { "-D" } else { "-d" };
+        run_git_command(&["branch", flag, branch])?;
+        if config.no_push {
+            let tracked = run_git_command(&[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("refs/remotes/origin/{}", branch),
+            ])
+            .is_ok();
+            if tracked {
+                println!(
+                    "💡 origin/{} was left alone; delete it later with `git push origin --delete {}`",
+                    branch, branch
+                );
+            }
+        } else {
+            let on_remote = run_git_command(&["ls-remote", "--heads", "origin", branch])
+                .is_ok_and(|heads| !heads.trim().is_empty());
+            if on_remote {
+                run_git_command(&["push", "origin", "--delete", branch])?;
+            }
+        }
+        println!("🧹 Deleted branch {}", branch);
+    }
+
+    println!("✅ Merged {} into {}", branch, base);
+    Ok(())
+}
+
+/// Find the task worked on in a branch: a task recording the branch in its
+/// front-matter wins, then one whose templated branch name matches, otherwise
+/// the ID is parsed from the branch name prefix
+fn find_task_for_branch(branch: &str, config: &Config) -> Result<Option<TaskFile>> {
+    let tasks = load_tasks(config)?;
+
+    if tasks
+        .iter()
+        .any(|tf| tf.task.branch.as_deref() == Some(branch))
+    {
+        return Ok(tasks
+            .into_iter()
+            .find(|tf| tf.task.branch.as_deref() == Some(branch)));
+    }
+
+    let templated = |tf: &TaskFile| task_branch_name(&tf.task, &tf.task.id, config) == branch;
+    if tasks.iter().any(templated) {
+        return Ok(tasks.into_iter().find(templated));
+    }
+
+    let Some(rest) = branch.strip_prefix(&config.git.branch_prefix) else {
+        return Ok(None);
+    };
+    // Prefixed IDs contain a dash themselves, so prefer the longest ID the name starts with
+    let task_id = tasks
+        .iter()
+        .map(|tf| tf.task.id.as_str())
+        .filter(|id| {
+            rest.strip_prefix(id)
+                .is_some_and(|r| r.is_empty() || r.starts_with('-'))
+        })
+        .max_by_key(|id| id.len())
+        .unwrap_or_else(|| rest.split('-').next().unwrap_or(rest))
+        .to_string();
+
+    find_task(tasks, &task_id).map(Some)
+}
+
+fn git_adopt_branch(id: Option<String>, config: &Config) -> Result<()> {
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    let current_branch = get_current_branch()?;
+    if current_branch.is_empty() || current_branch == base_branch(config) {
+        return Err(anyhow::anyhow!(
+            "Switch to the task's branch before adopting it. Current branch: {}",
+            current_branch
+        ));
+    }
+
+    // Without an explicit ID, use the first number in the branch name
+    let task_id = match id {
+        Some(id) => id,
+        None => {
+            let number: String = current_branch
+                .chars()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            let tasks = load_tasks(config)?;
+            tasks
+                .into_iter()
+                .find(|tf| {
+                    !number.is_empty()
+                        && (tf.task.id == number
+                            || tf.task.id.trim_start_matches('0') == number.trim_start_matches('0'))
+                })
+                .map(|tf| tf.task.id)
+                .context(format!(
+                    "Could not determine the task from branch '{}'; pass the task ID",
+                    current_branch
+                ))?
+        }
+    };
+
+    set_task_field(task_id.clone(), "branch", current_branch.clone(), config)?;
+    println!("🔗 Linked branch '{}' to task {}", current_branch, task_id);
+
+    Ok(())
+}
+
+fn git_status(config: &Config) -> Result<()> {
+    // Check if we're in a git repository
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    let current_branch = get_current_branch()?;
+    println!("🌿 Current branch: {}", current_branch);
+
+    match find_task_for_branch(&current_branch, config) {
+        Ok(Some(task)) => {
+            println!("📋 Current task: {} - {}", task.task.id, task.task.title);
+            println!(
+                "📊 Status: {}",
+                task.task.status.as_deref().unwrap_or("unknown")
+            );
+            println!(
+                "⭐ Priority: {}",
+                task.task.priority.as_deref().unwrap_or("none")
+            );
+        }
+        Ok(None) => println!("📋 No active task branch"),
+        Err(e) => println!("⚠️ {}", e),
+    }
+    if let Some(focused) = read_focus() {
+        match find_task(load_tasks(config)?, &focused) {
+            Ok(task_file) => println!("🎯 Focus: {} - {}", task_file.task.id, task_file.task.title),
+            Err(_) => println!("🎯 Focus: {} (no such task)", focused),
+        }
+    }
+
+    // Show git status
+    println!("\n📊 Git status:");
+    let status = git_status_lines()?;
+    if status.is_empty() {
+        println!("  (clean)");
+    }
+    for line in status {
+        println!("  {}", line);
+    }
+
+    Ok(())
+}
+
+// List columns
+
+/// Column shown by `list --columns`. To add a computed column, implement this
+/// trait and register the type in `list_columns`.
+trait ListColumn {
+    /// Name used in `--columns`; the header is its uppercase form
+    fn name(&self) -> &str;
+
+    /// Minimum display width
+    fn width(&self) -> usize;
+
+    /// Rendered value for a task and its markdown body
+    fn value(&self, task: &Task, content: &str) -> String;
+}
+
+/// Column showing a front-matter field as stored
+struct FieldColumn {
+    name: &'static str,
+    width: usize,
+}
+
+impl ListColumn for FieldColumn {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn value(&self, task: &Task, _content: &str) -> String {
+        match self.name {
+            "id" => task.id.clone(),
+            "status" => task.status.clone().unwrap_or_else(|| "unknown".to_string()),
+            "priority" => task
+                .priority
+                .clone()
+                .unwrap_or_else(|| "medium".to_string()),
+            field => task_field_value(task, field),
+        }
+    }
+}
+
+/// Example computed column: days since the task was created
+struct AgeColumn;
+
+impl ListColumn for AgeColumn {
+    fn name(&self) -> &str {
+        "age"
+    }
+
+    fn width(&self) -> usize {
+        5
+    }
+
+    fn value(&self, task: &Task, _content: &str) -> String {
+        let today = local_today();
+        task.created
+            .as_deref()
+            .and_then(parse_task_date)
+            .map(|created| format!("{}d", (today - created).num_days()))
+            .unwrap_or_default()
+    }
+}
+
+/// Example computed column: checked vs. total subtasks
+struct SubtasksColumn;
+
+impl ListColumn for SubtasksColumn {
+    fn name(&self) -> &str {
+        "subtasks"
+    }
+
+    fn width(&self) -> usize {
+        8
+    }
+
+    fn value(&self, _task: &Task, content: &str) -> String {
+        match subtask_progress(content) {
+            (_, 0) => String::new(),
+            (done, total) => format!("{}/{}", done, total),
+        }
+    }
+}
+
+/// Computed column: urgency score from due date, priority, age, and status
+struct UrgencyColumn<'a> {
+    weights: &'a UrgencyConfig,
+    today: chrono::NaiveDate,
+}
+
+impl ListColumn for UrgencyColumn<'_> {
+    fn name(&self) -> &str {
+        "urgency"
+    }
+
+    fn width(&self) -> usize {
+        7
+    }
+
+    fn value(&self, task: &Task, _content: &str) -> String {
+        format!("{:.1}", urgency_score(task, self.weights, self.today).0)
+    }
+}
+
+/// Urgency of a task and the contributions making it up; finished tasks score 0
+fn urgency_score(
+    task: &Task,
+    weights: &UrgencyConfig,
+    today: chrono::NaiveDate,
+) -> (f64, Vec<(&'static str, f64)>) {
+    if is_finished(task) {
+        return (0.0, Vec::new());
+    }
+
+    let mut parts = Vec::new();
+    if let Some(due) = task.due.as_deref().and_then(parse_task_date) {
+        // 1.0 at a week overdue down to 0.2 at two weeks out
+        let days_left = (due - today).num_days().clamp(-7, 14) as f64;
+        let factor = 1.0 - (days_left + 7.0) / 21.0 * 0.8;
+        parts.push(("due", weights.due * factor));
+    }
+    let priority = match priority_rank(task.priority.as_deref()) {
+        Priority::High => weights.high,
+        Priority::Medium => weights.medium,
+        Priority::Low => weights.low,
+    };
+    parts.push(("priority", priority));
+    if let Some(created) = task.created.as_deref().and_then(parse_task_date) {
+        let age = ((today - created).num_days() as f64 / 365.0).clamp(0.0, 1.0);
+        parts.push(("age", weights.age * age));
+    }
+    if task.status.as_deref() == Some("active") {
+        parts.push(("active", weights.active));
+    }
+
+    (parts.iter().map(|(_, value)| value).sum(), parts)
+}
+
+/// All columns available to `list --columns`
+fn list_columns(config: &Config) -> Vec<Box<dyn ListColumn + '_>> {
+    // Room for "PREFIX-<id> " with the longest configured prefix and ID scheme
+    let id_length = match config.tasks.id_scheme.as_str() {
+        "ulid" => 26,
+        "uuid" => 36,
+        _ => 3,
+    };
+    let id_width = std::iter::once(&config.tasks.id_prefix)
+        .chain(config.tasks.project_prefixes.values())
+        .filter(|prefix| !prefix.is_empty())
+        .map(|prefix| prefix.chars().count() + 1)
+        .max()
+        .unwrap_or(0)
+        + id_length
+        + 1;
+    let fields = [
+        ("id", id_width),
+        ("status", 12),
+        ("priority", 8),
+        ("title", 50),
+        ("tags", 20),
+        ("project", 12),
+        ("created", 10),
+        ("due", 16),
+        ("started", 10),
+        ("completed", 10),
+        ("scheduled", 10),
+        ("estimate", 8),
+        ("branch", 30),
+        ("parent", 6),
+        ("depends_on", 12),
+        ("wait_until", 10),
+        ("postponed", 9),
+        ("milestone", 12),
+        ("assignee", 12),
+        ("delegated_to", 12),
+        ("follow_up", 10),
+        ("updated", 10),
+    ];
+
+    let mut columns: Vec<Box<dyn ListColumn + '_>> = fields
+        .into_iter()
+        .map(|(name, width)| Box::new(FieldColumn { name, width }) as Box<dyn ListColumn>)
+        .collect();
+    columns.push(Box::new(AgeColumn));
+    columns.push(Box::new(SubtasksColumn));
+    columns.push(Box::new(UrgencyColumn {
+        weights: &config.urgency,
+        today: local_today(),
+    }));
+    columns
+}
+
+/// Resolve a comma-separated `--columns` value
+fn select_list_columns<'a>(
+    names: &str,
+    config: &'a Config,
+) -> Result<Vec<Box<dyn ListColumn + 'a>>> {
+    let mut available = list_columns(config);
+    let known = available
+        .iter()
+        .map(|c| c.name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut selected: Vec<Box<dyn ListColumn + 'a>> = Vec::new();
+    for name in names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if selected.iter().any(|c| c.name() == name) {
+            return Err(anyhow::anyhow!("Duplicate column: {}", name));
+        }
+        let index = available
+            .iter()
+            .position(|c| c.name() == name)
+            .context(format!("Unknown column: {} (available: {})", name, known))?;
+        selected.push(available.remove(index));
+    }
+
+    if selected.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No columns selected (available: {})",
+            known
+        ));
+    }
+
+    Ok(selected)
+}
+
+/// Count (checked, total) items in the subtask section of a task body
+fn subtask_progress(content: &str) -> (usize, usize) {
+    let section_start = match find_subtask_section(content) {
+        Some((_, start)) => start,
+        None => return (0, 0),
+    };
+
+    let mut done = 0;
+    let mut total = 0;
+    for line in content.lines().skip(section_start + 1) {
+        if is_leaving_subtask_section(line) {
+            break;
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]") {
+            done += 1;
+            total += 1;
+        } else if trimmed.starts_with("- [") {
+            total += 1;
+        }
+    }
+
+    (done, total)
+}
+
+// Milestones
+
+/// Dates recorded for a milestone in `<tasks dir>/milestones.toml`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct MilestoneInfo {
+    start: Option<String>,
+    due: Option<String>,
+    closed: Option<String>,
+}
+
+fn milestones_path(config: &Config) -> PathBuf {
+    Path::new(&config.tasks.dir).join("milestones.toml")
+}
+
+fn read_milestones(config: &Config) -> Result<std::collections::BTreeMap<String, MilestoneInfo>> {
+    let path = milestones_path(config);
+    if !path.exists() {
+        return Ok(std::collections::BTreeMap::new());
+    }
+    let content =
+        std::fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).context(format!("Failed to parse {}", path.display()))
+}
+
+fn write_milestones(
+    milestones: &std::collections::BTreeMap<String, MilestoneInfo>,
+    config: &Config,
+) -> Result<()> {
+    if !dry_run() {
+        std::fs::create_dir_all(&config.tasks.dir)?;
+    }
+    let content = toml::to_string(milestones).context("Failed to serialize milestones")?;
+    write_task_file(&milestones_path(config).to_string_lossy(), &content, config)
+}
+
+/// Every milestone named by a task or recorded in milestones.toml
+fn all_milestones(
+    tasks: &[TaskFile],
+    config: &Config,
+) -> Result<std::collections::BTreeMap<String, MilestoneInfo>> {
+    let mut milestones = read_milestones(config)?;
+    for task_file in tasks {
+        if let Some(ref milestone) = task_file.task.milestone {
+            milestones.entry(milestone.clone()).or_default();
+        }
+    }
+    Ok(milestones)
+}
+
+/// The open milestone running today, else the open one due soonest
+fn current_milestone(
+    milestones: &std::collections::BTreeMap<String, MilestoneInfo>,
+    today: chrono::NaiveDate,
+) -> Option<String> {
+    let open: Vec<(&String, &MilestoneInfo)> = milestones
+        .iter()
+        .filter(|(_, info)| info.closed.is_none())
+        .collect();
+    let date = |value: &Option<String>| value.as_deref().and_then(parse_task_date);
+    open.iter()
+        .find(|(_, info)| {
+            date(&info.start).is_some_and(|start| start <= today)
+                && date(&info.due).is_none_or(|due| due >= today)
+        })
+        .or_else(|| {
+            open.iter()
+                .filter(|(_, info)| date(&info.due).is_some())
+                .min_by_key(|(_, info)| date(&info.due))
+        })
+        .or(if open.len() == 1 { open.first() } else { None })
+        .map(|(name, _)| name.to_string())
+}
+
+fn milestone_tasks<'a>(tasks: &'a [TaskFile], name: &str) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| task.milestone.as_deref() == Some(name))
+        .collect()
+}
+
+fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done * width).checked_div(total).unwrap_or(0);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn list_milestones(config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let milestones = all_milestones(&tasks, config)?;
+    if milestones.is_empty() {
+        println!("No milestones yet. Plan a task with `mdtasks set-milestone <id> <name>`.");
+        return Ok(());
+    }
+    let today = local_today();
+    let current = current_milestone(&milestones, today);
+
+    println!("🏁 Milestones:");
+    for (name, info) in &milestones {
+        let tasks = milestone_tasks(&tasks, name);
+        let done = tasks.iter().filter(|task| is_finished(task)).count();
+        let state = match (&info.closed, current.as_deref() == Some(name.as_str())) {
+            (Some(closed), _) => format!("closed {}", closed),
+            (None, true) => "current".to_string(),
+            (None, false) => "open".to_string(),
+        };
+        let due = info
+            .due
+            .as_deref()
+            .map(|due| format!(", due {}", due))
+            .unwrap_or_default();
+        println!(
+            "  {:<16} {} {:>3}/{:<3} {}{}",
+            name,
+            progress_bar(done, tasks.len(), 10),
+            done,
+            tasks.len(),
+            state,
+            due
+        );
+    }
+    Ok(())
+}
+
+fn show_milestone(name: Option<String>, config: &Config) -> Result<()> {
+    let all = load_tasks(config)?;
+    let milestones = all_milestones(&all, config)?;
+    let today = local_today();
+    let name = match name {
+        Some(name) => name,
+        None => current_milestone(&milestones, today).context(
+            "No current milestone; name one or record its dates with `mdtasks milestone new`",
+        )?,
+    };
+    let info = milestones
+        .get(&name)
+        .context(format!("Unknown milestone '{}'", name))?;
+    let tasks = milestone_tasks(&all, &name);
+    let done = tasks.iter().filter(|task| is_finished(task)).count();
+
+    let mut header = format!("🏁 Milestone {}", name);
+    match (&info.start, &info.due) {
+        (Some(start), Some(due)) => header.push_str(&format!(" ({} → {})", start, due)),
+        (None, Some(due)) => header.push_str(&format!(" (due {})", due)),
+        (Some(start), None) => header.push_str(&format!(" (from {})", start)),
+        (None, None) => {}
+    }
+    if let Some(ref closed) = info.closed {
+        header.push_str(&format!(", closed {}", closed));
+    }
+    println!("{}", header);
+    println!(
+        "{} {}/{} done",
+        progress_bar(done, tasks.len(), 20),
+        done,
+        tasks.len()
+    );
+
+    let mut sorted = tasks.clone();
+    sorted.sort_by_key(|task| (is_finished(task), priority_rank(task.priority.as_deref())));
+    for task in &sorted {
+        let marker = if is_finished(task) { "✅" } else { "•" };
+        let due = task
+            .due
+            .as_deref()
+            .map(|due| format!(" (due {})", due))
+            .unwrap_or_default();
+        println!(
+            "  {} {} {:<8} {}{}",
+            marker,
+            task.id,
+            task.status.as_deref().unwrap_or("pending"),
+            task.title,
+            due
+        );
+    }
+
+    // Burndown from the milestone's start (or its oldest task) to today or its end
+    let date = |value: &Option<String>| value.as_deref().and_then(parse_task_date);
+    let start = date(&info.start).or_else(|| tasks.iter().filter_map(|t| date(&t.created)).min());
+    let end = [date(&info.closed), date(&info.due), Some(today)]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(today);
+    if let Some(start) = start.filter(|start| *start <= end) {
+        let points = burndown_series(&tasks, start, end, |_| 1.0);
+        let max = points.iter().map(|(_, open)| *open).fold(0.0, f64::max);
+        println!("\n📉 Burndown (open tasks):");
+        for (day, open) in points {
+            let width = if max > 0.0 {
+                (open / max * 30.0).round() as usize
+            } else {
+                0
+            };
+            println!(
+                "  {}  {:<30} {}",
+                day.format("%a %m-%d"),
+                "█".repeat(width),
+                open
+            );
+        }
+    }
+    Ok(())
+}
+
+fn new_milestone(
+    name: &str,
+    start: Option<String>,
+    due: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    for date in start.iter().chain(due.iter()) {
+        if !is_iso_date(date) {
+            return Err(anyhow::anyhow!(
+                "Invalid date '{}' (expected YYYY-MM-DD)",
+                date
+            ));
+        }
+    }
+    let _lock = lock_tasks(config)?;
+    let mut milestones = read_milestones(config)?;
+    let info = milestones.entry(name.to_string()).or_default();
+    info.start = start.or_else(|| Some(local_now().format("%Y-%m-%d").to_string()));
+    if due.is_some() {
+        info.due = due;
+    }
+    let summary = format!(
+        "🏁 Milestone {}: {} → {}",
+        name,
+        info.start.as_deref().unwrap_or("?"),
+        info.due.as_deref().unwrap_or("open-ended")
+    );
+    write_milestones(&milestones, config)?;
+    println!("{}", summary);
+    Ok(())
+}
+
+fn close_milestone(name: &str, yes: bool, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let milestones = all_milestones(&tasks, config)?;
+    let info = milestones
+        .get(name)
+        .context(format!("Unknown milestone '{}'", name))?;
+    if let Some(ref closed) = info.closed {
+        println!("🏁 Milestone {} was already closed on {}", name, closed);
+        return Ok(());
+    }
+
+    let open: Vec<&Task> = milestone_tasks(&tasks, name)
+        .into_iter()
+        .filter(|task| !is_finished(task))
+        .collect();
+    if !open.is_empty() {
+        println!("⚠️  Milestone {} has {} open task(s):", name, open.len());
+        for task in &open {
+            println!("  - {}: {}", task.id, task.title);
+        }
+        if !yes && !ask("❓ Close it anyway? (y/N): ")?.eq_ignore_ascii_case("y") {
+            println!("❌ Milestone left open");
+            return Ok(());
+        }
+    }
+
+    // The lock is only taken after the prompt, and the milestones are read
+    // again under it
+    let _lock = lock_tasks(config)?;
+    let mut milestones = all_milestones(&load_tasks(config)?, config)?;
+    let info = milestones
+        .get_mut(name)
+        .context(format!("Unknown milestone '{}'", name))?;
+    info.closed = Some(local_now().format("%Y-%m-%d").to_string());
+    write_milestones(&milestones, config)?;
+    println!("✅ Closed milestone {}", name);
+    Ok(())
+}
+
+// Roadmap
+
+/// Icon for a task's status in the roadmap
+fn status_icon(status: Option<&str>) -> &'static str {
+    match status.unwrap_or("pending") {
+        "done" => "✅",
+        "active" => "🔄",
+        "partial" => "◐",
+        "waiting" => "⏸️",
+        "cancelled" | "canceled" => "❌",
+        "archived" => "📦",
+        "pending" => "⬜",
+        _ => "•",
+    }
+}
+
+/// Outline of projects → milestones → tasks; milestones in due-date order,
+/// tasks by due date, then ID
+fn print_roadmap(
+    markdown: bool,
+    output: Option<&str>,
+    open_only: bool,
+    config: &Config,
+) -> Result<()> {
+    let markdown = markdown || output.is_some();
+    let all = load_tasks(config)?;
+    let milestones = all_milestones(&all, config)?;
+    let now = local_now();
+
+    let mut projects: std::collections::BTreeMap<Option<&str>, Vec<&Task>> =
+        std::collections::BTreeMap::new();
+    for task in all.iter().map(|tf| &tf.task) {
+        if !(open_only && is_finished(task)) {
+            projects
+                .entry(task.project.as_deref())
+                .or_default()
+                .push(task);
+        }
+    }
+    // Tasks without a project come last
+    let mut projects: Vec<_> = projects.into_iter().collect();
+    projects.sort_by_key(|(project, _)| (project.is_none(), *project));
+
+    let done_count = |tasks: &[&Task]| tasks.iter().filter(|task| is_finished(task)).count();
+    let mut lines = Vec::new();
+    if markdown {
+        lines.push("# Roadmap".to_string());
+        lines.push(String::new());
+        lines.push(format!("_Generated {}_", now.format("%Y-%m-%d")));
+    } else {
+        lines.push("🗺️  Roadmap".to_string());
+    }
+    if projects.is_empty() {
+        lines.push(String::new());
+        lines.push("No tasks.".to_string());
+    }
+
+    for (project, tasks) in &projects {
+        let name = project.unwrap_or("No project");
+        let progress = format!("{}/{} done", done_count(tasks), tasks.len());
+        lines.push(String::new());
+        if markdown {
+            lines.push(format!("## {} ({})", name, progress));
+        } else {
+            lines.push(format!("📁 {} ({})", name, progress));
+        }
+
+        let mut by_milestone: Vec<(Option<&str>, Vec<&Task>)> = Vec::new();
+        for task in tasks {
+            let milestone = task.milestone.as_deref();
+            match by_milestone.iter_mut().find(|(name, _)| *name == milestone) {
+                Some((_, group)) => group.push(task),
+                None => by_milestone.push((milestone, vec![task])),
+            }
+        }
+        let milestone_due = |name: Option<&str>| {
+            name.and_then(|name| milestones.get(name))
+                .and_then(|info| info.due.clone())
+        };
+        by_milestone.sort_by_key(|(name, _)| {
+            let due = milestone_due(*name);
+            (name.is_none(), due.is_none(), due, *name)
+        });
+
+        for (milestone, mut tasks) in by_milestone {
+            tasks.sort_by(|a, b| {
+                (a.due.is_none(), &a.due, &a.id).cmp(&(b.due.is_none(), &b.due, &b.id))
+            });
+            let mut heading = milestone.unwrap_or("No milestone").to_string();
+            if let Some(due) = milestone_due(milestone) {
+                heading.push_str(&format!(" — due {}", due));
+            }
+            heading.push_str(&format!(" ({}/{} done)", done_count(&tasks), tasks.len()));
+            if markdown {
+                lines.push(String::new());
+                lines.push(format!("### {}", heading));
+                lines.push(String::new());
+            } else {
+                lines.push(format!("  🏁 {}", heading));
+            }
+
+            for task in tasks {
+                let mut line = format!(
+                    "{} {} {}",
+                    status_icon(task.status.as_deref()),
+                    task.id,
+                    task.title
+                );
+                if let Some(ref due) = task.due {
+                    line.push_str(&format!(" — due {}", due));
+                    if is_overdue(task, &now) {
+                        line.push_str(" ⏰");
+                    }
+                }
+                lines.push(if markdown {
+                    format!("- {}", line)
+                } else {
+                    format!("    {}", line)
+                });
+            }
+        }
+    }
+
+    let text = lines.join("\n") + "\n";
+    match output {
+        Some(path) => {
+            std::fs::write(path, &text).context(format!("Failed to write {}", path))?;
+            println!("🗺️  Roadmap written to {}", path);
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+// Estimation accuracy
+
+/// Parse a logged duration such as "25m", "1h30m", "90s", or "1.5h" into seconds
+fn parse_logged_duration(value: &str) -> Option<u64> {
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in value.trim().to_lowercase().chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' | 's' => {
+                let amount: f64 = std::mem::take(&mut number).parse().ok()?;
+                seconds += amount
+                    * match c {
+                        'h' => 3600.0,
+                        'm' => 60.0,
+                        _ => 1.0,
+                    };
+            }
+            _ => return None,
+        }
+    }
+    (number.is_empty() && seconds > 0.0).then(|| seconds.round() as u64)
+}
+
+/// Total seconds in a task body's `## Time Log`, whose entries read
+/// "- <start> <duration> ..."; entries without a readable duration are skipped
+fn logged_seconds(content: &str) -> u64 {
+    content
+        .lines()
+        .skip_while(|line| line.trim() != "## Time Log")
+        .skip(1)
+        .take_while(|line| !is_leaving_subtask_section(line))
+        .filter_map(|line| {
+            let mut words = line.trim().strip_prefix("- ")?.split_whitespace();
+            words.next();
+            parse_logged_duration(words.next()?)
+        })
+        .sum()
+}
+
+/// Estimated against logged hours for tasks that have both, per task and
+/// project; the error is how far the time logged was off the estimate
+fn print_accuracy_report(all: bool, config: &Config) -> Result<()> {
+    // (task, estimated hours, actual hours)
+    let mut rows: Vec<(Task, f64, f64)> = load_tasks(config)?
+        .into_iter()
+        .filter(|tf| all || is_finished(&tf.task))
+        .filter_map(|tf| {
+            let estimate = tf.task.estimate.as_deref().and_then(parse_estimate_hours)?;
+            let actual = logged_seconds(&tf.content) as f64 / 3600.0;
+            (estimate > 0.0 && actual > 0.0).then_some((tf.task, estimate, actual))
+        })
+        .collect();
+    if rows.is_empty() {
+        println!(
+            "No {}tasks with both an estimate and logged time.",
+            if all { "" } else { "done " }
+        );
+        return Ok(());
+    }
+    rows.sort_by(|a, b| {
+        (a.0.project.is_none(), &a.0.project)
+            .cmp(&(b.0.project.is_none(), &b.0.project))
+            .then_with(|| a.0.id.cmp(&b.0.id))
+    });
+
+    let error = |estimate: f64, actual: f64| (actual - estimate) / estimate * 100.0;
+    println!(
+        "{:<6} {:<36} {:>9} {:>9} {:>8}",
+        "ID", "TITLE", "ESTIMATE", "ACTUAL", "ERROR"
+    );
+    println!("{}", "-".repeat(72));
+    for (task, estimate, actual) in &rows {
+        println!(
+            "{:<6} {:<36} {:>9} {:>9} {:>+7.0}%",
+            task.id,
+            task.title.chars().take(36).collect::<String>(),
+            format_hours(*estimate),
+            format_hours(*actual),
+            error(*estimate, *actual)
+        );
+    }
+
+    // Mean absolute error says how far off estimates are; the mean signed
+    // error whether they tend to be too low (positive) or too high
+    let summary = |rows: &[&(Task, f64, f64)]| {
+        let errors: Vec<f64> = rows.iter().map(|(_, e, a)| error(*e, *a)).collect();
+        let count = errors.len() as f64;
+        let estimated: f64 = rows.iter().map(|(_, e, _)| e).sum();
+        let actual: f64 = rows.iter().map(|(_, _, a)| a).sum();
+        format!(
+            "{} task(s), {} estimated, {} logged, average error {:.0}% (bias {:+.0}%)",
+            errors.len(),
+            format_hours(estimated),
+            format_hours(actual),
+            errors.iter().map(|e| e.abs()).sum::<f64>() / count,
+            errors.iter().sum::<f64>() / count
+        )
+    };
+
+    let mut projects: Vec<Option<&str>> =
+        rows.iter().map(|(t, _, _)| t.project.as_deref()).collect();
+    projects.dedup();
+    if projects.len() > 1 || projects[0].is_some() {
+        println!("\nBy project:");
+        for project in projects {
+            let in_project: Vec<_> = rows
+                .iter()
+                .filter(|(task, _, _)| task.project.as_deref() == project)
+                .collect();
+            println!(
+                "  {:<14} {}",
+                project.unwrap_or("(none)"),
+                summary(&in_project)
+            );
+        }
+    }
+    println!(
+        "\n🎯 Overall: {}",
+        summary(&rows.iter().collect::<Vec<_>>())
+    );
+    Ok(())
+}
+
+// Wiki links
+
+/// Task IDs referenced as `[[012]]` (or `[[012|label]]`) in a body, in order
+fn wiki_links(body: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("]]") else {
+            break;
+        };
+        let target = rest[..end].split('|').next().unwrap_or_default().trim();
+        if !target.is_empty() && !target.contains('\n') && !links.iter().any(|l| l == target) {
+            links.push(target.to_string());
+        }
+        rest = &rest[end + 2..];
+    }
+    links
+}
+
+/// Point `[[id]]` links in a body at new IDs where `moved` gives one, keeping
+/// any `|label`
+fn remap_wiki_links(body: &str, moved: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut remapped = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("[[") {
+        remapped.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("]]") else {
+            break;
+        };
+        let link = &rest[..end];
+        let (target, label) = match link.split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (link, None),
+        };
+        match moved(target.trim()).filter(|_| !link.contains('\n')) {
+            Some(id) => {
+                remapped.push_str(&id);
+                if let Some(label) = label {
+                    remapped.push('|');
+                    remapped.push_str(label);
+                }
+            }
+            None => remapped.push_str(link),
+        }
+        remapped.push_str("]]");
+        rest = &rest[end + 2..];
+    }
+    remapped.push_str(rest);
+    remapped
+}
+
+/// Tasks whose body links to `task`, skipping bodies that are encrypted
+fn tasks_linking_to<'a>(tasks: &'a [TaskFile], task: &Task) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|tf| tf.task.id != task.id && !is_encrypted_body(&tf.content))
+        .filter(|tf| {
+            wiki_links(&tf.content)
+                .iter()
+                .any(|link| same_task_id(&task.id, link))
+        })
+        .map(|tf| &tf.task)
+        .collect()
+}
+
+fn link_summary(task: &Task) -> String {
+    format!(
+        "{}: {} ({})",
+        task.id,
+        task.title,
+        task.status.as_deref().unwrap_or("pending")
+    )
+}
+
+fn show_backlinks(id: String, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let task = find_task(tasks.clone(), &id)?.task;
+
+    let backlinks = tasks_linking_to(&tasks, &task);
+    if backlinks.is_empty() {
+        println!("No tasks link to {}", id);
+        return Ok(());
+    }
+    println!("↩️  Tasks linking to {}: {}", task.id, task.title);
+    for linking in backlinks {
+        println!("  ← {}", link_summary(linking));
+    }
+    Ok(())
+}
+
+// Burndown
+
+/// Open work at the end of each day from `start` to `end`: every task created
+/// by then and not yet completed, weighted by `weight`
+fn burndown_series(
+    tasks: &[&Task],
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+    weight: impl Fn(&Task) -> f64,
+) -> Vec<(chrono::NaiveDate, f64)> {
+    let date = |value: &Option<String>| value.as_deref().and_then(parse_task_date);
+    start
+        .iter_days()
+        .take_while(|day| *day <= end)
+        .map(|day| {
+            let open = tasks
+                .iter()
+                .filter(|task| date(&task.created).is_none_or(|created| created <= day))
+                .filter(|task| {
+                    !(is_finished(task) && date(&task.completed).is_none_or(|done| done <= day))
+                })
+                .map(|task| weight(task))
+                .sum();
+            (day, open)
+        })
+        .collect()
+}
+
+/// Whole numbers without decimals, anything else with one
+fn chart_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Vertical bar chart, one column per point, drawn with eighth-block characters
+fn bar_chart(points: &[(chrono::NaiveDate, f64)], height: usize) -> Vec<String> {
+    const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = points.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+
+    let mut lines = Vec::new();
+    for row in (0..height).rev() {
+        let axis = match row {
+            r if r == height - 1 => format!("{:>6} ┤", chart_number(max)),
+            0 => format!("{:>6} ┤", 0),
+            _ => format!("{:>6} │", ""),
+        };
+        let bars: String = points
+            .iter()
+            .map(|(_, value)| {
+                let eighths = if max > 0.0 {
+                    (value / max * (height * 8) as f64).round() as usize
+                } else {
+                    0
+                };
+                BLOCKS[eighths.saturating_sub(row * 8).min(8)]
+            })
+            .collect();
+        lines.push(format!("{}{}", axis, bars));
+    }
+    lines.push(format!("{:>6} └{}", "", "─".repeat(points.len())));
+
+    // A date under the first day and then every seven days
+    let mut dates = vec![' '; points.len() + 5];
+    for (i, (day, _)) in points.iter().enumerate().step_by(7) {
+        for (offset, c) in day.format("%m-%d").to_string().chars().enumerate() {
+            dates[i + offset] = c;
+        }
+    }
+    lines.push(format!(
+        "{:>6}  {}",
+        "",
+        dates.into_iter().collect::<String>().trim_end()
+    ));
+    lines
+}
+
+fn show_burndown(
+    weeks: i64,
+    project: Option<String>,
+    milestone: Option<String>,
+    estimate: bool,
+    config: &Config,
+) -> Result<()> {
+    if weeks < 1 {
+        return Err(anyhow::anyhow!("--weeks must be at least 1"));
+    }
+    let all = load_tasks(config)?;
+    let tasks: Vec<&Task> = all
+        .iter()
+        .map(|tf| &tf.task)
+        .filter(|task| project.is_none() || task.project == project)
+        .filter(|task| milestone.is_none() || task.milestone == milestone)
+        .collect();
+
+    let today = local_today();
+    let start = today - chrono::Duration::days(weeks * 7 - 1);
+    let weight = |task: &Task| {
+        if estimate {
+            task.estimate
+                .as_deref()
+                .and_then(parse_estimate_hours)
+                .unwrap_or(0.0)
+        } else {
+            1.0
+        }
+    };
+    let points = burndown_series(&tasks, start, today, weight);
+
+    let mut scope = String::new();
+    if let Some(ref project) = project {
+        scope.push_str(&format!(", project {}", project));
+    }
+    if let Some(ref milestone) = milestone {
+        scope.push_str(&format!(", milestone {}", milestone));
+    }
+    let unit = if estimate {
+        "Remaining estimate (h)"
+    } else {
+        "Open tasks"
+    };
+    println!(
+        "📉 {}, {} → {}{}",
+        unit,
+        start.format("%Y-%m-%d"),
+        today.format("%Y-%m-%d"),
+        scope
+    );
+    for line in bar_chart(&points, 8) {
+        println!("{}", line);
+    }
+
+    let in_period = |value: &Option<String>| {
+        value
+            .as_deref()
+            .and_then(parse_task_date)
+            .is_some_and(|day| day >= start && day <= today)
+    };
+    let added = tasks.iter().filter(|task| in_period(&task.created)).count();
+    let completed = tasks
+        .iter()
+        .filter(|task| is_finished(task) && in_period(&task.completed))
+        .count();
+    let first = points.first().map(|(_, value)| *value).unwrap_or(0.0);
+    let last = points.last().map(|(_, value)| *value).unwrap_or(0.0);
+    println!(
+        "\n{} → {} over {} week(s): {} task(s) added, {} completed",
+        chart_number(first),
+        chart_number(last),
+        weeks,
+        added,
+        completed
+    );
+    if estimate {
+        let missing = tasks
+            .iter()
+            .filter(|task| !is_finished(task) && task.estimate.is_none())
+            .count();
+        if missing > 0 {
+            println!(
+                "⚠️  {} open task(s) have no estimate and count as 0h",
+                missing
+            );
+        }
+    }
+    Ok(())
+}
+
+// Dependency graph
+
+/// Look up a task by ID, refusing to guess when several files share it
+fn find_task(tasks: Vec<TaskFile>, id: &str) -> Result<TaskFile> {
+    let mut matches = matching_tasks(tasks, id)?;
+
+    if let Some(target) = TARGET_FILE.get() {
+        matches.retain(|tf| {
+            Path::new(&tf.file_path)
+                .canonicalize()
+                .is_ok_and(|path| &path == target)
+        });
+        return matches.pop().context(format!(
+            "Task with ID '{}' not found in {}",
+            id,
+            target.display()
+        ));
+    }
+
+    match matches.len() {
+        0 => Err(anyhow::anyhow!("Task with ID '{}' not found", id)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(anyhow::anyhow!(
+            "Task ID '{}' is used by {} files:\n{}\nPick one with --file <path>, or renumber with `mdtasks doctor --fix-duplicates`",
+            id,
+            matches.len(),
+            matches
+                .iter()
+                .map(|tf| format!("  {}", tf.file_path))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+/// Tasks an ID argument refers to, trying in turn: the exact ID, the same
+/// number written differently (`1` for `001`), a prefix of at least four
+/// characters of a ULID/UUID, and a case-insensitive title substring. Only an
+/// exact ID may match several tasks; otherwise the candidates are listed
+fn matching_tasks(tasks: Vec<TaskFile>, query: &str) -> Result<Vec<TaskFile>> {
+    let lowercase = query.to_lowercase();
+    let rules: [&dyn Fn(&Task) -> bool; 4] = [
+        &|task| task.id == query,
+        &|task| same_task_id(&task.id, query),
+        &|task| {
+            query.len() >= 4
+                && split_task_id(&task.id).is_none()
+                && task.id.to_lowercase().starts_with(&lowercase)
+        },
+        // Anything that looks like an ID is never taken for title words
+        &|task| {
+            split_task_id(query).is_none()
+                && query.chars().count() >= 3
+                && task.title.to_lowercase().contains(&lowercase)
+        },
+    ];
+
+    for (strictness, rule) in rules.iter().enumerate() {
+        let matches: Vec<TaskFile> = tasks.iter().filter(|tf| rule(&tf.task)).cloned().collect();
+        let mut candidates: Vec<(&str, &str)> = matches
+            .iter()
+            .map(|tf| (tf.task.id.as_str(), tf.task.title.as_str()))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup_by(|a, b| a.0 == b.0);
+        if strictness > 0 && candidates.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "'{}' matches {} tasks:\n{}\nUse the full task ID",
+                query,
+                candidates.len(),
+                candidates
+                    .iter()
+                    .map(|(id, title)| format!("  {}  {}", id, title))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        if !matches.is_empty() {
+            return Ok(matches);
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Most IDs a single range may expand to
+const MAX_ID_RANGE: u32 = 500;
+
+/// Expand ID ranges such as `012-015` or `API-3-5` into the IDs in between;
+/// arguments that are existing IDs or not ranges are kept as given
+fn expand_id_ranges(args: Vec<String>, tasks: &[TaskFile]) -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    for arg in args {
+        let range = arg.rsplit_once('-').and_then(|(first, last)| {
+            let (prefix, start) = split_task_id(first)?;
+            let end: u32 = last.parse().ok()?;
+            (start < end && !tasks.iter().any(|tf| tf.task.id == arg))
+                .then_some((prefix, start, end))
+        });
+        let Some((prefix, start, end)) = range else {
+            ids.push(arg);
+            continue;
+        };
+        if end - start >= MAX_ID_RANGE {
+            return Err(anyhow::anyhow!(
+                "Range {} covers {} IDs; at most {} can be given at once",
+                arg,
+                end - start + 1,
+                MAX_ID_RANGE
+            ));
+        }
+        let highest = tasks
+            .iter()
+            .filter_map(|tf| split_task_id(&tf.task.id))
+            .filter(|(task_prefix, _)| *task_prefix == prefix)
+            .map(|(_, number)| number)
+            .max();
+        match highest {
+            Some(highest) if end <= highest => {}
+            Some(highest) => {
+                return Err(anyhow::anyhow!(
+                    "Range {} goes past the highest task ID, {}",
+                    arg,
+                    format_task_id(prefix, highest)
+                ))
+            }
+            None => return Err(anyhow::anyhow!("No tasks match the range {}", arg)),
+        }
+        ids.extend((start..=end).map(|number| format_task_id(prefix, number)));
+    }
+    Ok(ids)
+}
+
+/// Run `action` for each task ID, reporting failures per task and carrying on;
+/// with a single ID its error is returned as is
+fn for_each_task(ids: Vec<String>, mut action: impl FnMut(String) -> Result<()>) -> Result<()> {
+    if let [id] = ids.as_slice() {
+        return action(id.clone());
+    }
+    let total = ids.len();
+    let mut failed = 0;
+    for id in ids {
+        if let Err(e) = action(id.clone()) {
+            eprintln!("❌ {}: {:#}", id, e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} tasks failed", failed, total));
+    }
+    Ok(())
+}
+
+/// Whether two task references name the same task ("7" and "007" do, as do "API-7" and "API-007")
+fn same_task_id(a: &str, b: &str) -> bool {
+    a == b || matches!((split_task_id(a), split_task_id(b)), (Some(x), Some(y)) if x == y)
+}
+
+/// Fill colour for a task node by status
+fn graph_status_color(status: Option<&str>) -> &'static str {
+    match status {
+        Some("active") => "#90caf9",
+        Some("partial") => "#fff59d",
+        Some("done") => "#a5d6a7",
+        Some("archived") | Some("cancelled") | Some("canceled") => "#bdbdbd",
+        Some("pending") | None => "#eeeeee",
+        Some(_) => "#ffcc80",
+    }
+}
+
+fn print_task_graph(format: &str, open: bool, config: &Config) -> Result<()> {
+    let tasks: Vec<Task> = load_tasks(config)?
+        .into_iter()
+        .map(|tf| tf.task)
+        .filter(|task| !open || !is_finished(task))
+        .collect();
+
+    let resolve = |id: &str| {
+        let found = tasks.iter().find(|task| same_task_id(&task.id, id));
+        if found.is_none() {
+            eprintln!("⚠️  Skipping reference to unknown or hidden task '{}'", id);
+        }
+        found
+    };
+
+    // (from, to, is_parent): dependencies point at the tasks waiting on them
+    let mut edges = Vec::new();
+    for task in &tasks {
+        for dependency in task.depends_on.iter().flatten() {
+            if let Some(dependency) = resolve(dependency) {
+                edges.push((dependency.id.as_str(), task.id.as_str(), false));
+            }
+        }
+        if let Some(parent) = task.parent.as_deref().and_then(resolve) {
+            edges.push((parent.id.as_str(), task.id.as_str(), true));
+        }
+    }
+
+    if format == "mermaid" {
+        println!("graph LR");
+        for task in &tasks {
+            println!(
+                "  t{}[\"{}: {}\"]",
+                task.id,
+                task.id,
+                task.title.replace('"', "#quot;")
+            );
+        }
+        for (from, to, is_parent) in &edges {
+            let arrow = if *is_parent { "-.->" } else { "-->" };
+            println!("  t{} {} t{}", from, arrow, to);
+        }
+        for task in &tasks {
+            println!(
+                "  style t{} fill:{}",
+                task.id,
+                graph_status_color(task.status.as_deref())
+            );
+        }
+    } else {
+        println!("digraph tasks {{");
+        println!("  rankdir=LR;");
+        println!("  node [shape=box, style=\"rounded,filled\"];");
+        for task in &tasks {
+            println!(
+                "  \"{}\" [label=\"{}\\n{}\", fillcolor=\"{}\"];",
+                task.id,
+                task.id,
+                task.title.replace('\\', "\\\\").replace('"', "\\\""),
+                graph_status_color(task.status.as_deref())
+            );
+        }
+        for (from, to, is_parent) in &edges {
+            let style = if *is_parent { " [style=dashed]" } else { "" };
+            println!("  \"{}\" -> \"{}\"{};", from, to, style);
+        }
+        println!("}}");
+    }
+
+    Ok(())
+}
+
+// Task tree
+
+/// Checklist items of a task body's subtask section, as (checked, text)
+fn subtask_items(content: &str) -> Vec<(bool, String)> {
+    let Some((_, section_start)) = find_subtask_section(content) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .skip(section_start + 1)
+        .take_while(|line| !is_leaving_subtask_section(line))
+        .filter_map(|line| {
+            let trimmed = line.trim();
+            if let Some(text) = trimmed
+                .strip_prefix("- [x]")
+                .or_else(|| trimmed.strip_prefix("- [X]"))
+            {
+                Some((true, text.trim().to_string()))
+            } else {
+                trimmed
+                    .strip_prefix("- [ ]")
+                    .map(|text| (false, text.trim().to_string()))
+            }
+        })
+        .collect()
+}
+
+/// Print `id`'s hierarchy, or that of every open task without a parent
+fn print_task_tree(id: Option<&str>, config: &Config) -> Result<()> {
+    let mut tasks = load_tasks(config)?;
+    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+
+    let parent_of = |task_file: &TaskFile| {
+        task_file.task.parent.as_deref().and_then(|parent| {
+            tasks
+                .iter()
+                .position(|other| same_task_id(&other.task.id, parent))
+        })
+    };
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); tasks.len()];
+    for (index, task_file) in tasks.iter().enumerate() {
+        if let Some(parent) = parent_of(task_file).filter(|parent| *parent != index) {
+            children[parent].push(index);
+        }
+    }
+
+    let roots: Vec<usize> = match id {
+        Some(id) => {
+            let root = find_task(tasks.clone(), id)?;
+            vec![tasks
+                .iter()
+                .position(|task_file| task_file.file_path == root.file_path)
+                .context("Task disappeared while building the tree")?]
+        }
+        None => (0..tasks.len())
+            .filter(|&index| parent_of(&tasks[index]).is_none() && !is_finished(&tasks[index].task))
+            .collect(),
+    };
+    if roots.is_empty() {
+        println!("No open tasks found.");
+        return Ok(());
+    }
+
+    for (number, root) in roots.into_iter().enumerate() {
+        if number > 0 {
+            println!();
+        }
+        let mut visited = std::collections::HashSet::new();
+        print_tree_node(&tasks, &children, root, "", "", &mut visited);
+    }
+    Ok(())
+}
+
+/// Print one task line with its progress, then its checklist items and child
+/// tasks below it; `visited` stops parent cycles from recursing forever
+fn print_tree_node(
+    tasks: &[TaskFile],
+    children: &[Vec<usize>],
+    index: usize,
+    lead: &str,
+    indent: &str,
+    visited: &mut std::collections::HashSet<usize>,
+) {
+    let task_file = &tasks[index];
+    let task = &task_file.task;
+    if !visited.insert(index) {
+        println!("{}↻ {} {} (parent cycle)", lead, task.id, task.title);
+        return;
+    }
+
+    // Descendant tasks and checklist items, finished over total
+    fn progress(
+        tasks: &[TaskFile],
+        children: &[Vec<usize>],
+        index: usize,
+        seen: &mut std::collections::HashSet<usize>,
+    ) -> (usize, usize, usize, usize) {
+        let items = subtask_items(&tasks[index].content);
+        let mut totals = (
+            0,
+            0,
+            items.iter().filter(|(checked, _)| *checked).count(),
+            items.len(),
+        );
+        for &child in &children[index] {
+            if !seen.insert(child) {
+                continue;
+            }
+            let (done, total, checked, items) = progress(tasks, children, child, seen);
+            totals.0 += done + usize::from(is_finished(&tasks[child].task));
+            totals.1 += total + 1;
+            totals.2 += checked;
+            totals.3 += items;
+        }
+        totals
+    }
+    let mut seen = std::collections::HashSet::from([index]);
+    let (done, total, checked, items) = progress(tasks, children, index, &mut seen);
+    let mut summary = Vec::new();
+    if total > 0 {
+        summary.push(format!("{}/{} tasks", done, total));
+    }
+    if items > 0 {
+        summary.push(format!("{}/{} items", checked, items));
+    }
+    let summary = if summary.is_empty() {
+        String::new()
+    } else {
+        let percent = (done + checked) * 100 / (total + items);
+        format!("  [{}, {}%]", summary.join(", "), percent)
+    };
+    println!(
+        "{}{} {} {}{}",
+        lead,
+        status_icon(task.status.as_deref()),
+        task.id,
+        task.title,
+        summary
+    );
+
+    let item_lines: Vec<String> = subtask_items(&task_file.content)
+        .into_iter()
+        .map(|(checked, text)| format!("[{}] {}", if checked { "x" } else { " " }, text))
+        .collect();
+    let count = item_lines.len() + children[index].len();
+    let branch = |position: usize| {
+        if position + 1 == count {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        }
+    };
+    for (position, line) in item_lines.iter().enumerate() {
+        println!("{}{}{}", indent, branch(position).0, line);
+    }
+    for (offset, &child) in children[index].iter().enumerate() {
+        let (lead, rest) = branch(item_lines.len() + offset);
+        print_tree_node(
+            tasks,
+            children,
+            child,
+            &format!("{}{}", indent, lead),
+            &format!("{}{}", indent, rest),
+            visited,
+        );
+    }
+}
+
+// Task roots
+
+/// Tasks of the tasks directory and every `[roots]` directory
+fn load_root_tasks(config: &Config) -> Result<Vec<TaskFile>> {
+    let mut tasks = load_tasks(config)?;
+    for (name, dir) in &config.roots {
+        let mut root_config = config.clone();
+        root_config.tasks.dir = dir.clone();
+        // The index belongs to the tasks directory
+        root_config.tasks.index = false;
+        root_config.roots.clear();
+        tasks.extend(load_tasks(&root_config).context(format!("Failed to load root '{}'", name))?);
+    }
+    Ok(tasks)
+}
+
+/// Name of the root a task file belongs to; "-" for the tasks directory
+fn task_root_name<'a>(file_path: &str, config: &'a Config) -> &'a str {
+    config
+        .roots
+        .iter()
+        .find(|(_, dir)| Path::new(file_path).starts_with(dir))
+        .map_or("-", |(name, _)| name.as_str())
+}
+
+// Overlays
+
+/// Tasks of every configured overlay repository, with prefixed IDs
+fn load_overlay_tasks(config: &Config) -> Vec<TaskFile> {
+    let mut tasks = Vec::new();
+
+    for overlay in &config.overlays {
+        match load_overlay(overlay) {
+            Ok(items) => tasks.extend(items),
+            Err(e) => eprintln!("⚠️  Failed to load overlay '{}': {:#}", overlay.repo, e),
+        }
+    }
+
+    tasks
+}
+
+fn load_overlay(overlay: &Overlay) -> Result<Vec<TaskFile>> {
+    let repo = PathBuf::from(shellexpand::tilde(&overlay.repo).to_string());
+    if !repo.is_dir() {
+        return Err(anyhow::anyhow!("Repository not found: {}", repo.display()));
+    }
+
+    // Use the overlay's own tasks directory, without touching its index
+    let mut overlay_config = Config::default();
+    for name in ["mdtasks.toml", ".mdtasks.toml"] {
+        let path = repo.join(name);
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .context(format!("Failed to read config file: {}", path.display()))?;
+            overlay_config = toml::from_str(&content)
+                .context(format!("Failed to parse config file: {}", path.display()))?;
+            break;
+        }
+    }
+    overlay_config.tasks.dir = repo
+        .join(&overlay_config.tasks.dir)
+        .to_string_lossy()
+        .to_string();
+    overlay_config.tasks.index = false;
+
+    let prefixed = |id: &str| format!("{}-{}", overlay.prefix, id);
+    let mut tasks = load_tasks(&overlay_config)?;
+    for task_file in &mut tasks {
+        let task = &mut task_file.task;
+        task.id = prefixed(&task.id);
+        task.parent = task.parent.as_deref().map(prefixed);
+        task.depends_on = task
+            .depends_on
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| prefixed(id)).collect());
+    }
+
+    Ok(tasks)
+}
+
+// Virtual sources
+
+/// Item from an external source, displayed but not stored locally
+#[derive(Debug)]
+struct VirtualTask {
+    id: String,
+    title: String,
+    source: String,
+    url: Option<String>,
+    due: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+impl VirtualTask {
+    fn as_task(&self) -> Task {
+        Task {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            status: Some("virtual".to_string()),
+            priority: None,
+            tags: self.tags.clone(),
+            project: None,
+            created: None,
+            due: self.due.clone(),
+            completed: None,
+            started: None,
+            branch: None,
+            scheduled: None,
+            estimate: None,
+            parent: None,
+            depends_on: None,
+            recur: None,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: None,
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        }
+    }
+}
+
+/// Load items from all configured virtual sources, warning about failures
+fn load_virtual_tasks(config: &Config) -> Vec<VirtualTask> {
+    let mut tasks = Vec::new();
+
+    for source in config.virtual_sources.iter().flatten() {
+        let result = match source.kind.as_str() {
+            "github" => fetch_github_virtual_tasks(source),
+            "ics" => fetch_ics_virtual_tasks(source),
+            other => Err(anyhow::anyhow!("Unknown virtual source kind: {}", other)),
+        };
+
+        match result {
+            Ok(items) => tasks.extend(items),
+            Err(e) => eprintln!("⚠️  Failed to load virtual source '{}': {}", source.name, e),
+        }
+    }
+
+    tasks
+}
+
+fn virtual_id_prefix(source: &VirtualSource) -> String {
+    source.prefix.clone().unwrap_or_else(|| source.name.clone())
+}
+
+fn fetch_github_virtual_tasks(source: &VirtualSource) -> Result<Vec<VirtualTask>> {
+    if !is_gh_cli_available()? {
+        return Err(anyhow::anyhow!("GitHub CLI (gh) is not installed"));
+    }
+
+    let mut args = vec![
+        "issue",
+        "list",
+        "--json",
+        "number,title,url,labels",
+        "--limit",
+        "100",
+    ];
+    if let Some(ref query) = source.query {
+        args.extend(["--search", query.as_str()]);
+    }
+
+    let output = std::process::Command::new("gh")
+        .args(&args)
+        .output()
+        .context("Failed to run gh issue list command")?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("gh issue list failed: {}", error_msg));
+    }
+
+    let issues: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse gh issue list output")?;
+
+    let prefix = virtual_id_prefix(source);
+    let mut tasks = Vec::new();
+    for issue in issues.as_array().into_iter().flatten() {
+        let number = match issue["number"].as_u64() {
+            Some(number) => number,
+            None => continue,
+        };
+        let labels: Vec<String> = issue["labels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|label| label["name"].as_str().map(|s| s.to_string()))
+            .collect();
+
+        tasks.push(VirtualTask {
+            id: format!("{}-{}", prefix, number),
+            title: issue["title"].as_str().unwrap_or_default().to_string(),
+            source: source.name.clone(),
+            url: issue["url"].as_str().map(|s| s.to_string()),
+            due: None,
+            tags: if labels.is_empty() {
+                None
+            } else {
+                Some(labels)
+            },
+        });
+    }
+
+    Ok(tasks)
+}
+
+fn fetch_ics_virtual_tasks(source: &VirtualSource) -> Result<Vec<VirtualTask>> {
+    let location = source.url.as_ref().context("ICS source requires a url")?;
+
+    let content = if location.starts_with("http://") || location.starts_with("https://") {
+        // Private feed URLs carry a secret token, so they go to curl on stdin
+        let (code, body) =
+            curl_request("GET", location, None, &[], None).context("Failed to fetch ICS feed")?;
+        if !(200..300).contains(&code) {
+            return Err(anyhow::anyhow!("Failed to fetch ICS feed: HTTP {}", code));
+        }
+        body
+    } else {
+        let path = shellexpand::tilde(location).to_string();
+        std::fs::read_to_string(&path).context(format!("Failed to read ICS file: {}", path))?
+    };
+
+    let prefix = virtual_id_prefix(source);
+    Ok(parse_ics_items(&content)
+        .into_iter()
+        .map(|(uid, summary, due)| VirtualTask {
+            id: format!("{}-{}", prefix, short_hash(&uid)),
+            title: summary,
+            source: source.name.clone(),
+            url: None,
+            due,
+            tags: None,
+        })
+        .collect())
+}
+
+/// Unfold the continuation lines of an ICS document (RFC 5545 section 3.1)
+fn unfold_ics_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+    lines
+}
+
+/// Extract (uid, summary, date) from VEVENT/VTODO components of an ICS document
+fn parse_ics_items(content: &str) -> Vec<(String, String, Option<String>)> {
+    let lines = unfold_ics_lines(content);
+    let mut items = Vec::new();
+    let mut current: Option<(String, String, Option<String>)> = None;
+
+    for line in &lines {
+        match line.as_str() {
+            "BEGIN:VEVENT" | "BEGIN:VTODO" => {
+                current = Some((String::new(), String::new(), None));
+            }
+            "END:VEVENT" | "END:VTODO" => {
+                if let Some(item) = current.take() {
+                    if !item.1.is_empty() {
+                        items.push(item);
+                    }
+                }
+            }
+            _ => {
+                let item = match current.as_mut() {
+                    Some(item) => item,
+                    None => continue,
+                };
+                let (name, value) = match line.split_once(':') {
+                    Some(parts) => parts,
+                    None => continue,
+                };
+                // Property parameters (e.g. DTSTART;VALUE=DATE) are ignored
+                let name = name.split(';').next().unwrap_or(name);
+                match name {
+                    "UID" => item.0 = value.to_string(),
+                    "SUMMARY" => item.1 = value.replace("\\,", ",").replace("\\;", ";"),
+                    // DUE wins over DTSTART when both are present
+                    "DUE" | "DTSTART" if name == "DUE" || item.2.is_none() => {
+                        // Remote feeds are untrusted; a date that isn't YYYYMMDD
+                        // is ignored rather than guessed at
+                        match value
+                            .get(0..8)
+                            .filter(|date| date.bytes().all(|b| b.is_ascii_digit()))
+                        {
+                            Some(date) => {
+                                item.2 = Some(format!(
+                                    "{}-{}-{}",
+                                    &date[0..4],
+                                    &date[4..6],
+                                    &date[6..8]
+                                ));
+                            }
+                            None => eprintln!(
+                                "⚠️  Ignoring invalid {} in calendar item: {}",
+                                name, value
+                            ),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Items without UID fall back to their summary for a stable ID
+    for item in &mut items {
+        if item.0.is_empty() {
+            item.0 = item.1.clone();
+        }
+    }
+
+    items
+}
+
+/// Short, stable hexadecimal hash (FNV-1a) used for virtual IDs
+fn short_hash(value: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in value.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:08x}", hash as u32)
+}
+
+fn adopt_virtual_task(id: String, config: &Config) -> Result<()> {
+    let virtual_task = load_virtual_tasks(config)
+        .into_iter()
+        .find(|vt| vt.id == id)
+        .context(format!("Virtual task with ID '{}' not found", id))?;
+
+    let mut notes = format!("Adopted from {}", virtual_task.source);
+    if let Some(ref url) = virtual_task.url {
+        notes.push_str(&format!(": {}", url));
+    }
+
+    add_task(
+        virtual_task.title,
+        None,
+        None,
+        virtual_task.tags,
+        None,
+        virtual_task.due,
+        Some(notes),
+        false,
+        config,
+    )?;
+
+    Ok(())
+}
+
+// Doctor
+
+/// Front-matter fields holding dates
+const DATE_FIELDS: [&str; 6] = [
+    "created",
+    "due",
+    "started",
+    "completed",
+    "scheduled",
+    "updated",
+];
+
+/// Problem found in a task file, with the front-matter change that repairs it
+struct DoctorIssue {
+    path: PathBuf,
+    message: String,
+    fix: Option<(&'static str, String)>,
+}
+
+/// Point a task file's `parent`, `depends_on`, and body `[[id]]` links at new
+/// IDs where `moved` gives one
+fn remap_task_references(content: &str, moved: &dyn Fn(&str) -> Option<String>) -> String {
+    let Some(mut task) = Matter::<gray_matter::engine::YAML>::new()
+        .parse(content)
+        .data
+        .and_then(|pod| extract_task_from_pod(&pod).ok())
+    else {
+        return content.to_string();
+    };
+    let parent = task.parent.as_deref().and_then(moved);
+    let depends_on: Option<Vec<String>> = task.depends_on.as_ref().map(|ids| {
+        ids.iter()
+            .map(|id| moved(id).unwrap_or_else(|| id.clone()))
+            .collect()
+    });
+    let body = split_front_matter(content).1;
+    let new_body = remap_wiki_links(body, moved);
+    if parent.is_none() && depends_on == task.depends_on && new_body == body {
+        return content.to_string();
+    }
+    task.parent = parent.or(task.parent);
+    task.depends_on = depends_on;
+    rewrite_front_matter(content, &task) + &new_body
+}
+
+/// Read a scalar front-matter value as a string
+fn pod_scalar(pod: &gray_matter::Pod) -> Option<String> {
+    match pod {
+        gray_matter::Pod::String(s) => Some(s.clone()),
+        gray_matter::Pod::Integer(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+/// Reformat a date written in a common non-ISO layout as YYYY-MM-DD
+fn normalize_task_date(value: &str) -> Option<String> {
+    ["%Y/%m/%d", "%Y.%m.%d", "%d.%m.%Y", "%d-%m-%Y", "%Y%m%d"]
+        .iter()
+        .find_map(|format| chrono::NaiveDate::parse_from_str(value.trim(), format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Derive a title from a task file name such as `004-fix-login.md`
+fn title_from_path(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    stem.trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['-', '_'])
+        .replace(['-', '_'], " ")
+}
+
+/// Replace a top-level `key:` line in the front-matter block, or add it
+/// before the closing delimiter
+fn set_front_matter_value(content: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let end = lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim_end() == "---")
+        .map(|i| i + 1);
+
+    if let Some(end) = end {
+        let prefix = format!("{}:", key);
+        let line = format!("{}: {}", key, value);
+        match lines[1..end].iter().position(|l| l.starts_with(&prefix)) {
+            Some(i) => lines[i + 1] = line,
+            // IDs go first, like in files written by mdtasks
+            None if key == "id" => lines.insert(1, line),
+            None => lines.insert(end, line),
+        }
+    }
+
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+fn remove_front_matter_value(content: &str, key: &str) -> String {
+    let (front_matter, body) = split_front_matter(content);
+    let prefix = format!("{}:", key);
+    let kept: String = front_matter
+        .split_inclusive('\n')
+        .filter(|line| !line.starts_with(&prefix))
+        .collect();
+    format!("{}{}", kept, body)
+}
+
+/// Check a single task file, returning its ID and `depends_on` references
+fn check_task_file(
+    path: &Path,
+    workflow: &WorkflowConfig,
+    issues: &mut Vec<DoctorIssue>,
+) -> Result<Option<(Option<String>, Vec<String>)>> {
+    use gray_matter::Pod;
+
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read file: {}", path.display()))?;
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let mut issue = |message: String, fix: Option<(&'static str, String)>| {
+        issues.push(DoctorIssue {
+            path: path.to_path_buf(),
+            message,
+            fix,
+        })
+    };
+
+    let hash = match matter.parse(&content).data {
+        Some(Pod::Hash(hash)) => hash,
+        _ => {
+            issue("missing or unparsable front-matter".to_string(), None);
+            return Ok(None);
+        }
+    };
+
+    let id = hash
+        .get("id")
+        .and_then(pod_scalar)
+        .filter(|id| !id.is_empty());
+    if id.is_none() {
+        // The replacement ID is assigned once all files have been read
+        issue("missing id".to_string(), Some(("id", String::new())));
+    }
+
+    match hash.get("title").and_then(pod_scalar) {
+        Some(title) if !title.trim().is_empty() => {}
+        _ => {
+            let title = title_from_path(path);
+            let fix = (!title.is_empty()).then(|| ("title", format!("\"{}\"", title)));
+            issue("missing title".to_string(), fix);
+        }
+    }
+
+    if let Some(status) = hash.get("status").and_then(pod_scalar) {
+        if !workflow.is_valid_status(&status) {
+            let normalized = status.trim().to_lowercase();
+            let fix = workflow
+                .is_valid_status(&normalized)
+                .then_some(("status", normalized));
+            issue(
+                format!(
+                    "unknown status '{}' (expected one of: {})",
+                    status,
+                    workflow.status_order().join(", ")
+                ),
+                fix,
+            );
+        }
+    }
+
+    if let Some(priority) = hash.get("priority").and_then(pod_scalar) {
+        if Priority::parse(&priority).is_none() {
+            let normalized = priority.trim().to_lowercase();
+            let fix = Priority::parse(&normalized).map(|p| ("priority", p.as_str().to_string()));
+            issue(
+                format!(
+                    "unknown priority '{}' (expected one of: high, medium, low)",
+                    priority
+                ),
+                fix,
+            );
+        }
+    }
+
+    for field in DATE_FIELDS {
+        if let Some(value) = hash.get(field).and_then(pod_scalar) {
+            if parse_task_date(&value).is_none() {
+                let fix = normalize_task_date(&value).map(|date| (field, date));
+                issue(
+                    format!("malformed {} date '{}' (expected YYYY-MM-DD)", field, value),
+                    fix,
+                );
+            }
+        }
+    }
+
+    let depends_on = match hash.get("depends_on") {
+        Some(Pod::Array(items)) => items.iter().filter_map(pod_scalar).collect(),
+        Some(value) => pod_scalar(value).into_iter().collect(),
+        None => Vec::new(),
+    };
+
+    Ok(Some((id, depends_on)))
+}
+
+/// File name for a task renumbered to `id`, if the name starts with its old ID
+fn renumbered_path(path: &Path, id: &str) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let (prefix, rest) = name.split_once('-')?;
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(path.with_file_name(format!("{}-{}", id, rest)))
+}
+
+fn run_doctor(fix: bool, fix_duplicates: bool, config: &Config) -> Result<()> {
+    let fix = fix || fix_duplicates;
+    let _lock = if fix { Some(lock_tasks(config)?) } else { None };
+
+    let (paths, skipped) = scan_task_paths(config)?;
+    let mut issues = Vec::new();
+
+    for (path, reason) in skipped {
+        issues.push(DoctorIssue {
+            path,
+            message: format!("not loaded: {}", reason),
+            fix: None,
+        });
+    }
+
+    let mut ids: Vec<(String, PathBuf)> = Vec::new();
+    let mut dependencies: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for path in &paths {
+        if let Some((id, depends_on)) = check_task_file(path, &config.workflow, &mut issues)? {
+            if let Some(id) = id {
+                ids.push((id, path.clone()));
+            }
+            dependencies.push((path.clone(), depends_on));
+        }
+    }
+
+    // Among files sharing an ID the oldest keeps it (by created date, then
+    // modification time); the newer ones are the duplicates
+    let age = |path: &Path| {
+        let created = validate_task_file(path).ok().and_then(|task| task.created);
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        (created.is_none(), created, modified)
+    };
+    let mut duplicates: Vec<(PathBuf, String)> = Vec::new();
+    let mut seen_ids = Vec::new();
+    for (id, _) in &ids {
+        if seen_ids.contains(&id) {
+            continue;
+        }
+        seen_ids.push(id);
+
+        let mut group: Vec<&PathBuf> = ids
+            .iter()
+            .filter(|(other, _)| other == id)
+            .map(|(_, path)| path)
+            .collect();
+        if group.len() < 2 {
+            continue;
+        }
+        group.sort_by_cached_key(|path| age(path));
+
+        for path in &group[1..] {
+            let hint = if fix_duplicates {
+                ""
+            } else {
+                " (renumber with --fix-duplicates)"
+            };
+            issues.push(DoctorIssue {
+                path: (*path).clone(),
+                message: format!(
+                    "duplicate id {} (also used by {}){}",
+                    id,
+                    group[0].display(),
+                    hint
+                ),
+                fix: fix_duplicates.then(|| ("id", String::new())),
+            });
+            duplicates.push(((*path).clone(), id.clone()));
+        }
+    }
+
+    // Tasks may depend on overlay repositories' tasks by their prefixed ID
+    let overlay_ids: Vec<String> = if dependencies.is_empty() {
+        Vec::new()
+    } else {
+        load_overlay_tasks(config)
+            .into_iter()
+            .map(|task_file| task_file.task.id)
+            .collect()
+    };
+    for (path, depends_on) in &dependencies {
+        for dependency in depends_on {
+            if !ids.iter().any(|(id, _)| id == dependency) && !overlay_ids.contains(dependency) {
+                issues.push(DoctorIssue {
+                    path: path.clone(),
+                    message: format!("depends_on references unknown task '{}'", dependency),
+                    fix: None,
+                });
+            }
+        }
+    }
+
+    // Missing IDs get fresh numbers after the highest existing one
+    let mut allocator = TaskIdAllocator::new(ids.iter().map(|(id, _)| id.as_str()));
+    for issue in &mut issues {
+        if let Some(("id", value)) = issue.fix.as_mut() {
+            *value = allocator.next(None, config)?;
+        }
+    }
+
+    // (path, old ID, new ID) of each duplicate that gets renumbered
+    let renumbered: Vec<(PathBuf, String, String)> = issues
+        .iter()
+        .filter_map(|issue| match &issue.fix {
+            Some(("id", new_id)) if fix => duplicates
+                .iter()
+                .find(|(path, _)| *path == issue.path)
+                .map(|(path, old_id)| (path.clone(), old_id.clone(), new_id.clone())),
+            _ => None,
+        })
+        .collect();
+    // Renumbered files usually arrived together (copied, or from the same
+    // merged branch), so references among them follow the new IDs
+    let moved_id = |id: &str| -> Option<String> {
+        let mut moved = renumbered
+            .iter()
+            .filter(|(_, old_id, _)| same_task_id(old_id, id));
+        match (moved.next(), moved.next()) {
+            (Some((_, _, new_id)), None) => Some(new_id.clone()),
+            _ => None,
+        }
+    };
+
+    println!("🩺 Checked {} task file(s)", paths.len());
+
+    let mut fixed = 0;
+    for issue in &issues {
+        match (&issue.fix, fix) {
+            (Some((key, value)), true) => {
+                let path = issue.path.to_string_lossy().to_string();
+                let content = read_task_file(&path, config)?;
+                let mut new_content = set_front_matter_value(&content, key, value);
+                if renumbered.iter().any(|(path, _, _)| *path == issue.path) {
+                    new_content = remap_task_references(&new_content, &moved_id);
+                }
+
+                // Keep "<id>-<slug>.md" file names in step with a new ID
+                let renamed = match *key {
+                    "id" => renumbered_path(&issue.path, value).filter(|p| !p.exists()),
+                    _ => None,
+                };
+                match renamed {
+                    Some(new_path) => {
+                        let new_path = new_path.to_string_lossy().to_string();
+                        write_task_file(&new_path, &new_content, config)
+                            .context(format!("Failed to write task file: {}", new_path))?;
+                        remove_task_file(&path, config)
+                            .context(format!("Failed to remove task file: {}", path))?;
+                        println!(
+                            "🔧 {}: {} → set {}: {} (renamed to {})",
+                            path, issue.message, key, value, new_path
+                        );
+                    }
+                    None => {
+                        write_task_file(&path, &new_content, config)
+                            .context(format!("Failed to write task file: {}", path))?;
+                        println!("🔧 {}: {} → set {}: {}", path, issue.message, key, value);
+                    }
+                }
+                fixed += 1;
+            }
+            (Some(_), false) => println!(
+                "⚠️  {}: {} (fixable with --fix)",
+                issue.path.display(),
+                issue.message
+            ),
+            (None, _) => println!("❌ {}: {}", issue.path.display(), issue.message),
+        }
+    }
+
+    // Other tasks keep pointing at the file that kept the ID
+    for path in paths
+        .iter()
+        .filter(|path| !renumbered.iter().any(|(p, _, _)| p == *path))
+    {
+        let Ok(task) = validate_task_file(path) else {
+            continue;
+        };
+        // A body that cannot be decrypted has no links to report
+        let links = read_task_file(&path.to_string_lossy(), config)
+            .map(|content| wiki_links(split_front_matter(&content).1))
+            .unwrap_or_default();
+        let references = task
+            .parent
+            .iter()
+            .chain(task.depends_on.iter().flatten())
+            .chain(links.iter());
+        let mut reported: Vec<&str> = Vec::new();
+        for reference in references {
+            if let Some((_, old_id, new_id)) = renumbered
+                .iter()
+                .find(|(_, old_id, _)| same_task_id(old_id, reference))
+            {
+                if reported.contains(&old_id.as_str()) {
+                    continue;
+                }
+                reported.push(old_id);
+                println!(
+                    "ℹ️  {} refers to {}; change it to {} if it meant the renumbered task",
+                    path.display(),
+                    old_id,
+                    new_id
+                );
+            }
+        }
+    }
+
+    let remaining = issues.len() - fixed;
+    if issues.is_empty() {
+        println!("✅ No problems found");
+    } else if fixed > 0 {
+        println!("🔧 Fixed {} problem(s)", fixed);
+    }
+
+    if remaining > 0 {
+        return Err(anyhow::anyhow!("{} problem(s) found", remaining));
+    }
+
+    Ok(())
+}
+
+// Import
+
+/// Parse CSV text (RFC 4180: quoted fields, doubled quotes, embedded newlines)
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+/// Strip the " <32 hex chars>" suffix Notion appends to exported file names
+fn strip_notion_hash(name: &str) -> &str {
+    match name.rsplit_once(' ') {
+        Some((title, hash)) if hash.len() == 32 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            title
+        }
+        _ => name,
+    }
+}
+
+/// Parse a Notion date such as "October 16, 2026", "2026/10/16", or a range
+fn parse_notion_date(value: &str) -> Option<String> {
+    let start = value.split(" → ").next().unwrap_or(value).trim();
+    // Drop a trailing time ("October 16, 2026 3:00 PM")
+    let date_only = start
+        .split_whitespace()
+        .take(3)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    [start, date_only.as_str()]
+        .iter()
+        .find_map(|candidate| {
+            ["%B %d, %Y", "%b %d, %Y", "%Y/%m/%d", "%Y-%m-%d", "%m/%d/%Y"]
+                .iter()
+                .find_map(|format| chrono::NaiveDate::parse_from_str(candidate, format).ok())
+        })
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Split an exported Notion page into its property lines and body
+fn split_notion_page(content: &str) -> (Vec<(String, String)>, String) {
+    let mut lines = content.lines().peekable();
+
+    // The page starts with its title as a heading
+    while lines.peek().is_some_and(|line| line.trim().is_empty()) {
+        lines.next();
+    }
+    if lines.peek().is_some_and(|line| line.starts_with("# ")) {
+        lines.next();
+    }
+    while lines.peek().is_some_and(|line| line.trim().is_empty()) {
+        lines.next();
+    }
+
+    // Followed by one "Property: value" line per database property
+    let mut properties = Vec::new();
+    while let Some(line) = lines.peek() {
+        match line.split_once(": ") {
+            Some((key, value)) if !key.is_empty() && !key.starts_with(['#', '-', '*', '>']) => {
+                properties.push((key.trim().to_string(), value.trim().to_string()));
+                lines.next();
+            }
+            _ => break,
+        }
+    }
+
+    let body = lines.collect::<Vec<_>>().join("\n");
+    (properties, body.trim().to_string())
+}
+
+fn import_notion(dir: &str, dry_run: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let mapping = &config.import.notion;
+    let export_dir = Path::new(dir);
+    if !export_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Notion export directory not found: {}",
+            dir
+        ));
+    }
+
+    let mut csv_files = Vec::new();
+    let mut pages = std::collections::HashMap::new();
+    for entry in WalkDir::new(export_dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            // "_all.csv" duplicates the main CSV with every property
+            Some("csv") if !stem.ends_with("_all") => csv_files.push(path.to_path_buf()),
+            Some("md") => {
+                pages.insert(strip_notion_hash(&stem).to_string(), path.to_path_buf());
+            }
+            _ => {}
+        }
+    }
+
+    // Database rows come from the CSV; without one, each page's property block is used
+    let mut rows: Vec<Vec<(String, String)>> = Vec::new();
+    for csv_path in &csv_files {
+        let content = std::fs::read_to_string(csv_path)
+            .context(format!("Failed to read CSV: {}", csv_path.display()))?;
+        let mut records = parse_csv(&content).into_iter();
+        let header = match records.next() {
+            Some(header) => header,
+            None => continue,
+        };
+        for record in records {
+            rows.push(header.iter().cloned().zip(record).collect());
+        }
+    }
+    if csv_files.is_empty() {
+        for (title, path) in &pages {
+            let content = std::fs::read_to_string(path)
+                .context(format!("Failed to read page: {}", path.display()))?;
+            let (mut properties, _) = split_notion_page(&content);
+            properties.push((mapping.title_property.clone(), title.clone()));
+            rows.push(properties);
+        }
+        rows.sort();
+    }
+
+    let existing: Vec<String> = load_tasks(config)?
+        .into_iter()
+        .map(|tf| tf.task.title)
+        .collect();
+    let mut ids = TaskIdAllocator::load(config)?;
+    let mut created = 0;
+    let mut skipped = 0;
+
+    for row in rows {
+        let property = |name: &str| {
+            row.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let title = match property(&mapping.title_property) {
+            Some(title) => title,
+            None => continue,
+        };
+        if existing.contains(&title) {
+            println!("⏭️  Skipping '{}': a task with this title exists", title);
+            skipped += 1;
+            continue;
+        }
+
+        let status = property(&mapping.status_property).map(|status| {
+            mapping
+                .status_map
+                .get(&status)
+                .cloned()
+                .unwrap_or_else(|| status.to_lowercase().replace(' ', "-"))
+        });
+        let tags = property(&mapping.tags_property).map(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        });
+
+        let project = property(&mapping.project_property);
+        let id = ids.next(project.as_deref(), config)?;
+        let task = Task {
+            id: id.clone(),
+            title: title.clone(),
+            status: status.or(Some("pending".to_string())),
+            priority: property(&mapping.priority_property)
+                .map(|p| p.to_lowercase())
+                .or(Some("medium".to_string())),
+            tags,
+            project,
+            created: Some(task_timestamp(config)),
+            due: property(&mapping.due_property).and_then(|due| parse_notion_date(&due)),
+            completed: None,
+            started: None,
+            branch: None,
+            scheduled: None,
+            estimate: None,
+            parent: None,
+            depends_on: None,
+            recur: None,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: None,
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        };
+
+        let mut content = format_front_matter(&task);
+        for (notion_property, field) in &mapping.properties {
+            if let Some(value) = property(notion_property) {
+                content = set_front_matter_value(&content, field, &yaml_quote(&value));
+            }
+        }
+
+        let body = match pages.get(&title) {
+            Some(path) => {
+                let page = std::fs::read_to_string(path)
+                    .context(format!("Failed to read page: {}", path.display()))?;
+                split_notion_page(&page).1
+            }
+            None => String::new(),
+        };
+        if body.is_empty() {
+            content.push_str("# Task Details\n\n## Subtasks\n\n");
+        } else {
+            content.push_str(&body);
+            content.push('\n');
+        }
+
+        let filename = format!(
+            "{}/{}-{}.md",
+            config.tasks.dir,
+            id,
+            slugify(&title, &config.slug)
+        );
+
+        if Path::new(&filename).exists() {
+            return Err(anyhow::anyhow!("Task file already exists: {}", filename));
+        }
+
+        if dry_run {
+            println!("📝 Would create {}: {}", id, filename);
+        } else {
+            std::fs::create_dir_all(&config.tasks.dir)?;
+            write_task_file(&filename, &content, config)
+                .context(format!("Failed to write task file: {}", filename))?;
+            println!("✅ Created task {}: {}", id, title);
+        }
+        created += 1;
+    }
+
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "📥 {} {} task(s) from Notion, skipped {}",
+        verb, created, skipped
+    );
+
+    Ok(())
+}
+
+// todo.txt
+
+/// One task parsed from a todo.txt line
+struct TodoTxtItem {
+    done: bool,
+    priority: Option<char>,
+    completed: Option<String>,
+    created: Option<String>,
+    title: String,
+    projects: Vec<String>,
+    contexts: Vec<String>,
+    /// `key:value` extensions such as `due:2026-10-16`
+    extensions: Vec<(String, String)>,
+}
+
+fn is_iso_date(value: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+}
+
+/// Parse a todo.txt line: `x 2026-10-16 2026-10-01 (A) Call mom +Family @phone due:2026-10-20`
+fn parse_todotxt_line(line: &str) -> Option<TodoTxtItem> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let done = words[0] == "x";
+    if done {
+        words.remove(0);
+    }
+
+    let mut priority = None;
+    if let Some(word) = words.first() {
+        let bytes = word.as_bytes();
+        if bytes.len() == 3 && bytes[0] == b'(' && bytes[2] == b')' && bytes[1].is_ascii_uppercase()
+        {
+            priority = Some(bytes[1] as char);
+            words.remove(0);
+        }
+    }
+
+    // Completed tasks carry the completion date first, then the creation date
+    let mut dates = Vec::new();
+    while dates.len() < if done { 2 } else { 1 } && words.first().is_some_and(|w| is_iso_date(w)) {
+        dates.push(words.remove(0).to_string());
+    }
+    let (completed, created) = match (done, dates.len()) {
+        (true, 2) => (Some(dates[0].clone()), Some(dates[1].clone())),
+        (true, 1) => (Some(dates[0].clone()), None),
+        (false, 1) => (None, Some(dates[0].clone())),
+        _ => (None, None),
+    };
+
+    let mut title = Vec::new();
+    let mut projects = Vec::new();
+    let mut contexts = Vec::new();
+    let mut extensions = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            projects.push(project.to_string());
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            contexts.push(context.to_string());
+        } else if let Some((key, value)) = word
+            .split_once(':')
+            .filter(|(key, value)| !key.is_empty() && !value.is_empty() && !value.starts_with('/'))
+        {
+            extensions.push((key.to_string(), value.to_string()));
+        } else {
+            title.push(word);
+        }
+    }
+
+    // Completed tasks keep their priority as `pri:A` by convention
+    if priority.is_none() {
+        priority = extensions
+            .iter()
+            .find(|(key, _)| key == "pri")
+            .and_then(|(_, value)| value.chars().next())
+            .filter(|c| c.is_ascii_uppercase());
+    }
+
+    Some(TodoTxtItem {
+        done,
+        priority,
+        completed,
+        created,
+        title: title.join(" "),
+        projects,
+        contexts,
+        extensions,
+    })
+}
+
+/// (A) is high, (B) medium, and anything lower is low
+fn todotxt_priority(priority: char) -> Priority {
+    match priority {
+        'A' => Priority::High,
+        'B' => Priority::Medium,
+        _ => Priority::Low,
+    }
+}
+
+fn import_todotxt(file: &str, dry_run: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let text =
+        std::fs::read_to_string(file).context(format!("Failed to read todo.txt file: {}", file))?;
+
+    let existing: Vec<String> = load_tasks(config)?
+        .into_iter()
+        .map(|tf| tf.task.title)
+        .collect();
+    let mut ids = TaskIdAllocator::load(config)?;
+    let mut created = 0;
+    let mut skipped = 0;
+
+    for item in text.lines().filter_map(parse_todotxt_line) {
+        if item.title.is_empty() {
+            continue;
+        }
+        if existing.contains(&item.title) {
+            println!(
+                "⏭️  Skipping '{}': a task with this title exists",
+                item.title
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let extension = |name: &str| {
+            item.extensions
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+                .filter(|value| is_iso_date(value))
+        };
+
+        // The first +project becomes the project; further projects and all @contexts become tags
+        let mut tags: Vec<String> = item.projects.iter().skip(1).cloned().collect();
+        tags.extend(item.contexts.iter().cloned());
+
+        let id = ids.next(item.projects.first().map(String::as_str), config)?;
+        let task = Task {
+            id: id.clone(),
+            title: item.title.clone(),
+            status: Some(if item.done { "done" } else { "pending" }.to_string()),
+            priority: Some(
+                item.priority
+                    .map(todotxt_priority)
+                    .unwrap_or(Priority::Medium)
+                    .as_str()
+                    .to_string(),
+            ),
+            tags: (!tags.is_empty()).then_some(tags),
+            project: item.projects.first().cloned(),
+            created: item
+                .created
+                .clone()
+                .or_else(|| Some(task_timestamp(config))),
+            due: extension("due"),
+            completed: item.completed.clone(),
+            started: None,
+            branch: None,
+            scheduled: extension("t"),
+            estimate: None,
+            parent: None,
+            depends_on: None,
+            recur: None,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: None,
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        };
+
+        // Other extensions are kept as custom front-matter fields
+        let mut content = format_front_matter(&task);
+        for (key, value) in &item.extensions {
+            if !["due", "t", "pri", "id"].contains(&key.as_str()) {
+                content = set_front_matter_value(&content, key, &yaml_quote(value));
+            }
+        }
+        content.push_str(&format_task_body(&config.body, None));
+
+        let filename = format!(
+            "{}/{}-{}.md",
+            config.tasks.dir,
+            id,
+            slugify(&item.title, &config.slug)
+        );
+
+        if Path::new(&filename).exists() {
+            return Err(anyhow::anyhow!("Task file already exists: {}", filename));
+        }
+
+        if dry_run {
+            println!("📝 Would create {}: {}", id, filename);
+        } else {
+            std::fs::create_dir_all(&config.tasks.dir)?;
+            write_task_file(&filename, &content, config)
+                .context(format!("Failed to write task file: {}", filename))?;
+            println!("✅ Created task {}: {}", id, item.title);
+        }
+        created += 1;
+    }
+
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "📥 {} {} task(s) from todo.txt, skipped {}",
+        verb, created, skipped
+    );
+
+    Ok(())
+}
+
+/// Format a task as a todo.txt line; finished tasks are written completed
+fn format_todotxt_line(task: &Task) -> String {
+    let mut words = Vec::new();
+    let priority =
+        task.priority
+            .as_deref()
+            .and_then(Priority::parse)
+            .map(|priority| match priority {
+                Priority::High => 'A',
+                Priority::Medium => 'B',
+                Priority::Low => 'C',
+            });
+
+    // todo.txt only knows dates, so timestamps are cut to their date part
+    let date = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|value| value.get(..10).unwrap_or(value).to_string())
+    };
+    let finished = is_finished(task);
+    if finished {
+        words.push("x".to_string());
+        // A creation date is only valid after a completion date
+        if let Some(completed) = date(&task.completed) {
+            words.push(completed);
+            words.extend(date(&task.created));
+        }
+    } else {
+        words.extend(priority.map(|p| format!("({})", p)));
+        words.extend(date(&task.created));
+    }
+
+    words.push(task.title.clone());
+    words.extend(
+        task.project
+            .iter()
+            .map(|project| format!("+{}", project.replace(' ', "_"))),
+    );
+    words.extend(
+        task.tags
+            .iter()
+            .flatten()
+            .map(|tag| format!("@{}", tag.replace(' ', "_"))),
+    );
+    words.extend(task.due.iter().map(|due| format!("due:{}", due)));
+    words.extend(task.scheduled.iter().map(|t| format!("t:{}", t)));
+    if finished {
+        words.extend(priority.map(|p| format!("pri:{}", p)));
+    }
+    words.push(format!("id:{}", task.id));
+
+    words.join(" ")
+}
+
+fn export_todotxt(output: Option<&str>, config: &Config) -> Result<()> {
+    let mut tasks = load_tasks(config)?;
+    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+
+    let mut text = String::new();
+    for task_file in &tasks {
+        text.push_str(&format_todotxt_line(&task_file.task));
+        text.push('\n');
+    }
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, text).context(format!("Failed to write {}", path))?;
+            println!("📤 Exported {} task(s) to {}", tasks.len(), path);
+        }
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
+
+// Taskwarrior
+
+/// Front-matter date from a Taskwarrior timestamp (`20261016T120000Z`), in local time
+fn taskwarrior_date(value: &serde_json::Value) -> Option<String> {
+    let value = value.as_str()?;
+    let time = chrono::NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ").ok()?;
+    Some(to_local_time(time.and_utc()).format("%Y-%m-%d").to_string())
+}
+
+/// Taskwarrior timestamp for a front-matter timestamp, or local midnight of a date
+fn taskwarrior_timestamp(date: &str) -> Option<String> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(date) {
+        return Some(
+            timestamp
+                .with_timezone(&chrono::Utc)
+                .format("%Y%m%dT%H%M%SZ")
+                .to_string(),
+        );
+    }
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let local = from_local_time(date.and_hms_opt(0, 0, 0)?)?;
+    Some(
+        local
+            .with_timezone(&chrono::Utc)
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string(),
+    )
+}
+
+/// Deterministic UUID for tasks that were not imported from Taskwarrior,
+/// so repeated exports (and `depends`) refer to the same task
+fn stable_uuid(seed: &str) -> String {
+    let hash = |salt: u64| {
+        let mut hash: u64 = 0xcbf29ce484222325 ^ salt;
+        for byte in seed.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    };
+    let high = (hash(0) & !0xf000) | 0x4000;
+    let low = (hash(0x9e3779b97f4a7c15) & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// Taskwarrior attributes that map onto mdtasks fields; anything else is a UDA
+const TASKWARRIOR_FIELDS: [&str; 22] = [
+    "id",
+    "uuid",
+    "description",
+    "status",
+    "entry",
+    "modified",
+    "due",
+    "end",
+    "start",
+    "scheduled",
+    "wait",
+    "until",
+    "project",
+    "tags",
+    "priority",
+    "urgency",
+    "annotations",
+    "depends",
+    "recur",
+    "mask",
+    "imask",
+    "parent",
+];
+
+fn import_taskwarrior(file: Option<&str>, dry_run: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let text = match file {
+        Some(file) => std::fs::read_to_string(file)
+            .context(format!("Failed to read Taskwarrior export: {}", file))?,
+        None => {
+            let output = std::process::Command::new("task")
+                .arg("export")
+                .output()
+                .context("Failed to run `task export` (pass a file exported with it instead)")?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "task export failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+    };
+
+    // `task export` writes a JSON array; older versions write one object per line
+    let items: Vec<serde_json::Value> = match serde_json::from_str(&text) {
+        Ok(serde_json::Value::Array(items)) => items,
+        _ => text
+            .lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| line.starts_with('{'))
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .context("Failed to parse Taskwarrior export JSON")?,
+    };
+
+    let existing: Vec<String> = load_tasks(config)?
+        .into_iter()
+        .map(|tf| tf.task.title)
+        .collect();
+    let mut ids = TaskIdAllocator::load(config)?;
+
+    // Assign IDs first so `depends` UUIDs can be translated
+    let mut planned = Vec::new();
+    let mut ids_by_uuid = std::collections::HashMap::new();
+    let mut skipped = 0;
+    for item in &items {
+        let Some(title) = item["description"].as_str().map(str::trim) else {
+            continue;
+        };
+        // Deleted tasks and generated instances of recurring tasks are left out
+        if title.is_empty() || item["status"] == "deleted" || !item["parent"].is_null() {
+            continue;
+        }
+        if existing.iter().any(|t| t == title) {
+            println!("⏭️  Skipping '{}': a task with this title exists", title);
+            skipped += 1;
+            continue;
+        }
+        let id = ids.next(item["project"].as_str(), config)?;
+        if let Some(uuid) = item["uuid"].as_str() {
+            ids_by_uuid.insert(uuid.to_string(), id.clone());
+        }
+        planned.push((id, title.to_string(), item));
+    }
+
+    for (id, title, item) in &planned {
+        let status = match item["status"].as_str() {
+            Some("completed") => "done",
+            _ if !item["start"].is_null() => "active",
+            _ => "pending",
+        };
+        let priority = match item["priority"].as_str() {
+            Some("H") => "high",
+            Some("L") => "low",
+            _ => "medium",
+        };
+        let tags: Option<Vec<String>> = item["tags"].as_array().map(|tags| {
+            tags.iter()
+                .filter_map(|tag| tag.as_str().map(str::to_string))
+                .collect()
+        });
+        // `depends` is an array of UUIDs, or a comma-separated string in older exports
+        let depends: Vec<String> = match &item["depends"] {
+            serde_json::Value::Array(uuids) => uuids
+                .iter()
+                .filter_map(|u| u.as_str())
+                .map(str::to_string)
+                .collect(),
+            serde_json::Value::String(uuids) => uuids.split(',').map(str::to_string).collect(),
+            _ => Vec::new(),
+        };
+        let depends_on: Vec<String> = depends
+            .iter()
+            .filter_map(|uuid| ids_by_uuid.get(uuid.trim()).cloned())
+            .collect();
+        let recur = item["recur"]
+            .as_str()
+            .filter(|recur| parse_recurrence(recur).is_ok())
+            .map(str::to_string);
+
+        let task = Task {
+            id: id.clone(),
+            title: title.clone(),
+            status: Some(status.to_string()),
+            priority: Some(priority.to_string()),
+            tags: tags.filter(|tags| !tags.is_empty()),
+            project: item["project"].as_str().map(str::to_string),
+            created: taskwarrior_date(&item["entry"]).or_else(|| Some(task_timestamp(config))),
+            due: taskwarrior_date(&item["due"]),
+            completed: taskwarrior_date(&item["end"]),
+            started: taskwarrior_date(&item["start"]),
+            branch: None,
+            scheduled: taskwarrior_date(&item["scheduled"]),
+            estimate: None,
+            parent: None,
+            depends_on: (!depends_on.is_empty()).then_some(depends_on),
+            recur,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: None,
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        };
+
+        // The UUID, urgency, and UDAs are kept as extra front-matter fields
+        let mut content = format_front_matter(&task);
+        if let Some(uuid) = item["uuid"].as_str() {
+            content = set_front_matter_value(&content, "uuid", uuid);
+        }
+        if let Some(urgency) = item["urgency"].as_f64() {
+            content = set_front_matter_value(&content, "urgency", &format!("{:.2}", urgency));
+        }
+        if let Some(object) = item.as_object() {
+            for (key, value) in object {
+                if TASKWARRIOR_FIELDS.contains(&key.as_str()) {
+                    continue;
+                }
+                let value = match value {
+                    serde_json::Value::String(s) => yaml_quote(s),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    _ => continue,
+                };
+                content = set_front_matter_value(&content, key, &value);
+            }
+        }
+
+        content.push_str(&format_task_body(&config.body, None));
+
+        // Annotations become comment log entries
+        let annotations = item["annotations"].as_array().cloned().unwrap_or_default();
+        if !annotations.is_empty() {
+            content = format!("{}\n\n## Log\n\n", content.trim_end());
+            for annotation in annotations {
+                let text = annotation["description"].as_str().unwrap_or_default();
+                let stamp = annotation["entry"]
+                    .as_str()
+                    .and_then(|e| chrono::NaiveDateTime::parse_from_str(e, "%Y%m%dT%H%M%SZ").ok())
+                    .map(|time| {
+                        to_local_time(time.and_utc())
+                            .format("%Y-%m-%dT%H:%M:%S%:z")
+                            .to_string()
+                    });
+                match stamp {
+                    Some(stamp) => content.push_str(&format!("- {} {}\n", stamp, text)),
+                    None => content.push_str(&format!("- {}\n", text)),
+                }
+            }
+        }
+
+        let filename = format!(
+            "{}/{}-{}.md",
+            config.tasks.dir,
+            id,
+            slugify(title, &config.slug)
+        );
+
+        if Path::new(&filename).exists() {
+            return Err(anyhow::anyhow!("Task file already exists: {}", filename));
+        }
+
+        if dry_run {
+            println!("📝 Would create {}: {}", id, filename);
+        } else {
+            std::fs::create_dir_all(&config.tasks.dir)?;
+            write_task_file(&filename, &content, config)
+                .context(format!("Failed to write task file: {}", filename))?;
+            println!("✅ Created task {}: {}", id, title);
+        }
+    }
+
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "📥 {} {} task(s) from Taskwarrior, skipped {}",
+        verb,
+        planned.len(),
+        skipped
+    );
+
+    Ok(())
+}
+
+/// Front-matter fields other than the ones mdtasks manages, as strings
+fn custom_front_matter_fields(content: &str) -> Vec<(String, String)> {
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let Some(gray_matter::Pod::Hash(hash)) = matter.parse(content).data else {
+        return Vec::new();
+    };
+    let mut fields: Vec<(String, String)> = hash
+        .iter()
+        .filter(|(key, _)| !FRONT_MATTER_FIELDS.contains(&key.as_str()))
+        .filter_map(|(key, value)| pod_scalar(value).map(|value| (key.clone(), value)))
+        .collect();
+    fields.sort();
+    fields
+}
+
+fn export_taskwarrior(output: Option<&str>, config: &Config) -> Result<()> {
+    let mut tasks = load_tasks(config)?;
+    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+
+    // Bodies are read decrypted so Log entries of encrypted tasks export as text
+    let mut custom = Vec::new();
+    let mut bodies = Vec::new();
+    for task_file in &tasks {
+        let content = read_task_file(&task_file.file_path, config)?;
+        custom.push(custom_front_matter_fields(&content));
+        bodies.push(
+            Matter::<gray_matter::engine::YAML>::new()
+                .parse(&content)
+                .content,
+        );
+    }
+    let uuid_of = |index: usize| {
+        custom[index]
+            .iter()
+            .find(|(key, _)| key == "uuid")
+            .map(|(_, uuid)| uuid.clone())
+            .unwrap_or_else(|| {
+                let task = &tasks[index].task;
+                stable_uuid(&format!(
+                    "{}:{}:{}",
+                    task.id,
+                    task.created.as_deref().unwrap_or(""),
+                    task.title
+                ))
+            })
+    };
+    let uuids: Vec<String> = (0..tasks.len()).map(uuid_of).collect();
+
+    let mut items = Vec::new();
+    for (index, task_file) in tasks.iter().enumerate() {
+        let task = &task_file.task;
+        let mut item = serde_json::Map::new();
+        let mut set = |key: &str, value: serde_json::Value| {
+            item.insert(key.to_string(), value);
+        };
+
+        set("uuid", uuids[index].clone().into());
+        set("description", task.title.clone().into());
+        let status = match task.status.as_deref() {
+            Some("done") | Some("archived") => "completed",
+            Some("cancelled") | Some("canceled") => "deleted",
+            _ => "pending",
+        };
+        set("status", status.into());
+        // Taskwarrior treats any `start` as active, and finished tasks need an `end`
+        let active = task.status.as_deref() == Some("active");
+        let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        for (key, date) in [
+            ("entry", &task.created),
+            ("due", &task.due),
+            ("start", if active { &task.started } else { &None }),
+            ("scheduled", &task.scheduled),
+        ] {
+            if let Some(stamp) = date.as_deref().and_then(taskwarrior_timestamp) {
+                set(key, stamp.into());
+            }
+        }
+        if status != "pending" {
+            let end = task.completed.as_deref().and_then(taskwarrior_timestamp);
+            set("end", end.unwrap_or(now).into());
+        }
+        if let Some(ref project) = task.project {
+            set("project", project.clone().into());
+        }
+        if let Some(ref tags) = task.tags {
+            set("tags", tags.clone().into());
+        }
+        if let Some(priority) = task.priority.as_deref().and_then(Priority::parse) {
+            let letter = match priority {
+                Priority::High => "H",
+                Priority::Medium => "M",
+                Priority::Low => "L",
+            };
+            set("priority", letter.into());
+        }
+        let depends: Vec<String> = task
+            .depends_on
+            .iter()
+            .flatten()
+            .filter_map(|dep| {
+                tasks
+                    .iter()
+                    .position(|other| same_task_id(&other.task.id, dep))
+                    .map(|i| uuids[i].clone())
+            })
+            .collect();
+        if !depends.is_empty() {
+            set("depends", depends.into());
+        }
+
+        // Log entries become annotations
+        let annotations: Vec<serde_json::Value> = section_lines(&bodies[index], "Log")
+            .into_iter()
+            .filter_map(|line| line.trim().strip_prefix("- "))
+            .map(|entry| {
+                let (stamp, text) = entry.split_once(' ').unwrap_or((entry, ""));
+                match chrono::DateTime::parse_from_rfc3339(stamp) {
+                    Ok(time) => serde_json::json!({
+                        "entry": time.with_timezone(&chrono::Utc).format("%Y%m%dT%H%M%SZ").to_string(),
+                        "description": text.replace("**", ""),
+                    }),
+                    Err(_) => serde_json::json!({ "description": entry }),
+                }
+            })
+            .collect();
+        if !annotations.is_empty() {
+            set("annotations", annotations.into());
+        }
+
+        // Custom front-matter fields go out as UDAs, plus the mdtasks ID
+        for (key, value) in &custom[index] {
+            if key != "uuid" && key != "urgency" && !TASKWARRIOR_FIELDS.contains(&key.as_str()) {
+                set(key, value.clone().into());
+            }
+        }
+        if let Some(ref estimate) = task.estimate {
+            set("estimate", estimate.clone().into());
+        }
+        set("mdtasks_id", task.id.clone().into());
+
+        items.push(serde_json::Value::Object(item));
+    }
+
+    let json = serde_json::to_string_pretty(&items)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, json + "\n").context(format!("Failed to write {}", path))?;
+            println!("📤 Exported {} task(s) to {}", items.len(), path);
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
+
+// JSON Lines export
+
+/// Stream every task as one JSON object per line: the front-matter fields
+/// (custom ones included), the file path, and the body as `content`
+fn export_jsonl(output: Option<&str>, config: &Config) -> Result<()> {
+    use std::io::Write;
+
+    let mut tasks = load_tasks(config)?;
+    tasks.sort_by(|a, b| a.task.id.cmp(&b.task.id));
+
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).context(format!("Failed to create {}", path))?,
+        )),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    for task_file in &tasks {
+        let mut item = serde_json::to_value(&task_file.task)?;
+        for (key, value) in custom_front_matter_fields(&task_file.content) {
+            if item.get(&key).is_none() {
+                item[key] = serde_json::Value::String(value);
+            }
+        }
+        item["file"] = serde_json::Value::String(task_file.file_path.clone());
+        let body = Matter::<gray_matter::engine::YAML>::new()
+            .parse(&task_file.content)
+            .content;
+        item["content"] = serde_json::Value::String(body);
+        writeln!(out, "{}", item)?;
+    }
+    out.flush()?;
+
+    if let Some(path) = output {
+        println!("📤 Exported {} task(s) to {}", tasks.len(), path);
+    }
+    Ok(())
+}
+
+// HTML dashboard
+
+const DASHBOARD_CSS: &str = "body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+a { color: #0969da; text-decoration: none; }
+.board { display: flex; gap: 1rem; align-items: flex-start; overflow-x: auto; }
+.column { background: #f6f8fa; border-radius: 6px; padding: 0.5rem; min-width: 16rem; }
+.column h3 { margin: 0.25rem 0.5rem; text-transform: capitalize; }
+.card { background: #fff; border: 1px solid #d0d7de; border-left: 4px solid #8c959f; border-radius: 4px; margin: 0.5rem 0; padding: 0.5rem; }
+.card.high { border-left-color: #cf222e; }
+.card.medium { border-left-color: #bf8700; }
+.card.low { border-left-color: #1a7f37; }
+.meta { color: #57606a; font-size: 0.85em; }
+.overdue { color: #cf222e; font-weight: 600; }
+footer { margin-top: 2rem; color: #57606a; font-size: 0.85em; }
+";
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Wrap page content in the dashboard layout; `root` is the relative path to the site root
+fn html_page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body>\n{body}<footer>Generated by mdtasks on {date}</footer>\n</body>\n</html>\n",
+        title = html_escape(title),
+        root = root,
+        body = body,
+        date = local_now().format("%Y-%m-%d %H:%M"),
+    )
+}
+
+/// Page name of each project under `projects/`: its slug, with `-2`, `-3`, …
+/// added when another project's name slugifies the same
+fn project_page_names<'a>(
+    projects: impl Iterator<Item = &'a str>,
+    config: &Config,
+) -> std::collections::BTreeMap<&'a str, String> {
+    let mut pages = std::collections::BTreeMap::new();
+    // Compared case-insensitively, since "API" and "api" share a file on macOS
+    let mut taken = std::collections::HashSet::new();
+    for project in projects {
+        let slug = slugify(project, &config.slug);
+        let slug = if slug.is_empty() {
+            "project".to_string()
+        } else {
+            slug
+        };
+        let mut page = slug.clone();
+        let mut n = 2;
+        while !taken.insert(page.to_lowercase()) {
+            page = format!("{}-{}", slug, n);
+            n += 1;
+        }
+        pages.insert(project, page);
+    }
+    pages
+}
+
+fn html_task_card(
+    task_file: &TaskFile,
+    root: &str,
+    pages: &std::collections::BTreeMap<&str, String>,
+) -> String {
+    let task = &task_file.task;
+    let mut meta = Vec::new();
+
+    if let Some(ref due) = task.due {
+        if is_overdue(task, &local_now()) {
+            meta.push(format!(
+                "<span class=\"overdue\">due {}</span>",
+                html_escape(due)
+            ));
+        } else {
+            meta.push(format!("due {}", html_escape(due)));
+        }
+    }
+    if let Some(ref project) = task.project {
+        meta.push(format!(
+            "<a href=\"{}projects/{}.html\">{}</a>",
+            root,
+            pages[project.as_str()],
+            html_escape(project)
+        ));
+    }
+    if let Some(ref tags) = task.tags {
+        let tags: Vec<String> = tags
+            .iter()
+            .map(|tag| format!("#{}", html_escape(tag)))
+            .collect();
+        meta.push(tags.join(" "));
+    }
+    let (done, total) = subtask_progress(&task_file.content);
+    if total > 0 {
+        meta.push(format!("{}/{} subtasks", done, total));
+    }
+
+    format!(
+        "<div class=\"card {}\"><strong>{}</strong> {}<div class=\"meta\">{}</div></div>\n",
+        html_escape(task.priority.as_deref().unwrap_or("medium")),
+        html_escape(&task.id),
+        html_escape(&task.title),
+        meta.join(" · ")
+    )
+}
+
+/// Columns of task cards, one per status that has tasks, in workflow order
+fn html_board(
+    tasks: &[&TaskFile],
+    root: &str,
+    pages: &std::collections::BTreeMap<&str, String>,
+    config: &Config,
+) -> String {
+    let order = config.workflow.status_order();
+    let mut statuses: Vec<&str> = order
+        .iter()
+        .copied()
+        .filter(|status| {
+            tasks
+                .iter()
+                .any(|tf| tf.task.status.as_deref() == Some(status))
+        })
+        .collect();
+    for task_file in tasks {
+        let status = task_file.task.status.as_deref().unwrap_or("pending");
+        if !statuses.contains(&status) {
+            statuses.push(status);
+        }
+    }
+
+    let mut html = String::from("<div class=\"board\">\n");
+    for status in statuses {
+        let mut column: Vec<&&TaskFile> = tasks
+            .iter()
+            .filter(|tf| tf.task.status.as_deref().unwrap_or("pending") == status)
+            .collect();
+        column.sort_by_key(|tf| priority_rank(tf.task.priority.as_deref()));
+        html.push_str(&format!(
+            "<div class=\"column\"><h3>{} ({})</h3>\n",
+            html_escape(status),
+            column.len()
+        ));
+        for task_file in column {
+            html.push_str(&html_task_card(task_file, root, pages));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+/// Write a static dashboard for publishing, e.g. on GitHub Pages
+fn export_html(out: &str, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let out = Path::new(out);
+    let projects_dir = out.join("projects");
+    if !dry_run() {
+        std::fs::create_dir_all(&projects_dir)
+            .context(format!("Failed to create {}", projects_dir.display()))?;
+    }
+
+    let mut projects: std::collections::BTreeMap<&str, Vec<&TaskFile>> =
+        std::collections::BTreeMap::new();
+    for task_file in &tasks {
+        if let Some(ref project) = task_file.task.project {
+            projects.entry(project).or_default().push(task_file);
+        }
+    }
+    let pages = project_page_names(projects.keys().copied(), config);
+
+    let now = local_now();
+    let mut overdue: Vec<&TaskFile> = tasks
+        .iter()
+        .filter(|tf| is_overdue(&tf.task, &now))
+        .collect();
+    overdue.sort_by(|a, b| a.task.due.cmp(&b.task.due));
+
+    let mut body = String::from("<h1>Tasks</h1>\n");
+    body.push_str(&format!("<h2>Overdue ({})</h2>\n", overdue.len()));
+    if overdue.is_empty() {
+        body.push_str("<p>Nothing is overdue.</p>\n");
+    }
+    for task_file in &overdue {
+        body.push_str(&html_task_card(task_file, "", &pages));
+    }
+    if !projects.is_empty() {
+        body.push_str("<h2>Projects</h2>\n<ul>\n");
+        for (project, project_tasks) in &projects {
+            let open = project_tasks
+                .iter()
+                .filter(|tf| !is_finished(&tf.task))
+                .count();
+            body.push_str(&format!(
+                "<li><a href=\"projects/{}.html\">{}</a> <span class=\"meta\">{} open, {} total</span></li>\n",
+                pages[project],
+                html_escape(project),
+                open,
+                project_tasks.len()
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str("<h2>Board</h2>\n");
+    let all: Vec<&TaskFile> = tasks.iter().collect();
+    body.push_str(&html_board(&all, "", &pages, config));
+
+    let write = |path: PathBuf, content: String| {
+        if dry_run() {
+            println!("📄 Would write {}", path.display());
+            return Ok(());
+        }
+        std::fs::write(&path, content).context(format!("Failed to write {}", path.display()))
+    };
+    write(out.join("style.css"), DASHBOARD_CSS.to_string())?;
+    write(out.join("index.html"), html_page("Tasks", "", &body))?;
+
+    for (project, project_tasks) in &projects {
+        let body = format!(
+            "<p><a href=\"../index.html\">← All tasks</a></p>\n<h1>{}</h1>\n{}",
+            html_escape(project),
+            html_board(project_tasks, "../", &pages, config)
+        );
+        write(
+            projects_dir.join(format!("{}.html", pages[project])),
+            html_page(project, "../", &body),
+        )?;
+    }
+
+    if dry_run() {
+        return Ok(());
+    }
+    println!(
+        "🌐 Wrote dashboard for {} task(s) and {} project(s) to {}",
+        tasks.len(),
+        projects.len(),
+        out.join("index.html").display()
+    );
+    Ok(())
+}
+
+// Encryption
+
+const AGE_ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const PGP_ARMOR_HEADER: &str = "-----BEGIN PGP MESSAGE-----";
+
+fn is_encrypted_body(body: &str) -> bool {
+    let body = body.trim_start();
+    body.starts_with(AGE_ARMOR_HEADER) || body.starts_with(PGP_ARMOR_HEADER)
+}
+
+/// Split a task file after its closing `---` line into front-matter and body
+fn split_front_matter(content: &str) -> (&str, &str) {
+    if !content.starts_with("---") {
+        return ("", content);
+    }
+    let mut offset = 0;
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        if i > 0 && line.trim_end() == "---" {
+            return content.split_at(offset);
+        }
+    }
+    ("", content)
+}
+
+fn wants_encryption(front_matter: &str) -> bool {
+    let matter = Matter::<gray_matter::engine::YAML>::new();
+    let Some(gray_matter::Pod::Hash(hash)) = matter.parse(front_matter).data else {
+        return false;
+    };
+    match hash.get("encrypted") {
+        Some(gray_matter::Pod::Boolean(flag)) => *flag,
+        Some(gray_matter::Pod::String(flag)) => flag == "true",
+        _ => false,
+    }
+}
+
+/// Pipe `input` through an encryption program and return its output
+fn run_crypto_tool(program: &str, args: &[String], input: &str) -> Result<String> {
+    use std::io::Write;
+
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .context(format!("Failed to run {} (is it installed?)", program))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("{} exited with {}", program, output.status));
+    }
+    String::from_utf8(output.stdout).context(format!("{} produced invalid UTF-8", program))
+}
+
+fn encrypt_body(body: &str, config: &Config) -> Result<String> {
+    let encryption = &config.encryption;
+    if encryption.recipients.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No recipients to encrypt to; set [encryption] recipients in the config"
+        ));
+    }
+    let mut args = Vec::new();
+    match encryption.tool.as_str() {
+        "age" => {
+            args.extend(["--encrypt", "--armor"].map(String::from));
+            for recipient in &encryption.recipients {
+                args.extend(["-r".to_string(), recipient.clone()]);
+            }
+        }
+        "gpg" => {
+            args.extend(["--batch", "--yes", "--armor", "--encrypt"].map(String::from));
+            for recipient in &encryption.recipients {
+                args.extend(["-r".to_string(), recipient.clone()]);
+            }
+        }
+        tool => {
+            return Err(anyhow::anyhow!(
+                "Unknown encryption tool: {} (expected age or gpg)",
+                tool
+            ))
+        }
+    }
+    run_crypto_tool(&encryption.tool, &args, body)
+}
+
+fn decrypt_body(body: &str, config: &Config) -> Result<String> {
+    let body = body.trim_start();
+    let mut args = Vec::new();
+    let program = if body.starts_with(PGP_ARMOR_HEADER) {
+        args.extend(["--quiet", "--decrypt"].map(String::from));
+        "gpg"
+    } else {
+        let identity =
+            config.encryption.identity.as_deref().context(
+                "No age identity to decrypt with; set [encryption] identity in the config",
+            )?;
+        args.extend(["--decrypt", "-i"].map(String::from));
+        args.push(shellexpand::tilde(identity).into_owned());
+        "age"
+    };
+    run_crypto_tool(program, &args, body).context("Failed to decrypt task body")
+}
+
+/// Read a task file, decrypting its body when it is stored encrypted
+fn read_task_file(path: &str, config: &Config) -> Result<String> {
+    let content =
+        std::fs::read_to_string(path).context(format!("Failed to read task file: {}", path))?;
+    let (front_matter, body) = split_front_matter(&content);
+    if !is_encrypted_body(body) {
+        return Ok(content);
+    }
+    let plain = decrypt_body(body, config).context(format!("Cannot read {}", path))?;
+    Ok(format!("{}{}", front_matter, plain))
+}
+
+/// Encrypt the body of task file content marked `encrypted: true`; `None`
+/// when it is already encrypted or not marked
+fn encrypt_task_content(content: &str, config: &Config) -> Result<Option<String>> {
+    let (front_matter, body) = split_front_matter(content);
+    if front_matter.is_empty() || is_encrypted_body(body) || !wants_encryption(front_matter) {
+        return Ok(None);
+    }
+    let encrypted = encrypt_body(body, config)?;
+    Ok(Some(format!("{}\n{}", front_matter, encrypted)))
+}
+
+/// Turn encryption of a task's body on or off
+fn set_task_encryption(id: String, encrypted: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let tasks = load_tasks(config)?;
+    let task_file = find_task(tasks, &id)?;
+    let content = read_task_file(&task_file.file_path, config)?;
+
+    let new_content = if encrypted {
+        set_front_matter_value(&content, "encrypted", "true")
+    } else {
+        remove_front_matter_value(&content, "encrypted")
+    };
+    write_task_file(&task_file.file_path, &new_content, config)?;
+
+    if encrypted {
+        println!("🔒 Encrypted task {}: {}", id, task_file.task.title);
+    } else {
+        println!("🔓 Decrypted task {}: {}", id, task_file.task.title);
+    }
+    Ok(())
+}
+
+// Hooks
+
+/// Run the commands configured for `event` through the shell, passing the
+/// task as JSON on stdin; a failing hook is reported but never fails the command
+fn run_hooks(event: &str, task: &Task, file_path: &str, content: &str, config: &Config) {
+    let commands = match event {
+        "on-add" => &config.hooks.on_add,
+        "on-start" => &config.hooks.on_start,
+        "on-done" => &config.hooks.on_done,
+        "on-modify" => &config.hooks.on_modify,
+        _ => return,
+    };
+    if commands.is_empty() {
+        return;
+    }
+
+    let mut payload = serde_json::to_value(task).unwrap_or_default();
+    payload["event"] = serde_json::Value::String(event.to_string());
+    payload["file"] = serde_json::Value::String(file_path.to_string());
+    // Same shape as the REST API: the body without front-matter. Callers pass
+    // decrypted content, so an encrypted body is left out rather than handed
+    // to scripts that post to chats or spreadsheets
+    payload["content"] = if wants_encryption(split_front_matter(content).0) {
+        serde_json::Value::Null
+    } else {
+        serde_json::Value::String(
+            Matter::<gray_matter::engine::YAML>::new()
+                .parse(content)
+                .content,
+        )
+    };
+    let payload = payload.to_string();
+
+    for command in commands {
+        if dry_run() {
+            println!("🪝 Would run {} hook: {}", event, command);
+            continue;
+        }
+        if let Err(e) = run_hook(command, event, &payload) {
+            eprintln!("⚠️  Hook {} '{}': {:#}", event, command, e);
+        }
+    }
+}
+
+/// Run the hooks for a task file that was just written: `on-add` for a new
+/// task, `on-start` or `on-done` when its status became active or done, and
+/// `on-modify` for every change to an existing task. Files that are not
+/// tasks, such as milestones.toml, run none
+fn run_task_hooks(path: &str, before: Option<&str>, content: &str, config: &Config) {
+    let task_of = |content: &str| {
+        let data = Matter::<gray_matter::engine::YAML>::new()
+            .parse(content)
+            .data?;
+        extract_task_from_pod(&data).ok()
+    };
+    let Some(task) = task_of(content) else {
+        return;
+    };
+    let Some(before) = before else {
+        run_hooks("on-add", &task, path, content, config);
+        return;
+    };
+    if task_of(before).and_then(|old| old.status) != task.status {
+        match task.status.as_deref() {
+            Some("active") => run_hooks("on-start", &task, path, content, config),
+            Some("done") => run_hooks("on-done", &task, path, content, config),
+            _ => {}
+        }
+    }
+    run_hooks("on-modify", &task, path, content, config);
+}
+
+fn run_hook(command: &str, event: &str, payload: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(shellexpand::tilde(command).as_ref())
+        .env("MDTASKS_EVENT", event)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .context("Failed to start hook")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores its input may close stdin early
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+    let status = child.wait().context("Failed to wait for hook")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("exited with {}", status));
+    }
+    Ok(())
+}
+
+// Chat webhook
+
+fn webhook_queue_path() -> PathBuf {
+    state_dir().join("state").join("webhook_queue.jsonl")
+}
+
+/// Webhook event for a task entering `status`, if it is one that gets announced
+fn status_event(status: &str) -> Option<&'static str> {
+    match status {
+        "done" => Some("done"),
+        "active" => Some("start"),
+        "cancelled" | "canceled" => Some("cancel"),
+        _ => None,
+    }
+}
+
+/// Queue a chat message for a task's status change and, unless the webhook
+/// is in queue mode, send it right away; failures never fail the command
+fn announce_status_change(task: &Task, config: &Config) {
+    let webhook = &config.webhook;
+    let Some(event) = task.status.as_deref().and_then(status_event) else {
+        return;
+    };
+    if webhook.url.is_none() || !webhook.events.iter().any(|e| e == event) {
+        return;
+    }
+    if !webhook.projects.is_empty()
+        && !task
+            .project
+            .as_ref()
+            .is_some_and(|project| webhook.projects.contains(project))
+    {
+        return;
+    }
+    if !webhook.tags.is_empty()
+        && !task
+            .tags
+            .iter()
+            .flatten()
+            .any(|tag| webhook.tags.contains(tag))
+    {
+        return;
+    }
+
+    let icon = match event {
+        "done" => "✅",
+        "start" => "🚀",
+        _ => "🚫",
+    };
+    let mut text = format!("{} {} {}", icon, task.id, task.title);
+    if let Some(author) = git_user_name() {
+        text.push_str(&format!(" — {}", author));
+    }
+    if dry_run() {
+        println!("📣 Would post to webhook: {}", text);
+        return;
+    }
+
+    let result = queue_webhook_message(&text).and_then(|_| {
+        if webhook.queue {
+            Ok(())
+        } else {
+            flush_webhook_queue(config, true)
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("⚠️  Webhook: {:#}", e);
+    }
+}
+
+fn queue_webhook_message(text: &str) -> Result<()> {
+    use std::io::Write;
+
+    let path = webhook_queue_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context(format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::json!({ "text": text }))?;
+    Ok(())
+}
+
+/// POST a JSON payload to the webhook URL with curl; the URL embeds the
+/// webhook's token, so it goes through `curl_request` rather than the command line
+fn post_webhook(url: &str, payload: &str) -> Result<()> {
+    let (code, body) = curl_request(
+        "POST",
+        url,
+        None,
+        &["Content-Type: application/json"],
+        Some(payload),
+    )
+    .context("Failed to post to webhook")?;
+    if !(200..300).contains(&code) {
+        return Err(anyhow::anyhow!(
+            "Failed to post to webhook: HTTP {} {}",
+            code,
+            body.trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Send queued messages in order, keeping the ones from the first failure on
+fn flush_webhook_queue(config: &Config, quiet: bool) -> Result<()> {
+    let url = config
+        .webhook
+        .url
+        .as_deref()
+        .context("No webhook configured: set url in [webhook]")?;
+
+    let path = webhook_queue_path();
+    let queued = match std::fs::read_to_string(&path) {
+        Ok(queued) => queued,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+    };
+    let messages: Vec<&str> = queued.lines().filter(|l| !l.trim().is_empty()).collect();
+    if dry_run() {
+        for message in &messages {
+            println!("📣 Would post to webhook: {}", message);
+        }
+        return Ok(());
+    }
+
+    let mut sent = 0;
+    let mut error = None;
+    for message in &messages {
+        if let Err(e) = post_webhook(url, message) {
+            error = Some(e);
+            break;
+        }
+        sent += 1;
+    }
+
+    let remaining = &messages[sent..];
+    if remaining.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+    } else {
+        let mut rest = remaining.join("\n");
+        rest.push('\n');
+        std::fs::write(&path, rest).context(format!("Failed to write {}", path.display()))?;
+    }
+
+    if !quiet {
+        println!("📣 Sent {} queued message(s)", sent);
+    }
+    match error {
+        Some(e) => Err(e.context(format!(
+            "{} message(s) left in the queue; retry with `mdtasks webhook flush`",
+            remaining.len()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn test_webhook(config: &Config) -> Result<()> {
+    let url = config
+        .webhook
+        .url
+        .as_deref()
+        .context("No webhook configured: set url in [webhook]")?;
+    let payload = serde_json::json!({ "text": "👋 mdtasks webhook test" }).to_string();
+    post_webhook(url, &payload)?;
+    println!("✅ Posted a test message to the webhook");
+    Ok(())
+}
+
+// Reminders
+
+/// Last reminder sent for a task, so repeated runs don't resend it
+#[derive(Serialize, Deserialize)]
+struct LastNotified {
+    /// Due date the reminder was for; changing it restarts the chain
+    due: String,
+    /// Escalation step that fired ("3d", "0d", or "overdue:<date>" for daily reminders)
+    step: String,
+}
+
+fn notify_state_path() -> PathBuf {
+    state_dir().join("state").join("last_notified.json")
+}
+
+/// Escalation step reached `days_left` days before the due date, if any
+fn current_escalation_step(
+    chain: &[String],
+    days_left: i64,
+    today: chrono::NaiveDate,
+) -> Option<String> {
+    if days_left < 0 && chain.iter().any(|step| step == "daily") {
+        return Some(format!("overdue:{}", today.format("%Y-%m-%d")));
+    }
+
+    // The tightest threshold already reached is the current step
+    chain
+        .iter()
+        .filter_map(|step| {
+            let days = step.strip_suffix('d')?.parse::<i64>().ok()?;
+            Some((days, step))
+        })
+        .filter(|(days, _)| days_left <= *days)
+        .min_by_key(|(days, _)| *days)
+        .map(|(_, step)| step.clone())
+}
+
+fn notify_due_tasks(dry_run: bool, config: &Config) -> Result<()> {
+    let today = local_today();
+    let state_path = notify_state_path();
+    let mut state: std::collections::BTreeMap<String, LastNotified> =
+        std::fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+    let tasks = load_tasks(config)?;
+    let mut sent = 0;
+
+    for task_file in &tasks {
+        let task = &task_file.task;
+        if is_finished(task) {
+            state.remove(&task.id);
+            continue;
+        }
+
+        // Delegated tasks remind once a day from their follow-up date on
+        if task.status.as_deref() == Some("waiting") {
+            if let Some(follow_up) = task.follow_up.as_deref().and_then(parse_task_date) {
+                let key = format!("{}#follow-up", task.id);
+                let step = format!("follow-up:{}", today.format("%Y-%m-%d"));
+                let follow_up_str = follow_up.format("%Y-%m-%d").to_string();
+                let sent_today = state
+                    .get(&key)
+                    .is_some_and(|last| last.due == follow_up_str && last.step == step);
+                if follow_up <= today && !sent_today {
+                    let days = (today - follow_up).num_days();
+                    let when = match days {
+                        0 => "today".to_string(),
+                        1 => "since yesterday".to_string(),
+                        d => format!("since {} days", d),
+                    };
+                    println!(
+                        "📨 {} {} — follow up with {} ({})",
+                        task.id,
+                        task.title,
+                        task.delegated_to.as_deref().unwrap_or("delegate"),
+                        when
+                    );
+                    sent += 1;
+                    state.insert(
+                        key,
+                        LastNotified {
+                            due: follow_up_str,
+                            step,
+                        },
+                    );
+                }
+            }
+        }
+
+        let due = match task.due.as_deref().and_then(parse_task_date) {
+            Some(due) => due,
+            None => continue,
+        };
+        let priority = task.priority.as_deref().unwrap_or("medium");
+        let chain = match config.notify.escalation.get(priority) {
+            Some(chain) => chain,
+            None => continue,
+        };
+
+        let days_left = (due - today).num_days();
+        let step = match current_escalation_step(chain, days_left, today) {
+            Some(step) => step,
+            None => continue,
+        };
+
+        let due_str = due.format("%Y-%m-%d").to_string();
+        if state
+            .get(&task.id)
+            .is_some_and(|last| last.due == due_str && last.step == step)
+        {
+            continue;
+        }
+
+        let when = match days_left {
+            0 => "due today".to_string(),
+            1 => "due tomorrow".to_string(),
+            d if d > 0 => format!("due in {} days", d),
+            -1 => "overdue by 1 day".to_string(),
+            d => format!("overdue by {} days", -d),
+        };
+        println!("🔔 {} {} — {} ({})", task.id, task.title, when, priority);
+        sent += 1;
+
+        state.insert(task.id.clone(), LastNotified { due: due_str, step });
+    }
+
+    // Forget tasks that no longer exist
+    state.retain(|key, _| {
+        let id = key.trim_end_matches("#follow-up");
+        tasks.iter().any(|tf| tf.task.id == id)
+    });
+
+    if !dry_run {
+        if let Some(parent) = state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&state_path, serde_json::to_string_pretty(&state)?).context(format!(
+            "Failed to write reminder state: {}",
+            state_path.display()
+        ))?;
+    }
+
+    if sent == 0 {
+        println!("✅ No new reminders");
+    }
+
+    Ok(())
+}
+
+// Recurrence
+
+/// Print a rule as mdtasks understands it with its next `count` occurrences
+fn explain_recurrence(expr: &str, count: usize) -> Result<()> {
+    let rule = parse_recurrence(expr)?;
+    let today = local_today();
+
+    println!("🔁 {}", rule);
+    let mut date = today.pred_opt().unwrap_or(today);
+    for _ in 0..count {
+        date = rule.next_after(today, date);
+        println!("  {} ({})", date.format("%Y-%m-%d"), date.format("%a"));
+    }
+    Ok(())
+}
+
+/// Open every checked checklist item again, dropping Obsidian done dates
+fn uncheck_all_subtasks(body: &str) -> String {
+    let mut content = String::new();
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        match trimmed
+            .strip_prefix("- [x]")
+            .or(trimmed.strip_prefix("- [X]"))
+        {
+            Some(rest) => content.push_str(&format!(
+                "{}- [ ] {}",
+                &line[..line.len() - trimmed.len()],
+                stamp_done_date(rest.trim(), false)
+            )),
+            None => content.push_str(line),
+        }
+        content.push('\n');
+    }
+    content
+}
+
+/// Create the next instance of a completed recurring task, due on the
+/// rule's next occurrence after the old due date (or after today)
+fn create_next_occurrence(task: &Task, body: &str, encrypted: bool, config: &Config) -> Result<()> {
+    let Some(expr) = task.recur.as_deref() else {
+        return Ok(());
+    };
+    let rule = parse_recurrence(expr)?;
+
+    let today = local_today();
+    let due = task
+        .due
+        .as_deref()
+        .and_then(|due| chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d").ok());
+    let anchor = due.unwrap_or(today);
+    let mut next_due = rule.next_after(anchor, anchor);
+    while next_due <= today && due.is_none() {
+        next_due = rule.next_after(anchor, next_due);
+    }
+
+    let next_id = get_next_task_id(task.project.as_deref(), config)?;
+    let next = Task {
+        id: next_id.clone(),
+        status: Some("pending".to_string()),
+        created: Some(task_timestamp(config)),
+        due: Some(next_due.format("%Y-%m-%d").to_string()),
+        started: None,
+        completed: None,
+        branch: None,
+        wait_until: None,
+        postponed: None,
+        milestone: None,
+        assignee: None,
+        delegated_to: None,
+        follow_up: None,
+        updated: None,
+        ..task.clone()
+    };
+
+    // Start the new instance with every checklist item open again
+    let mut content = format_front_matter(&next);
+    content.push_str(&uncheck_all_subtasks(body));
+    if encrypted {
+        content = set_front_matter_value(&content, "encrypted", "true");
+    }
+
+    let filename = format!(
+        "{}/{}-{}.md",
+        config.tasks.dir,
+        next_id,
+        slugify(&next.title, &config.slug)
+    );
+    write_task_file(&filename, &content, config)
+        .context(format!("Failed to write task file: {}", filename))?;
+
+    println!(
+        "🔁 Next occurrence: task {} due {} ({})",
+        next_id,
+        next_due.format("%Y-%m-%d"),
+        rule
+    );
+    Ok(())
+}
+
+// Snapshots
+
+/// Compact per-task state kept in a snapshot
+#[derive(Serialize, Deserialize, PartialEq)]
+struct SnapshotTask {
+    title: String,
+    status: Option<String>,
+    priority: Option<String>,
+    due: Option<String>,
+}
+
+fn snapshot_dir() -> PathBuf {
+    state_dir().join("snapshots")
+}
+
+fn current_snapshot(config: &Config) -> Result<std::collections::BTreeMap<String, SnapshotTask>> {
+    Ok(load_tasks(config)?
+        .into_iter()
+        .map(|tf| {
+            let task = tf.task;
+            (
+                task.id,
+                SnapshotTask {
+                    title: task.title,
+                    status: task.status,
+                    priority: task.priority,
+                    due: task.due,
+                },
+            )
+        })
+        .collect())
+}
+
+fn read_snapshot(label: &str) -> Result<std::collections::BTreeMap<String, SnapshotTask>> {
+    let path = snapshot_dir().join(format!("{}.json", label));
+    let content = std::fs::read_to_string(&path).context(format!(
+        "Snapshot '{}' not found (see `mdtasks snapshot list`)",
+        label
+    ))?;
+    serde_json::from_str(&content).context(format!("Invalid snapshot: {}", path.display()))
+}
+
+fn save_snapshot(label: Option<String>, config: &Config) -> Result<()> {
+    let label = label.unwrap_or_else(|| local_now().format("%Y-%m-%d").to_string());
+    if label.is_empty() || label.contains(['/', '\\']) || label.starts_with('.') {
+        return Err(anyhow::anyhow!("Invalid snapshot label: {}", label));
+    }
+
+    let snapshot = current_snapshot(config)?;
+    let dir = snapshot_dir();
+    let path = dir.join(format!("{}.json", label));
+    if dry_run() {
+        println!(
+            "📸 Would save snapshot '{}' ({} tasks) to {}",
+            label,
+            snapshot.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir)
+        .context(format!("Failed to create directory: {}", dir.display()))?;
+    std::fs::write(&path, serde_json::to_string_pretty(&snapshot)?)
+        .context(format!("Failed to write snapshot: {}", path.display()))?;
+
+    println!("📸 Saved snapshot '{}' ({} tasks)", label, snapshot.len());
+
+    Ok(())
+}
+
+fn list_snapshots() -> Result<()> {
+    let mut labels: Vec<String> = match std::fs::read_dir(snapshot_dir()) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .map(|label| label.to_string())
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    labels.sort();
+
+    if labels.is_empty() {
+        println!("No snapshots saved yet (use `mdtasks snapshot save`)");
+    }
+    for label in labels {
+        println!("📸 {}", label);
+    }
+
+    Ok(())
+}
+
+fn diff_snapshots(from: &str, to: Option<&str>, config: &Config) -> Result<()> {
+    let old = read_snapshot(from)?;
+    let new = match to {
+        Some(to) => read_snapshot(to)?,
+        None => current_snapshot(config)?,
+    };
+    let to = to.unwrap_or("now");
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut completed = Vec::new();
+    let mut changed = Vec::new();
+
+    for (id, task) in &new {
+        let Some(before) = old.get(id) else {
+            added.push(format!("{} {}", id, task.title));
+            continue;
+        };
+        if before == task {
+            continue;
+        }
+
+        let just_completed =
+            task.status.as_deref() == Some("done") && before.status.as_deref() != Some("done");
+        if just_completed {
+            completed.push(format!("{} {}", id, task.title));
+        }
+
+        let fields = [
+            ("title", Some(&before.title), Some(&task.title)),
+            ("status", before.status.as_ref(), task.status.as_ref()),
+            ("priority", before.priority.as_ref(), task.priority.as_ref()),
+            ("due", before.due.as_ref(), task.due.as_ref()),
+        ];
+        for (field, was, is) in fields {
+            // Completions are already listed on their own
+            if was != is && !(field == "status" && just_completed) {
+                let show = |value: Option<&String>| value.map_or("-".to_string(), |v| v.clone());
+                changed.push(format!(
+                    "{} {}: {} {} → {}",
+                    id,
+                    task.title,
+                    field,
+                    show(was),
+                    show(is)
+                ));
+            }
+        }
+    }
+    for (id, task) in &old {
+        if !new.contains_key(id) {
+            removed.push(format!("{} {}", id, task.title));
+        }
+    }
+
+    println!("📊 Backlog changes from {} to {}", from, to);
+    let sections = [
+        ("➕ Added", added),
+        ("✅ Completed", completed),
+        ("✏️  Changed", changed),
+        ("➖ Removed", removed),
+    ];
+    let mut any = false;
+    for (heading, entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+        any = true;
+        println!("\n{} ({})", heading, entries.len());
+        for entry in entries {
+            println!("  {}", entry);
+        }
+    }
+    if !any {
+        println!("\nNo changes");
+    }
+
+    Ok(())
+}
+
+// Task history
+
+/// Front-matter values of a task file version, keyed by field name
+fn front_matter_values(content: &str) -> std::collections::BTreeMap<String, String> {
+    let (front_matter, _) = split_front_matter(content);
+    let lines: Vec<String> = front_matter
+        .lines()
+        .filter(|line| line.trim_end() != "---")
+        .map(str::to_string)
+        .collect();
+    front_matter_fields(&lines)
+        .into_iter()
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, block)| {
+            let first = block[0].split_once(':').map_or("", |(_, value)| value);
+            let value = std::iter::once(first)
+                .chain(block[1..].iter().map(String::as_str))
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            (key, value.trim_matches('"').to_string())
+        })
+        .collect()
+}
+
+/// Describe the fields that differ between two versions of a task's
+/// front-matter ("status: pending → active"), leaving out `updated`
+fn field_changes(
+    before: &std::collections::BTreeMap<String, String>,
+    after: &std::collections::BTreeMap<String, String>,
+) -> Vec<String> {
+    let keys: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| *key != "updated")
+        .filter_map(|key| match (before.get(key), after.get(key)) {
+            (Some(old), Some(new)) if old != new => Some(format!("{}: {} → {}", key, old, new)),
+            (None, Some(new)) => Some(format!("{}: + {}", key, new)),
+            (Some(old), None) => Some(format!("{}: − {}", key, old)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Timeline of a task's file from `git log --follow`: per commit, the author,
+/// date, and the front-matter fields that changed
+fn show_task_history(id: String, config: &Config) -> Result<()> {
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+    let task_file = find_task(load_tasks(config)?, &id)?;
+
+    // With the file's path at each commit to follow renames (`--follow`
+    // does not combine with `--reverse`, so the order is flipped below)
+    let log = run_git_command(&[
+        "log",
+        "--follow",
+        "--name-only",
+        "--format=%x1e%h%x1f%an%x1f%aI%x1f%s",
+        "--",
+        &task_file.file_path,
+    ])?;
+    let commits: Vec<Vec<&str>> = log
+        .split('\x1e')
+        .filter_map(|entry| {
+            let mut lines = entry.lines().filter(|line| !line.trim().is_empty());
+            let mut fields: Vec<&str> = lines.next()?.splitn(4, '\x1f').collect();
+            fields.push(lines.next_back()?);
+            (fields.len() == 5).then_some(fields)
+        })
+        .rev()
+        .collect();
+
+    println!(
+        "📜 History of {}: {}",
+        task_file.task.id, task_file.task.title
+    );
+    if commits.is_empty() {
+        println!("  (not committed yet)");
+        return Ok(());
+    }
+
+    let mut previous: Option<(std::collections::BTreeMap<String, String>, String, &str)> = None;
+    for commit in &commits {
+        let [hash, author, date, subject, path] = commit[..] else {
+            continue;
+        };
+        // Deleting the file ends its history; there is nothing to show at that commit
+        let Ok(content) = file_at_revision(hash, path) else {
+            continue;
+        };
+        let date = chrono::DateTime::parse_from_rfc3339(date)
+            .map(|date| {
+                to_local_time(date.with_timezone(&chrono::Utc))
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| date.to_string());
+        println!("\n{}  {}  {} {}", date, author, hash, subject);
+
+        let fields = front_matter_values(&content);
+        let body = split_front_matter(&content).1.to_string();
+        match &previous {
+            None => {
+                let status = fields.get("status").map_or("pending", String::as_str);
+                println!("  ✨ created ({})", status);
+            }
+            Some((before, before_body, before_path)) => {
+                let changes = field_changes(before, &fields);
+                let mut changed = !changes.is_empty();
+                for change in changes {
+                    println!("  {}", change);
+                }
+                if *before_body != body {
+                    changed = true;
+                    println!("  📝 body edited");
+                }
+                if *before_path != path {
+                    changed = true;
+                    println!("  📁 renamed to {}", path);
+                }
+                if !changed {
+                    println!("  (no changes)");
+                }
+            }
+        }
+        previous = Some((fields, body, path));
+    }
+    Ok(())
+}
+
+// Team sync
+
+/// Commit local task changes, rebase onto the remote branch, and push
+fn sync_tasks_with_remote(message: Option<String>, config: &Config) -> Result<()> {
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+    let _lock = lock_tasks(config)?;
+
+    let remote = config.git.sync_remote.as_deref().unwrap_or("origin");
+    let branch = get_current_branch()?;
+    if branch.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Cannot sync from a detached HEAD; check out a branch"
+        ));
+    }
+    // Pulling and pushing another branch from here would mix it with this
+    // branch's code commits
+    if let Some(sync_branch) = config.git.sync_branch.as_deref() {
+        if sync_branch != branch {
+            return Err(anyhow::anyhow!(
+                "git.sync_branch is '{}' but '{}' is checked out; run `git switch {}` first",
+                sync_branch,
+                branch,
+                sync_branch
+            ));
+        }
+    }
+    let tasks_dir = config.tasks.dir.as_str();
+
+    // The push publishes every unpushed commit on the branch, so code
+    // commits waiting there would go out with the tasks
+    let exclude = format!(":(exclude){}", tasks_dir);
+    let outside = run_git_command(&[
+        "log",
+        "--name-only",
+        "--format=",
+        "HEAD",
+        "--not",
+        &format!("--remotes={}", remote),
+        "--",
+        ":/",
+        &exclude,
+    ])?;
+    let mut outside: Vec<&str> = outside.lines().filter(|line| !line.is_empty()).collect();
+    outside.sort_unstable();
+    outside.dedup();
+    if !outside.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Unpushed commits on '{}' change files outside {}: {}\nPush them yourself, or set git.sync_branch to a branch that only holds tasks",
+            branch,
+            tasks_dir,
+            outside.join(", ")
+        ));
+    }
+
+    // Only files under the tasks directory are staged and committed; they
+    // are counted before staging, which --dry-run skips
+    let changed = run_git_command(&[
+        "status",
+        "--porcelain",
+        "--untracked-files=all",
+        "--",
+        tasks_dir,
+    ])?;
+    let changed: Vec<&str> = changed.lines().filter(|line| !line.is_empty()).collect();
+    run_git_command(&["add", "--all", "--", tasks_dir])?;
+    if changed.is_empty() {
+        println!("📭 No local task changes to commit");
+    } else {
+        let message =
+            message.unwrap_or_else(|| format!("Sync tasks ({} file(s) changed)", changed.len()));
+        run_git_command(&["commit", "--quiet", "-m", &message, "--", tasks_dir])?;
+        println!("📝 Committed {} task file(s)", changed.len());
+    }
+
+    let remote_has_branch = std::process::Command::new("git")
+        .args(["ls-remote", "--exit-code", "--heads", remote, &branch])
+        .output()
+        .context(format!("Failed to query remote '{}'", remote))?
+        .status
+        .success();
+    if remote_has_branch {
+        println!("🔄 Pulling {}/{} with rebase...", remote, branch);
+        if let Err(e) = run_git_command(&["pull", "--rebase", "--autostash", remote, &branch]) {
+            return Err(anyhow::anyhow!(
+                "{:#}\nResolve the conflicting task files, `git add` them, then run `git rebase --continue` and `mdtasks sync` again",
+                e
+            ));
+        }
+    }
+
+    println!("⬆️  Pushing to {}/{}...", remote, branch);
+    run_git_command(&["push", "--quiet", remote, &format!("HEAD:{}", branch)])?;
+    println!("✅ Tasks synced with {}/{}", remote, branch);
+    Ok(())
+}
+
+// Conflict resolution
+
+/// A stretch of a conflicted file: lines both sides share, or a conflict hunk
+/// with the common ancestor's lines when git wrote them (`merge.conflictStyle
+/// = diff3` or `zdiff3`)
+enum ConflictSegment {
+    Common(Vec<String>),
+    Conflict {
+        ours: Vec<String>,
+        base: Option<Vec<String>>,
+        theirs: Vec<String>,
+    },
+}
+
+/// A file with git conflict markers, split into segments, plus the side labels
+struct ConflictedFile {
+    segments: Vec<ConflictSegment>,
+    ours_label: String,
+    theirs_label: String,
+}
+
+fn has_conflict_markers(content: &str) -> bool {
+    content.lines().any(|line| line.starts_with("<<<<<<<"))
+}
+
+fn parse_conflicts(content: &str) -> Result<ConflictedFile> {
+    #[derive(PartialEq)]
+    enum Side {
+        Common,
+        Ours,
+        Base,
+        Theirs,
+    }
+    let label = |line: &str| line[7..].trim().to_string();
+
+    let mut file = ConflictedFile {
+        segments: Vec::new(),
+        ours_label: "ours".to_string(),
+        theirs_label: "theirs".to_string(),
+    };
+    let mut side = Side::Common;
+    let (mut common, mut ours, mut theirs) = (Vec::new(), Vec::new(), Vec::new());
+    let mut base: Option<Vec<String>> = None;
+    for line in content.lines() {
+        if line.starts_with("<<<<<<<") && side == Side::Common {
+            if !common.is_empty() {
+                file.segments
+                    .push(ConflictSegment::Common(std::mem::take(&mut common)));
+            }
+            if !label(line).is_empty() {
+                file.ours_label = label(line);
+            }
+            side = Side::Ours;
+        } else if line.starts_with("|||||||") && side == Side::Ours {
+            base = Some(Vec::new());
+            side = Side::Base;
+        } else if line == "=======" && (side == Side::Ours || side == Side::Base) {
+            side = Side::Theirs;
+        } else if line.starts_with(">>>>>>>") && side == Side::Theirs {
+            if !label(line).is_empty() {
+                file.theirs_label = label(line);
+            }
+            file.segments.push(ConflictSegment::Conflict {
+                ours: std::mem::take(&mut ours),
+                base: base.take(),
+                theirs: std::mem::take(&mut theirs),
+            });
+            side = Side::Common;
+        } else {
+            match side {
+                Side::Common => common.push(line.to_string()),
+                Side::Ours => ours.push(line.to_string()),
+                Side::Base => base.get_or_insert_with(Vec::new).push(line.to_string()),
+                Side::Theirs => theirs.push(line.to_string()),
+            }
+        }
+    }
+    if side != Side::Common {
+        return Err(anyhow::anyhow!("Unterminated conflict block"));
+    }
+    if !common.is_empty() {
+        file.segments.push(ConflictSegment::Common(common));
+    }
+    Ok(file)
+}
+
+/// One side of a conflicted file, line by line
+fn conflict_side(segments: &[ConflictSegment], ours: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for segment in segments {
+        match segment {
+            ConflictSegment::Common(common) => lines.extend(common.iter().cloned()),
+            ConflictSegment::Conflict {
+                ours: o, theirs: t, ..
+            } => lines.extend(if ours { o } else { t }.iter().cloned()),
+        }
+    }
+    lines
+}
+
+/// The common ancestor of a conflicted file, when every hunk records it
+fn conflict_base(segments: &[ConflictSegment]) -> Option<Vec<String>> {
+    let mut lines = Vec::new();
+    for segment in segments {
+        match segment {
+            ConflictSegment::Common(common) => lines.extend(common.iter().cloned()),
+            ConflictSegment::Conflict { base, .. } => lines.extend(base.as_ref()?.iter().cloned()),
+        }
+    }
+    Some(lines)
+}
+
+/// Index of the closing front-matter delimiter, if the lines start with front matter
+fn front_matter_end(lines: &[String]) -> Option<usize> {
+    if lines.first().map(|line| line.trim_end()) != Some("---") {
+        return None;
+    }
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim_end() == "---")
+        .map(|i| i + 1)
+}
+
+/// Top-level front-matter fields with their continuation lines, in file order
+fn front_matter_fields(lines: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    for line in lines {
+        let starts_field = !line.starts_with([' ', '\t', '-', '#']) && line.contains(':');
+        match fields.last_mut() {
+            Some((_, block)) if !starts_field => block.push(line.clone()),
+            _ => {
+                let key = if starts_field {
+                    line.split(':')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                } else {
+                    String::new()
+                };
+                fields.push((key, vec![line.clone()]));
+            }
+        }
+    }
+    fields
+}
+
+/// Merge a field both sides changed, or None when a person has to choose
+fn merge_field_value(key: &str, ours: &str, theirs: &str) -> Option<String> {
+    let unquote = |value: &str| value.trim().trim_matches('"').to_string();
+    match key {
+        "status" => {
+            let order = ["pending", "partial", "active", "done", "archived"];
+            let rank = |value: &str| order.iter().position(|s| *s == unquote(value));
+            let (a, b) = (rank(ours)?, rank(theirs)?);
+            Some(if a >= b { ours } else { theirs }.to_string())
+        }
+        "created" | "due" | "scheduled" | "started" | "completed" | "wait_until" | "updated" => {
+            let a = parse_task_date(&unquote(ours))?;
+            let b = parse_task_date(&unquote(theirs))?;
+            // The original creation date is kept; for everything else the later date wins
+            let ours_wins = if key == "created" { a <= b } else { a >= b };
+            Some(if ours_wins { ours } else { theirs }.to_string())
+        }
+        "postponed" => {
+            let a: u32 = ours.trim().parse().ok()?;
+            let b: u32 = theirs.trim().parse().ok()?;
+            Some(a.max(b).to_string())
+        }
+        "tags" | "depends_on" => {
+            let items = |value: &str| -> Option<Vec<String>> {
+                let inner = value.trim().strip_prefix('[')?.strip_suffix(']')?;
+                Some(
+                    inner
+                        .split(',')
+                        .map(|item| item.trim().to_string())
+                        .filter(|item| !item.is_empty())
+                        .collect(),
+                )
+            };
+            let mut merged = items(ours)?;
+            for item in items(theirs)? {
+                if !merged.contains(&item) {
+                    merged.push(item);
+                }
+            }
+            Some(format!("[{}]", merged.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+/// Pick a side for a field that cannot be merged by rule
+fn choose_side(
+    file: &str,
+    key: &str,
+    ours: &[String],
+    theirs: &[String],
+    labels: (&str, &str),
+    prefer: Option<&str>,
+) -> Result<Vec<String>> {
+    match prefer {
+        Some("ours") => return Ok(ours.to_vec()),
+        Some("theirs") => return Ok(theirs.to_vec()),
+        _ => {}
+    }
+    println!("❓ {}: conflicting `{}`", file, key);
+    println!("  1) {}: {}", labels.0, ours.join("\n     "));
+    println!("  2) {}: {}", labels.1, theirs.join("\n     "));
+
+    // Without a terminal (scripts, git hooks) or after Ctrl-D nobody can answer
+    let no_answer = || {
+        anyhow::anyhow!(
+            "No answer for `{}` in {}; run `mdtasks resolve --prefer ours|theirs` to pick sides without asking",
+            key,
+            file
+        )
+    };
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err(no_answer());
+    }
+    loop {
+        use std::io::Write;
+
+        print!("Keep which? [1/2]: ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Err(no_answer());
+        }
+        match input.trim() {
+            "1" => return Ok(ours.to_vec()),
+            "2" => return Ok(theirs.to_vec()),
+            _ => {}
+        }
+    }
+}
+
+/// Merge two front-matter blocks field by field. With the common ancestor's
+/// fields, a field only one side changed takes that side's value, and a field
+/// one side removed without the other changing it stays removed
+fn merge_front_matter(
+    file: &str,
+    ours: &[String],
+    theirs: &[String],
+    base: Option<&[String]>,
+    labels: (&str, &str),
+    prefer: Option<&str>,
+) -> Result<Vec<String>> {
+    let theirs_fields = front_matter_fields(theirs);
+    let ours_fields = front_matter_fields(ours);
+    let base_fields = base.map(front_matter_fields).unwrap_or_default();
+    let base_block = |key: &str| {
+        base_fields
+            .iter()
+            .find(|(k, _)| k == key && !key.is_empty())
+            .map(|(_, block)| block)
+    };
+    let mut merged = Vec::new();
+    for (key, block) in &ours_fields {
+        let other = theirs_fields
+            .iter()
+            .find(|(k, _)| k == key && !key.is_empty());
+        match other {
+            // Removed on their side and left alone on ours
+            None if base_block(key) == Some(block) => {}
+            Some((_, other)) if other != block && base_block(key) == Some(block) => {
+                merged.extend(other.iter().cloned())
+            }
+            Some((_, other)) if other != block && base_block(key) == Some(other) => {
+                merged.extend(block.iter().cloned())
+            }
+            Some((_, other)) if other != block => {
+                let value = |block: &[String]| block[0][key.len() + 1..].trim().to_string();
+                let rule = if block.len() == 1 && other.len() == 1 {
+                    merge_field_value(key, &value(block), &value(other))
+                } else {
+                    None
+                };
+                match rule {
+                    Some(value) => merged.push(format!("{}: {}", key, value)),
+                    None => merged.extend(choose_side(file, key, block, other, labels, prefer)?),
+                }
+            }
+            _ => merged.extend(block.iter().cloned()),
+        }
+    }
+    // Fields only their side has are kept, unless we removed them
+    for (key, block) in &theirs_fields {
+        let only_theirs = key.is_empty() || !ours_fields.iter().any(|(k, _)| k == key);
+        let removed_by_us = !key.is_empty() && base_block(key) == Some(block);
+        if only_theirs && !removed_by_us && !merged.iter().any(|line| block.first() == Some(line)) {
+            merged.extend(block.iter().cloned());
+        }
+    }
+    Ok(merged)
+}
+
+/// Merge a body conflict hunk: both sides' lines are kept, ours first, and a
+/// checklist item checked on either side stays checked
+fn merge_body_hunk(ours: &[String], theirs: &[String]) -> Vec<String> {
+    let unchecked = |line: &str| {
+        line.replacen("- [x] ", "- [ ] ", 1)
+            .replacen("- [X] ", "- [ ] ", 1)
+    };
+    let mut merged = ours.to_vec();
+    for line in theirs {
+        let key = unchecked(line);
+        match merged
+            .iter()
+            .position(|existing| unchecked(existing) == key)
+        {
+            Some(i) => {
+                if key != *line {
+                    merged[i] = line.clone();
+                }
+            }
+            None => merged.push(line.clone()),
+        }
+    }
+    merged
+}
+
+/// Resolve every conflict in one file, returning the merged content
+fn resolve_conflicted_content(file: &str, content: &str, prefer: Option<&str>) -> Result<String> {
+    let conflicted = parse_conflicts(content)?;
+    let labels = (
+        conflicted.ours_label.as_str(),
+        conflicted.theirs_label.as_str(),
+    );
+    let ours = conflict_side(&conflicted.segments, true);
+    let theirs = conflict_side(&conflicted.segments, false);
+    let base = conflict_base(&conflicted.segments);
+    let base_front_matter = base
+        .as_ref()
+        .and_then(|base| Some(&base[1..front_matter_end(base)?]));
+
+    let mut merged = Vec::new();
+    let (ours_body, theirs_body) = match (front_matter_end(&ours), front_matter_end(&theirs)) {
+        (Some(ours_end), Some(theirs_end)) => {
+            merged.push("---".to_string());
+            merged.extend(merge_front_matter(
+                file,
+                &ours[1..ours_end],
+                &theirs[1..theirs_end],
+                base_front_matter,
+                labels,
+                prefer,
+            )?);
+            merged.push("---".to_string());
+            (ours_end + 1, theirs_end + 1)
+        }
+        _ => (0, 0),
+    };
+
+    // Walk the segments again, keeping only the lines past the front matter
+    let (mut ours_line, mut theirs_line) = (0, 0);
+    for segment in &conflicted.segments {
+        match segment {
+            ConflictSegment::Common(lines) => {
+                for line in lines {
+                    if ours_line >= ours_body {
+                        merged.push(line.clone());
+                    }
+                    ours_line += 1;
+                    theirs_line += 1;
+                }
+            }
+            ConflictSegment::Conflict { ours, theirs, .. } => {
+                let ours_part: Vec<String> = ours
+                    .iter()
+                    .skip(ours_body.saturating_sub(ours_line))
+                    .cloned()
+                    .collect();
+                let theirs_part: Vec<String> = theirs
+                    .iter()
+                    .skip(theirs_body.saturating_sub(theirs_line))
+                    .cloned()
+                    .collect();
+                merged.extend(merge_body_hunk(&ours_part, &theirs_part));
+                ours_line += ours.len();
+                theirs_line += theirs.len();
+            }
+        }
+    }
+
+    let mut result = merged.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+fn resolve_conflicts(prefer: Option<&str>, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+    let (paths, _) = scan_task_paths(config)?;
+    let in_git = is_git_repo()?;
+
+    let mut resolved = 0;
+    for path in paths {
+        let content =
+            std::fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        if !has_conflict_markers(&content) {
+            continue;
+        }
+        let file = path.display().to_string();
+        let merged = resolve_conflicted_content(&file, &content, prefer)
+            .context(format!("Failed to resolve {}", file))?;
+        write_task_file(&file, &merged, config)?;
+        if in_git {
+            run_git_command(&["add", "--", &file])?;
+        }
+        println!("✅ Resolved {}", file);
+        resolved += 1;
+    }
+
+    if resolved == 0 {
+        println!("No conflicted task files found.");
+    } else if in_git {
+        println!("📌 {} file(s) resolved and staged", resolved);
+    }
+    Ok(())
+}
+
+// Commit trailer sync
+
+fn last_synced_commit_path() -> PathBuf {
+    state_dir().join("state").join("last_synced_commit")
+}
+
+/// Task IDs listed in a trailer value such as "014, #15"
+fn trailer_task_ids(value: &str) -> Vec<String> {
+    value
+        .split([',', ' '])
+        .map(|id| id.trim().trim_start_matches('#'))
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .collect()
+}
+
+fn sync_commit_trailers(since: Option<String>, dry_run: bool, config: &Config) -> Result<()> {
+    if !is_git_repo()? {
+        return Err(anyhow::anyhow!("Not in a git repository"));
+    }
+
+    let state_path = last_synced_commit_path();
+    let since = since.or_else(|| {
+        std::fs::read_to_string(&state_path)
+            .ok()
+            .map(|commit| commit.trim().to_string())
+            .filter(|commit| !commit.is_empty())
+    });
+    let base = base_branch(config);
+    let range = match &since {
+        Some(since) => format!("{}..{}", since, base),
+        None => base,
+    };
+
+    // One record per commit: full hash, short hash, author, subject, trailers
+    let output = run_git_command(&[
+        "log",
+        "--reverse",
+        "--format=%H%x1f%h%x1f%an%x1f%s%x1f%(trailers:only,unfold)%x1e",
+        &range,
+    ])?;
+
+    let _lock = lock_tasks(config)?;
+    let tasks = load_tasks(config)?;
+    let find_task = |id: &str| tasks.iter().find(|tf| same_task_id(&tf.task.id, id));
+
+    let mut last_commit = None;
+    let mut applied = 0;
+    let mut finished = std::collections::HashSet::new();
+
+    for record in output
+        .split('\x1e')
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+    {
+        let fields: Vec<&str> = record.splitn(5, '\x1f').collect();
+        let [hash, short, author, subject, trailers] = fields[..] else {
+            continue;
+        };
+        last_commit = Some(hash.to_string());
+
+        for trailer in trailers.lines() {
+            let Some((key, value)) = trailer.split_once(':') else {
+                continue;
+            };
+            let done = match key.trim().to_lowercase().as_str() {
+                "task-done" => true,
+                "refs-task" => false,
+                _ => continue,
+            };
+
+            for id in trailer_task_ids(value) {
+                let Some(task_file) = find_task(&id) else {
+                    println!("⚠️  {} references unknown task '{}'", short, id);
+                    continue;
+                };
+                let task = &task_file.task;
+                let finish = done && !is_finished(task) && finished.insert(task.id.clone());
+                let action = if finish { "Done in" } else { "Referenced in" };
+
+                if dry_run {
+                    println!(
+                        "🔍 {} {} {} commit {}: {}",
+                        task.id,
+                        task.title,
+                        action.to_lowercase(),
+                        short,
+                        subject
+                    );
+                    continue;
+                }
+
+                if finish {
+                    mark_task_done(task.id.clone(), None, config)?;
+                }
+                add_task_comment(
+                    task.id.clone(),
+                    format!("{} commit {}: {}", action, short, subject),
+                    Some(author.to_string()),
+                    config,
+                )?;
+                applied += 1;
+            }
+        }
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if let Some(commit) = last_commit {
+        if let Some(parent) = state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&state_path, format!("{}\n", commit)).context(format!(
+            "Failed to write sync state: {}",
+            state_path.display()
+        ))?;
+    }
+
+    if applied == 0 {
+        println!("✅ No task trailers in new commits");
+    } else {
+        println!("🔄 Applied {} task trailer(s)", applied);
+    }
+
+    Ok(())
+}
+
+// CalDAV sync
+//
+// Each task is stored on the server as a VTODO with UID `mdtasks-<id>`.
+// Title, status, priority, and due date flow from the task files to the
+// server; only completion flows back, so tasks can be ticked off from a
+// phone. VTODOs created by other clients are left alone.
+
+/// UID prefix marking VTODOs that mirror a task
+const CALDAV_UID_PREFIX: &str = "mdtasks-";
+
+/// Task IDs that were complete on the server after the last `sync caldav`,
+/// so a task reopened locally is not completed again by the stale server copy
+fn caldav_state_path() -> PathBuf {
+    state_dir().join("state").join("caldav.json")
+}
+
+/// A VTODO on the server
+struct RemoteTodo {
+    href: String,
+    etag: Option<String>,
+    /// Properties by name, without parameters, values unescaped
+    properties: std::collections::HashMap<String, String>,
+}
+
+impl RemoteTodo {
+    fn is_completed(&self) -> bool {
+        self.properties.get("STATUS").map(String::as_str) == Some("COMPLETED")
+            || self.properties.contains_key("COMPLETED")
+    }
+
+    /// The server's completion time as RFC 3339, if it recorded one
+    fn completed_at(&self) -> Option<String> {
+        let value = self.properties.get("COMPLETED")?;
+        let naive =
+            chrono::NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+                .ok()?;
+        Some(
+            naive
+                .and_utc()
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        )
+    }
+}
+
+fn caldav_password(caldav: &CaldavConfig) -> Result<String> {
+    if let Ok(password) = std::env::var("MDTASKS_CALDAV_PASSWORD") {
+        return Ok(password);
+    }
+    if let Some(command) = &caldav.password_command {
+        let output = std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()
+            .context(format!("Failed to run password_command '{}'", command))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "password_command '{}' failed: {}",
+                command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let password = String::from_utf8_lossy(&output.stdout);
+        return Ok(password.lines().next().unwrap_or_default().to_string());
+    }
+    caldav.password.clone().context(
+        "No CalDAV password: set MDTASKS_CALDAV_PASSWORD, or password_command or password in [caldav]",
+    )
+}
+
+/// Inner text of every element with this local name, whatever its namespace prefix
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        let qualified = tag.split_whitespace().next().unwrap_or_default();
+        let local = qualified.rsplit(':').next().unwrap_or_default();
+        if tag.starts_with(['/', '?', '!']) || local != name {
+            continue;
+        }
+        if tag.ends_with('/') {
+            elements.push("");
+            continue;
+        }
+        let close = format!("</{}>", qualified);
+        if let Some(inner_end) = rest.find(&close) {
+            elements.push(&rest[..inner_end]);
+            rest = &rest[inner_end + close.len()..];
+        }
+    }
+    elements
+}
+
+fn xml_unescape(text: &str) -> String {
+    let text = text.trim();
+    if let Some(data) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+    {
+        return data.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn ics_unescape(text: &str) -> String {
+    text.replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Fetch every VTODO in the collection with a calendar-query REPORT
+fn fetch_remote_todos(url: &str, credentials: &str) -> Result<Vec<RemoteTodo>> {
+    let query = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+    let (code, body) = curl_request(
+        "REPORT",
+        url,
+        Some(credentials),
+        &["Depth: 1", "Content-Type: application/xml; charset=utf-8"],
+        Some(query),
+    )?;
+    if code != 207 {
+        return Err(anyhow::anyhow!(
+            "CalDAV server answered {} to the task query; check url and credentials in [caldav]",
+            code
+        ));
+    }
+
+    let mut todos = Vec::new();
+    for response in xml_elements(&body, "response") {
+        let Some(href) = xml_elements(response, "href")
+            .first()
+            .map(|h| xml_unescape(h))
+        else {
+            continue;
+        };
+        let Some(data) = xml_elements(response, "calendar-data")
+            .first()
+            .map(|d| xml_unescape(d))
+        else {
+            continue;
+        };
+        let mut properties = std::collections::HashMap::new();
+        let mut in_todo = false;
+        for line in unfold_ics_lines(&data) {
+            match line.as_str() {
+                "BEGIN:VTODO" => in_todo = true,
+                "END:VTODO" => break,
+                _ if in_todo => {
+                    if let Some((name, value)) = line.split_once(':') {
+                        let name = name.split(';').next().unwrap_or(name);
+                        properties
+                            .entry(name.to_uppercase())
+                            .or_insert_with(|| ics_unescape(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if in_todo {
+            todos.push(RemoteTodo {
+                href,
+                etag: xml_elements(response, "getetag")
+                    .first()
+                    .map(|e| xml_unescape(e))
+                    .filter(|e| !e.is_empty()),
+                properties,
+            });
+        }
+    }
+    Ok(todos)
+}
+
+/// The VTODO properties mirrored from a task, in file order
+fn vtodo_properties(task: &Task) -> Vec<(&'static str, String)> {
+    let status = match task.status.as_deref() {
+        Some("done" | "archived") => "COMPLETED",
+        Some("cancelled" | "canceled") => "CANCELLED",
+        Some("active") => "IN-PROCESS",
+        _ => "NEEDS-ACTION",
+    };
+    let mut properties = vec![
+        ("SUMMARY", task.title.clone()),
+        ("STATUS", status.to_string()),
+    ];
+    // iCalendar priorities run from 1 (highest) to 9 (lowest)
+    if let Some(priority) = task.priority.as_deref().and_then(Priority::parse) {
+        let value = match priority {
+            Priority::High => "1",
+            Priority::Medium => "5",
+            Priority::Low => "9",
+        };
+        properties.push(("PRIORITY", value.to_string()));
+    }
+    if let Some(due) = task.due.as_deref().and_then(parse_task_date) {
+        properties.push(("DUE", due.format("%Y%m%d").to_string()));
+    }
+    properties
+}
+
+/// Whether the server copy already matches the task
+fn remote_todo_matches(remote: &RemoteTodo, task: &Task) -> bool {
+    let wanted = vtodo_properties(task);
+    ["SUMMARY", "STATUS", "PRIORITY", "DUE"].iter().all(|name| {
+        let local = wanted
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str());
+        let remote = remote.properties.get(*name).map(|v| match *name {
+            // Compare dates only, whether or not the server added a time
+            "DUE" => v.get(..8).unwrap_or(v),
+            _ => v.as_str(),
+        });
+        local.unwrap_or_default() == remote.unwrap_or_default()
+    })
+}
+
+fn build_vtodo(task: &Task) -> String {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//mdtasks//mdtasks//EN".to_string(),
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}{}", CALDAV_UID_PREFIX, task.id),
+        format!("DTSTAMP:{}", now),
+    ];
+    let properties = vtodo_properties(task);
+    let completed = properties.contains(&("STATUS", "COMPLETED".to_string()));
+    for (name, value) in properties {
+        lines.push(match name {
+            "SUMMARY" => format!("SUMMARY:{}", ics_escape(&value)),
+            "DUE" => format!("DUE;VALUE=DATE:{}", value),
+            _ => format!("{}:{}", name, value),
+        });
+    }
+    if let Some(completed) = task
+        .completed
+        .as_deref()
+        .and_then(parse_task_datetime)
+        .filter(|_| completed)
+    {
+        lines.push(format!(
+            "COMPLETED:{}",
+            completed
+                .with_timezone(&chrono::Utc)
+                .format("%Y%m%dT%H%M%SZ")
+        ));
+    }
+    if let Some(tags) = task.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let tags: Vec<String> = tags.iter().map(|tag| ics_escape(tag)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.push("END:VTODO".to_string());
+    lines.push("END:VCALENDAR".to_string());
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Absolute URL of an href from a multistatus response
+fn resolve_href(base: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        return href.to_string();
+    }
+    // Keep scheme and host of the collection URL
+    let origin_end = base
+        .find("://")
+        .and_then(|scheme| base[scheme + 3..].find('/').map(|path| scheme + 3 + path))
+        .unwrap_or(base.len());
+    format!("{}{}", &base[..origin_end], href)
+}
+
+fn sync_caldav(dry_run: bool, config: &Config) -> Result<()> {
+    let caldav = &config.caldav;
+    let url = caldav
+        .url
+        .as_deref()
+        .context("No CalDAV task list configured: set url in [caldav]")?;
+    let url = format!("{}/", url.trim_end_matches('/'));
+    let username = caldav
+        .username
+        .as_deref()
+        .context("No CalDAV username: set username in [caldav]")?;
+    let credentials = format!("{}:{}", username, caldav_password(caldav)?);
+
+    let _lock = lock_tasks(config)?;
+    let remote: std::collections::HashMap<String, RemoteTodo> =
+        fetch_remote_todos(&url, &credentials)?
+            .into_iter()
+            .filter_map(|todo| {
+                let id = todo
+                    .properties
+                    .get("UID")?
+                    .strip_prefix(CALDAV_UID_PREFIX)?;
+                Some((id.to_string(), todo))
+            })
+            .collect();
+
+    let state_path = caldav_state_path();
+    let last_completed: std::collections::BTreeMap<String, bool> =
+        std::fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|state| serde_json::from_str(&state).ok())
+            .unwrap_or_default();
+
+    // Pull: tasks completed on the server since the last sync
+    let mut pulled = 0;
+    for task_file in load_tasks(config)? {
+        let task = &task_file.task;
+        let Some(todo) = remote.get(&task.id) else {
+            continue;
+        };
+        if !todo.is_completed()
+            || is_finished(task)
+            || last_completed.get(&task.id).copied().unwrap_or(false)
+        {
+            continue;
+        }
+        if dry_run {
+            println!("🔍 Would mark {} done: {}", task.id, task.title);
+        } else {
+            mark_task_done(task.id.clone(), todo.completed_at(), config)?;
+        }
+        pulled += 1;
+    }
+
+    // Push: open tasks, and finished ones the server still has
+    let tasks = load_tasks(config)?;
+    let mut pushed = 0;
+    let mut unchanged = 0;
+    let mut failed = 0;
+    let mut completed = std::collections::BTreeMap::new();
+    for task_file in &tasks {
+        let task = &task_file.task;
+        let existing = remote.get(&task.id);
+        if existing.is_none() && is_finished(task) {
+            continue;
+        }
+        if existing.is_some_and(|todo| remote_todo_matches(todo, task)) {
+            completed.insert(task.id.clone(), existing.is_some_and(|t| t.is_completed()));
+            unchanged += 1;
+            continue;
+        }
+        if dry_run {
+            let action = if existing.is_some() {
+                "update"
+            } else {
+                "create"
+            };
+            println!("🔍 Would {} {}: {}", action, task.id, task.title);
+            pushed += 1;
+            continue;
+        }
+
+        let (target, condition) = match existing {
+            Some(todo) => (
+                resolve_href(&url, &todo.href),
+                todo.etag.as_ref().map(|etag| format!("If-Match: {}", etag)),
+            ),
+            None => (
+                format!("{}{}{}.ics", url, CALDAV_UID_PREFIX, task.id),
+                Some("If-None-Match: *".to_string()),
+            ),
+        };
+        let mut headers = vec!["Content-Type: text/calendar; charset=utf-8"];
+        headers.extend(condition.as_deref());
+        match curl_request(
+            "PUT",
+            &target,
+            Some(&credentials),
+            &headers,
+            Some(&build_vtodo(task)),
+        ) {
+            Ok((200..=299, _)) => {
+                completed.insert(task.id.clone(), is_finished(task));
+                pushed += 1;
+            }
+            Ok((412, _)) => {
+                eprintln!(
+                    "⚠️  {} changed on the server during the sync; run sync again",
+                    task.id
+                );
+                failed += 1;
+            }
+            Ok((code, _)) => {
+                eprintln!("❌ {}: server answered {} to PUT {}", task.id, code, target);
+                failed += 1;
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {:#}", task.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let orphaned = remote
+        .keys()
+        .filter(|id| !tasks.iter().any(|tf| same_task_id(&tf.task.id, id)))
+        .count();
+    if orphaned > 0 {
+        println!(
+            "ℹ️  {} task(s) on the server have no task file here; they were left alone",
+            orphaned
+        );
+    }
+
+    if dry_run {
+        println!(
+            "🔍 Dry run: {} to push, {} to complete from the server, {} unchanged",
+            pushed, pulled, unchanged
+        );
+        return Ok(());
+    }
+
+    if let Some(parent) = state_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&state_path, serde_json::to_string_pretty(&completed)?).context(format!(
+        "Failed to write sync state: {}",
+        state_path.display()
+    ))?;
+
+    println!(
+        "✅ CalDAV sync: {} pushed, {} completed from the server, {} unchanged",
+        pushed, pulled, unchanged
+    );
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} task(s) could not be pushed", failed));
+    }
+    Ok(())
+}
+
+// Google Tasks sync
+//
+// Each project has a list of its own, and tasks without a project go to the
+// default list. Titles flow from the task files to Google; due dates and
+// completion are merged both ways against the values agreed on at the last
+// sync, the task file winning when both sides changed. Open tasks added in
+// Google are created here.
+
+const GOOGLE_TASKS_API: &str = "https://tasks.googleapis.com/tasks/v1";
+const GOOGLE_DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const GOOGLE_TASKS_SCOPE: &str = "https://www.googleapis.com/auth/tasks";
+
+/// Google task mirroring a task, with the due date and completion both
+/// sides had after the last sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GoogleTaskLink {
+    list: String,
+    id: String,
+    due: Option<String>,
+    completed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GoogleToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Unix time the access token expires
+    expires_at: i64,
+}
+
+fn google_links_path() -> PathBuf {
+    state_dir().join("state").join("google_tasks.json")
+}
+
+/// OAuth tokens belong to the user, so they are kept outside the repository
+fn google_token_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.config/mdtasks/google_token.json").to_string())
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// POST a form to a Google OAuth endpoint, returning the status code and JSON answer
+fn google_oauth_post(url: &str, form: &[(&str, &str)]) -> Result<(u16, serde_json::Value)> {
+    let form: Vec<String> = form
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, percent_encode(value)))
+        .collect();
+    let (code, body) = curl_request(
+        "POST",
+        url,
+        None,
+        &["Content-Type: application/x-www-form-urlencoded"],
+        Some(&form.join("&")),
+    )?;
+    let answer = serde_json::from_str(&body).context(format!("Unexpected answer from {}", url))?;
+    Ok((code, answer))
+}
+
+/// Save the tokens from a token endpoint answer, readable by the user only
+fn save_google_token(answer: &serde_json::Value, refresh_token: Option<String>) -> Result<String> {
+    use std::io::Write;
+
+    let token = GoogleToken {
+        access_token: answer["access_token"]
+            .as_str()
+            .context("No access token in Google's answer")?
+            .to_string(),
+        // Refreshing does not always hand out a new refresh token
+        refresh_token: answer["refresh_token"]
+            .as_str()
+            .map(str::to_string)
+            .or(refresh_token),
+        expires_at: chrono::Utc::now().timestamp() + answer["expires_in"].as_i64().unwrap_or(3600),
+    };
+
+    let path = google_token_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).write(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .context(format!("Failed to write {}", path.display()))?;
+    file.write_all(serde_json::to_string_pretty(&token)?.as_bytes())?;
+    Ok(token.access_token)
+}
+
+/// A current access token: the saved one, a refreshed one, or a new one
+/// authorized through the OAuth device flow
+fn google_access_token(settings: &GoogleTasksConfig) -> Result<String> {
+    let client_id = settings
+        .client_id
+        .as_deref()
+        .context("No Google OAuth client: set client_id in [google_tasks]")?;
+    let client_secret = settings.client_secret.as_deref().unwrap_or_default();
+
+    let saved: Option<GoogleToken> = std::fs::read_to_string(google_token_path())
+        .ok()
+        .and_then(|token| serde_json::from_str(&token).ok());
+    if let Some(token) = saved {
+        if token.expires_at > chrono::Utc::now().timestamp() + 60 {
+            return Ok(token.access_token);
+        }
+        if let Some(refresh_token) = token.refresh_token {
+            let (code, answer) = google_oauth_post(
+                GOOGLE_TOKEN_URL,
+                &[
+                    ("client_id", client_id),
+                    ("client_secret", client_secret),
+                    ("refresh_token", &refresh_token),
+                    ("grant_type", "refresh_token"),
+                ],
+            )?;
+            if code == 200 {
+                return save_google_token(&answer, Some(refresh_token));
+            }
+            eprintln!(
+                "⚠️  Google did not refresh the access token ({}); authorizing again",
+                answer["error"].as_str().unwrap_or("unknown error")
+            );
+        }
+    }
+
+    let (code, device) = google_oauth_post(
+        GOOGLE_DEVICE_CODE_URL,
+        &[("client_id", client_id), ("scope", GOOGLE_TASKS_SCOPE)],
+    )?;
+    if code != 200 {
+        return Err(anyhow::anyhow!(
+            "Google refused the device authorization: {}",
+            device["error_description"]
+                .as_str()
+                .or(device["error"].as_str())
+                .unwrap_or("unknown error")
+        ));
+    }
+    let device_code = device["device_code"]
+        .as_str()
+        .context("No device code in Google's answer")?;
+    println!(
+        "🔑 Open {} and enter the code {}",
+        device["verification_url"]
+            .as_str()
+            .unwrap_or("https://www.google.com/device"),
+        device["user_code"].as_str().unwrap_or_default()
+    );
+
+    let mut interval = device["interval"].as_u64().unwrap_or(5);
+    let deadline = std::time::Instant::now()
+        + std::time::Duration::from_secs(device["expires_in"].as_u64().unwrap_or(1800));
+    while std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+        let (code, answer) = google_oauth_post(
+            GOOGLE_TOKEN_URL,
+            &[
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("device_code", device_code),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ],
+        )?;
+        if code == 200 {
+            println!("✅ Authorized access to Google Tasks");
+            return save_google_token(&answer, None);
+        }
+        match answer["error"].as_str() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += 5,
+            Some("access_denied") => {
+                return Err(anyhow::anyhow!("Access to Google Tasks was denied"))
+            }
+            error => {
+                return Err(anyhow::anyhow!(
+                    "Google authorization failed: {}",
+                    error.unwrap_or("unknown error")
+                ))
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "The code expired before it was entered; run the sync again"
+    ))
+}
+
+/// Call the Google Tasks API and return its JSON answer
+fn google_api(
+    method: &str,
+    path: &str,
+    token: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<serde_json::Value> {
+    let url = format!("{}{}", GOOGLE_TASKS_API, path);
+    let authorization = format!("Authorization: Bearer {}", token);
+    let body = body.map(|body| body.to_string());
+    let (code, answer) = curl_request(
+        method,
+        &url,
+        None,
+        &[&authorization, "Content-Type: application/json"],
+        body.as_deref(),
+    )?;
+    if !(200..300).contains(&code) {
+        let message = serde_json::from_str::<serde_json::Value>(&answer)
+            .ok()
+            .and_then(|error| error["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or(answer);
+        return Err(anyhow::anyhow!(
+            "Google Tasks answered {} to {} {}: {}",
+            code,
+            method,
+            path,
+            message.trim()
+        ));
+    }
+    if answer.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_str(&answer).context(format!("Unexpected answer to {} {}", method, path))
+}
+
+/// Every item of a paged Google Tasks collection
+fn google_api_items(path: &str, token: &str) -> Result<Vec<serde_json::Value>> {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    let mut items = Vec::new();
+    let mut page = format!("{}{}maxResults=100", path, separator);
+    loop {
+        let answer = google_api("GET", &page, token, None)?;
+        items.extend(answer["items"].as_array().cloned().unwrap_or_default());
+        match answer["nextPageToken"].as_str() {
+            Some(next) => {
+                page = format!(
+                    "{}{}maxResults=100&pageToken={}",
+                    path,
+                    separator,
+                    percent_encode(next)
+                )
+            }
+            None => return Ok(items),
+        }
+    }
+}
+
+/// A due date as YYYY-MM-DD; Google Tasks keeps dates only
+fn google_due_date(value: Option<&str>) -> Option<String> {
+    value
+        .and_then(parse_task_date)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Write a due date or reopening pulled from Google into a task file
+fn apply_google_change(
+    task_file: &TaskFile,
+    due: Option<Option<String>>,
+    reopen: bool,
+    config: &Config,
+) -> Result<()> {
+    let content = read_task_file(&task_file.file_path, config)?;
+    let mut task = Matter::<gray_matter::engine::YAML>::new()
+        .parse(&content)
+        .data
+        .context("Could not parse front-matter from task file")
+        .and_then(|pod| extract_task_from_pod(&pod))?;
+    if let Some(due) = due {
+        task.due = due;
+    }
+    if reopen {
+        task.status = Some("pending".to_string());
+        task.completed = None;
+    }
+    let new_content = rewrite_front_matter(&content, &task) + split_front_matter(&content).1;
+    write_task_file(&task_file.file_path, &new_content, config).context(format!(
+        "Failed to write updated task file: {}",
+        task_file.file_path
+    ))
+}
+
+/// Create or update the Google task mirroring `task` in `list`
+fn push_google_task(
+    task: &Task,
+    list: &str,
+    link: Option<&GoogleTaskLink>,
+    remote: Option<&serde_json::Value>,
+    token: &str,
+) -> Result<GoogleTaskLink> {
+    let due = google_due_date(task.due.as_deref());
+    let completed = is_finished(task);
+    let mut body = serde_json::json!({
+        "title": task.title,
+        "due": due.as_ref().map(|date| format!("{}T00:00:00.000Z", date)),
+        "status": if completed { "completed" } else { "needsAction" },
+    });
+    if !completed {
+        body["completed"] = serde_json::Value::Null;
+    }
+
+    let id = match link {
+        Some(link) if link.list == list => {
+            google_api(
+                "PATCH",
+                &format!("/lists/{}/tasks/{}", list, link.id),
+                token,
+                Some(&body),
+            )?;
+            link.id.clone()
+        }
+        _ => {
+            // Google Tasks cannot move a task to another list, so a task
+            // whose project changed is created anew
+            if let (Some(link), Some(_)) = (link, remote) {
+                google_api(
+                    "DELETE",
+                    &format!("/lists/{}/tasks/{}", link.list, link.id),
+                    token,
+                    None,
+                )?;
+            }
+            let created = google_api(
+                "POST",
+                &format!("/lists/{}/tasks", list),
+                token,
+                Some(&body),
+            )?;
+            created["id"]
+                .as_str()
+                .context("No ID in Google's answer")?
+                .to_string()
+        }
+    };
+    Ok(GoogleTaskLink {
+        list: list.to_string(),
+        id,
+        due,
+        completed,
+    })
+}
+
+fn sync_google_tasks(dry_run: bool, config: &Config) -> Result<()> {
+    let settings = &config.google_tasks;
+    let token = google_access_token(settings)?;
+    let _lock = lock_tasks(config)?;
+
+    // List IDs by title
+    let mut lists: std::collections::BTreeMap<String, String> =
+        google_api_items("/users/@me/lists", &token)?
+            .iter()
+            .filter_map(|list| {
+                Some((
+                    list["title"].as_str()?.to_string(),
+                    list["id"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+    let default_list = match &settings.default_list {
+        Some(title) => lists
+            .get(title)
+            .cloned()
+            .context(format!("No Google Tasks list named '{}'", title))?,
+        None => google_api("GET", "/users/@me/lists/@default", &token, None)?["id"]
+            .as_str()
+            .context("No ID for the default Google Tasks list")?
+            .to_string(),
+    };
+
+    // Google tasks by ID, with their list
+    let mut remote: std::collections::HashMap<String, (String, serde_json::Value)> =
+        std::collections::HashMap::new();
+    for list in lists.values() {
+        let path = format!("/lists/{}/tasks?showCompleted=true&showHidden=true", list);
+        for item in google_api_items(&path, &token)? {
+            if let Some(id) = item["id"].as_str() {
+                remote.insert(id.to_string(), (list.clone(), item));
+            }
+        }
+    }
+
+    let links_path = google_links_path();
+    let mut links: std::collections::BTreeMap<String, GoogleTaskLink> =
+        std::fs::read_to_string(&links_path)
+            .ok()
+            .and_then(|links| serde_json::from_str(&links).ok())
+            .unwrap_or_default();
+    links.retain(|id, link| {
+        let found = remote.contains_key(&link.id);
+        if !found {
+            println!("ℹ️  The Google task of {} was deleted", id);
+        }
+        found
+    });
+
+    // Pull due dates and completions that changed only in Google
+    let mut pulled = 0;
+    for task_file in load_tasks(config)? {
+        let task = &task_file.task;
+        let Some(link) = links.get(&task.id) else {
+            continue;
+        };
+        let item = &remote[&link.id].1;
+        let remote_due = google_due_date(item["due"].as_str());
+        let remote_completed = item["status"].as_str() == Some("completed");
+
+        let due = (remote_due != link.due && google_due_date(task.due.as_deref()) == link.due)
+            .then(|| remote_due.clone());
+        if let Some(due) = &due {
+            println!(
+                "⬇️  {} due {} in Google",
+                task.id,
+                due.as_deref().unwrap_or("date removed")
+            );
+        }
+        let completion_changed =
+            remote_completed != link.completed && is_finished(task) == link.completed;
+
+        if dry_run {
+            if completion_changed {
+                let action = if remote_completed { "done" } else { "reopened" };
+                println!("⬇️  {} {} in Google", task.id, action);
+            }
+        } else if completion_changed && remote_completed {
+            if due.is_some() {
+                apply_google_change(&task_file, due.clone(), false, config)?;
+            }
+            mark_task_done(
+                task.id.clone(),
+                item["completed"].as_str().map(str::to_string),
+                config,
+            )?;
+        } else if due.is_some() || completion_changed {
+            apply_google_change(&task_file, due.clone(), completion_changed, config)?;
+            if completion_changed {
+                println!("🔄 Reopened task {}: {}", task.id, task.title);
+            }
+        }
+        pulled += usize::from(due.is_some()) + usize::from(completion_changed);
+    }
+
+    // Push new and changed tasks
+    let tasks = load_tasks(config)?;
+    let mut pushed = 0;
+    let mut failed = 0;
+    let mut synced = std::collections::BTreeMap::new();
+    for task_file in &tasks {
+        let task = &task_file.task;
+        let link = links.get(&task.id);
+        let item = link.map(|link| &remote[&link.id].1);
+        if link.is_none() && is_finished(task) {
+            continue;
+        }
+
+        let list = match &task.project {
+            None => Some(default_list.clone()),
+            Some(project) => lists.get(project).cloned(),
+        };
+        let unchanged = match (link, item) {
+            (Some(link), Some(item)) => {
+                list.as_ref() == Some(&link.list)
+                    && item["title"].as_str() == Some(task.title.as_str())
+                    && google_due_date(item["due"].as_str()) == google_due_date(task.due.as_deref())
+                    && (item["status"].as_str() == Some("completed")) == is_finished(task)
+            }
+            _ => false,
+        };
+        if let Some(link) = link.filter(|_| unchanged) {
+            synced.insert(
+                task.id.clone(),
+                GoogleTaskLink {
+                    due: google_due_date(task.due.as_deref()),
+                    completed: is_finished(task),
+                    ..link.clone()
+                },
+            );
+            continue;
+        }
+        if dry_run {
+            let action = if link.is_some() { "update" } else { "create" };
+            println!("🔍 Would {} {} in Google: {}", action, task.id, task.title);
+            pushed += 1;
+            continue;
+        }
+
+        let result = (|| {
+            let list = match list {
+                Some(list) => list,
+                None => {
+                    let project = task.project.clone().unwrap_or_default();
+                    let created = google_api(
+                        "POST",
+                        "/users/@me/lists",
+                        &token,
+                        Some(&serde_json::json!({ "title": project })),
+                    )?;
+                    let id = created["id"]
+                        .as_str()
+                        .context("No ID in Google's answer")?
+                        .to_string();
+                    println!("📋 Created Google Tasks list '{}'", project);
+                    lists.insert(project, id.clone());
+                    id
+                }
+            };
+            push_google_task(task, &list, link, item, &token)
+        })();
+        match result {
+            Ok(new_link) => {
+                synced.insert(task.id.clone(), new_link);
+                pushed += 1;
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {:#}", task.id, e);
+                if let Some(link) = link {
+                    synced.insert(task.id.clone(), link.clone());
+                }
+                failed += 1;
+            }
+        }
+    }
+
+    // Import open tasks added in Google; tasks linked before this sync,
+    // including those just replaced in another list, are not new
+    let mut imported = 0;
+    let linked: std::collections::HashSet<String> = links
+        .values()
+        .chain(synced.values())
+        .map(|link| link.id.clone())
+        .collect();
+    let mut new_items: Vec<&(String, serde_json::Value)> = remote
+        .iter()
+        .filter(|(id, (_, item))| {
+            !linked.contains(*id) && item["status"].as_str() == Some("needsAction")
+        })
+        .map(|(_, entry)| entry)
+        .collect();
+    new_items.sort_by_key(|(_, item)| item["position"].as_str().unwrap_or_default().to_string());
+    for (list, item) in new_items {
+        let title = item["title"].as_str().unwrap_or_default().trim();
+        if title.is_empty() {
+            continue;
+        }
+        let project = lists
+            .iter()
+            .find(|(_, id)| *id == list && *list != default_list)
+            .map(|(title, _)| title.clone());
+        let due = google_due_date(item["due"].as_str());
+        if dry_run {
+            println!("🔍 Would create a task from Google: {}", title);
+            imported += 1;
+            continue;
+        }
+        let id = add_task(
+            title.to_string(),
+            None,
+            None,
+            None,
+            project,
+            due.clone(),
+            item["notes"].as_str().map(str::to_string),
+            false,
+            config,
+        )?;
+        synced.insert(
+            id,
+            GoogleTaskLink {
+                list: list.clone(),
+                id: item["id"].as_str().unwrap_or_default().to_string(),
+                due,
+                completed: false,
+            },
+        );
+        imported += 1;
+    }
+
+    // Links of deleted task files are kept so their Google tasks are not imported
+    for (id, link) in links {
+        if !tasks.iter().any(|tf| tf.task.id == id) {
+            synced.insert(id, link);
+        }
+    }
+
+    if dry_run {
+        println!(
+            "🔍 Dry run: {} to push, {} change(s) to pull, {} to create from Google",
+            pushed, pulled, imported
+        );
+        return Ok(());
+    }
+
+    if let Some(parent) = links_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&links_path, serde_json::to_string_pretty(&synced)?).context(format!(
+        "Failed to write sync state: {}",
+        links_path.display()
+    ))?;
+
+    println!(
+        "✅ Google Tasks sync: {} pushed, {} change(s) pulled, {} created from Google",
+        pushed, pulled, imported
+    );
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} task(s) could not be pushed", failed));
+    }
+    Ok(())
+}
+
+// Code TODO scan
+
+/// A `TODO(name): text` or `FIXME: text` comment found by `scan`
+struct CodeTodo {
+    /// "todo" or "fixme", also the tag of its task
+    kind: &'static str,
+    author: Option<String>,
+    text: String,
+}
+
+/// Parse a TODO/FIXME comment; the marker must follow a comment opener so
+/// string literals mentioning TODO are not picked up
+fn parse_code_todo(line: &str) -> Option<CodeTodo> {
+    const COMMENT_OPENERS: [&str; 8] = ["//", "#", "/*", "*", "--", ";", "<!--", "%"];
+
+    for (marker, kind) in [("TODO", "todo"), ("FIXME", "fixme")] {
+        let Some(start) = line.find(marker) else {
+            continue;
+        };
+        let before = line[..start].trim_end();
+        if !COMMENT_OPENERS
+            .iter()
+            .any(|opener| before.ends_with(opener))
+        {
+            continue;
+        }
+        let mut rest = &line[start + marker.len()..];
+        let mut author = None;
+        if let Some(inner) = rest.strip_prefix('(') {
+            let end = inner.find(')')?;
+            author = Some(inner[..end].trim().to_string()).filter(|name| !name.is_empty());
+            rest = &inner[end + 1..];
+        }
+        let text = rest
+            .strip_prefix(':')?
+            .trim()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim();
+        if text.is_empty() {
+            return None;
+        }
+        return Some(CodeTodo {
+            kind,
+            author,
+            text: text.to_string(),
+        });
+    }
+    None
+}
+
+/// Replace the entries of a `## <heading>` section, creating it if needed
+fn replace_section(content: &str, heading: &str, entries: &[String]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let header = format!("## {}", heading);
+    let Some(start) = lines.iter().position(|line| line.trim() == header) else {
+        return entries.iter().fold(content.to_string(), |content, entry| {
+            append_to_section(&content, heading, entry)
+        });
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim().starts_with("## "))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let mut result: Vec<String> = lines[..=start].iter().map(|l| l.to_string()).collect();
+    result.push(String::new());
+    result.extend(entries.iter().cloned());
+    if end < lines.len() {
+        result.push(String::new());
+        result.extend(lines[end..].iter().map(|l| l.to_string()));
+    }
+    let mut result = result.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Source files under `path`, skipping hidden and build directories and the
+/// task directories themselves
+fn scan_source_files(path: &str, config: &Config) -> Vec<PathBuf> {
+    const SKIPPED_DIRS: [&str; 5] = ["target", "node_modules", "vendor", "dist", "build"];
+
+    let task_dirs: Vec<PathBuf> = std::iter::once(&config.tasks.dir)
+        .chain(config.roots.values())
+        .filter_map(|dir| Path::new(dir).canonicalize().ok())
+        .collect();
+    WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            if entry.depth() > 0 && name.starts_with('.') {
+                return false;
+            }
+            if !entry.file_type().is_dir() {
+                return true;
+            }
+            !SKIPPED_DIRS.contains(&name.as_ref())
+                && !entry
+                    .path()
+                    .canonicalize()
+                    .is_ok_and(|dir| task_dirs.contains(&dir))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Create a task for each new TODO/FIXME comment, refresh the locations of
+/// known ones, and report tasks whose comments are gone
+fn scan_code_todos(paths: &[String], config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    // Comments with the same text share one task
+    let mut found: Vec<(CodeTodo, Vec<String>)> = Vec::new();
+    for path in paths {
+        for file in scan_source_files(path, config) {
+            // Binary and non-UTF-8 files are skipped
+            let Ok(source) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            let display = file.display().to_string();
+            let display = display.strip_prefix("./").unwrap_or(&display);
+            for (number, line) in source.lines().enumerate() {
+                let Some(todo) = parse_code_todo(line) else {
+                    continue;
+                };
+                let location = format!("- `{}:{}`", display, number + 1);
+                match found.iter_mut().find(|(known, _)| known.text == todo.text) {
+                    Some((known, locations)) => {
+                        known.author = known.author.take().or(todo.author);
+                        locations.push(location);
+                    }
+                    None => found.push((todo, vec![location])),
+                }
+            }
+        }
+    }
+
+    let tasks = load_tasks(config)?;
+    let from_scan = |tf: &TaskFile| {
+        tf.task
+            .tags
+            .iter()
+            .flatten()
+            .any(|tag| tag == "todo" || tag == "fixme")
+            && !section_lines(&tf.content, "Locations").is_empty()
+    };
+    let mut ids = TaskIdAllocator::load(config)?;
+    let (mut created, mut updated) = (0, 0);
+
+    for (todo, locations) in &found {
+        let existing = tasks
+            .iter()
+            .find(|tf| from_scan(tf) && tf.task.title == todo.text);
+        if let Some(task_file) = existing {
+            let current: Vec<&str> = section_lines(&task_file.content, "Locations")
+                .into_iter()
+                .filter(|line| !line.trim().is_empty())
+                .collect();
+            if current != *locations {
+                let content = read_task_file(&task_file.file_path, config)?;
+                let (front_matter, body) = split_front_matter(&content);
+                let content = format!(
+                    "{}{}",
+                    front_matter,
+                    replace_section(body, "Locations", locations)
+                );
+                write_task_file(&task_file.file_path, &content, config)?;
+                println!(
+                    "🔄 Updated {}: {} ({})",
+                    task_file.task.id,
+                    todo.text,
+                    locations.len()
+                );
+                updated += 1;
+            }
+            continue;
+        }
+
+        let id = ids.next(None, config)?;
+        let task = Task {
+            id: id.clone(),
+            title: todo.text.clone(),
+            status: Some("pending".to_string()),
+            priority: Some(
+                if todo.kind == "fixme" {
+                    "high"
+                } else {
+                    "medium"
+                }
+                .to_string(),
+            ),
+            tags: Some(vec![todo.kind.to_string()]),
+            project: None,
+            created: Some(task_timestamp(config)),
+            due: None,
+            completed: None,
+            started: None,
+            branch: None,
+            scheduled: None,
+            estimate: None,
+            parent: None,
+            depends_on: None,
+            recur: None,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: todo.author.clone(),
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        };
+        let mut content = format_front_matter(&task);
+        content.push_str(&format_task_body(&config.body, None));
+        let content = replace_section(&content, "Locations", locations);
+
+        let filename = format!(
+            "{}/{}-{}.md",
+            config.tasks.dir,
+            id,
+            slugify(&todo.text, &config.slug)
+        );
+        if !dry_run() {
+            std::fs::create_dir_all(&config.tasks.dir)?;
+        }
+        write_task_file(&filename, &content, config)
+            .context(format!("Failed to write task file: {}", filename))?;
+        println!("✅ Created task {}: {}", id, todo.text);
+        created += 1;
+    }
+
+    let orphans: Vec<&TaskFile> = tasks
+        .iter()
+        .filter(|tf| from_scan(tf) && !is_finished(&tf.task))
+        .filter(|tf| !found.iter().any(|(todo, _)| todo.text == tf.task.title))
+        .collect();
+    if !orphans.is_empty() {
+        println!("\n🧹 Comment removed from the code (close with `mdtasks done <id>`):");
+        for task_file in &orphans {
+            println!("  {}: {}", task_file.task.id, task_file.task.title);
+        }
+    }
+
+    println!(
+        "🔎 {} comment(s): {} task(s) created, {} updated, {} orphaned",
+        found.len(),
+        created,
+        updated,
+        orphans.len()
+    );
+    Ok(())
+}
+
+// Focus
+
+fn focus_path() -> PathBuf {
+    state_dir().join("state").join("focus")
+}
+
+/// ID of the task chosen with `focus`, if any
+fn read_focus() -> Option<String> {
+    std::fs::read_to_string(focus_path())
+        .ok()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+fn focus_task(id: &str, config: &Config) -> Result<()> {
+    let task_file = find_task(load_tasks(config)?, id)?;
+    if dry_run() {
+        println!("🎯 Would focus on {}", task_file.task.id);
+        return Ok(());
+    }
+    let path = focus_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, format!("{}\n", task_file.task.id))
+        .context(format!("Failed to write {}", path.display()))?;
+    println!(
+        "🎯 Focused on {}: {}",
+        task_file.task.id, task_file.task.title
+    );
+    Ok(())
+}
+
+fn clear_focus() -> Result<()> {
+    let Some(id) = read_focus() else {
+        return Ok(());
+    };
+    if dry_run() {
+        println!("🎯 Would stop focusing on {}", id);
+        return Ok(());
+    }
+    std::fs::remove_file(focus_path()).context("Failed to clear the focused task")?;
+    println!("🎯 No longer focused on {}", id);
+    Ok(())
+}
+
+fn show_current_task(id_only: bool, config: &Config) -> Result<()> {
+    let Some(id) = read_focus() else {
+        if !id_only {
+            println!("No focused task; set one with `mdtasks focus <id>`");
+        }
+        return Ok(());
+    };
+    if id_only {
+        println!("{}", id);
+        return Ok(());
+    }
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    let task = &task_file.task;
+    println!(
+        "🎯 {}: {} [{}]",
+        task.id,
+        task.title,
+        task.status.as_deref().unwrap_or("pending")
+    );
+    let (done, total) = subtask_progress(&task_file.content);
+    if total > 0 {
+        println!("   {}/{} subtasks done", done, total);
+    }
+    Ok(())
+}
+
+/// The given task ID, or the focused task when none was given
+fn or_focused_task(id: Option<String>) -> Result<String> {
+    match id {
+        Some(id) => Ok(id),
+        None => read_focus().context(
+            "No task ID given and no focused task; pass an ID or run `mdtasks focus <id>`",
+        ),
+    }
+}
+
+/// The given task IDs, or the focused task when none were given
+fn or_focused_tasks(ids: Vec<String>) -> Result<Vec<String>> {
+    if ids.is_empty() {
+        return Ok(vec![or_focused_task(None)?]);
+    }
+    Ok(ids)
+}
+
+/// Split `[ID] TEXT` arguments, using the focused task when only text is given
+fn focused_task_and_text(args: Vec<String>) -> Result<(String, String)> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(id), Some(text)) => Ok((id, text)),
+        (Some(text), None) => Ok((or_focused_task(None)?, text)),
+        _ => Err(anyhow::anyhow!("Missing text")),
+    }
+}
+
+// Task picker
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence;
+/// consecutive characters and word starts score higher, `None` means no match
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if let Some(previous) = previous {
+            score -= (found - previous - 1).min(5) as i64;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// Run an external fuzzy finder over `lines`; `None` when none is installed.
+/// `$MDTASKS_FINDER` is used if set, otherwise `sk`, then `fzf`
+fn run_external_finder(lines: &[String], query: Option<&str>) -> Result<Option<Option<String>>> {
+    use std::io::Write;
+
+    let finders = match std::env::var("MDTASKS_FINDER") {
+        Ok(finder) if finder == "builtin" => return Ok(None),
+        Ok(finder) if !finder.is_empty() => vec![finder],
+        _ => vec!["sk".to_string(), "fzf".to_string()],
+    };
+    for finder in finders {
+        let mut command = std::process::Command::new("sh");
+        let script = match query {
+            Some(_) => format!("{} --query \"$1\"", finder),
+            None => finder.clone(),
+        };
+        command
+            .args(["-c", &script, "finder", query.unwrap_or_default()])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped());
+        let mut child = command
+            .spawn()
+            .context(format!("Failed to start {}", finder))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            for line in lines {
+                if writeln!(stdin, "{}", line).is_err() {
+                    break;
+                }
+            }
+        }
+        let output = child
+            .wait_with_output()
+            .context(format!("Failed to wait for {}", finder))?;
+        // The shell reports a missing command with 127
+        if output.status.code() == Some(127) {
+            continue;
+        }
+        let chosen = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return Ok(Some((!chosen.is_empty()).then_some(chosen)));
+    }
+    Ok(None)
+}
+
+/// Built-in picker: search, choose a numbered match, or search again
+fn run_builtin_finder(lines: &[String], query: Option<&str>) -> Result<Option<String>> {
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err(anyhow::anyhow!(
+            "pick needs a terminal (or install sk or fzf and set $MDTASKS_FINDER)"
+        ));
+    }
+
+    let mut query = query.unwrap_or_default().to_string();
+    loop {
+        if query.is_empty() {
+            query = ask("🔎 Search tasks (empty to cancel): ")?;
+            if query.is_empty() {
+                return Ok(None);
+            }
+        }
+        let mut matches: Vec<(i64, &String)> = lines
+            .iter()
+            .filter_map(|line| fuzzy_score(&query, line).map(|score| (score, line)))
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.truncate(10);
+        if matches.is_empty() {
+            println!("No tasks match '{}'", query);
+            query.clear();
+            continue;
+        }
+
+        for (i, (_, line)) in matches.iter().enumerate() {
+            println!("  {:>2}. {}", i + 1, line);
+        }
+        let answer = ask(&format!(
+            "Pick 1-{}, or type to search again (empty to cancel): ",
+            matches.len()
+        ))?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=matches.len()).contains(&n) => return Ok(Some(matches[n - 1].1.clone())),
+            _ => query = answer,
+        }
+    }
+}
+
+/// Pick a task with a fuzzy finder and show, start, finish, or edit it
+fn pick_task(
+    command: &str,
+    query: Option<&str>,
+    all: bool,
+    force: bool,
+    config: &Config,
+) -> Result<()> {
+    let mut tasks: Vec<Task> = load_tasks(config)?
+        .into_iter()
+        .map(|tf| tf.task)
+        .filter(|task| all || !is_finished(task))
+        .collect();
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    if tasks.is_empty() {
+        println!("No tasks to pick from.");
+        return Ok(());
+    }
+
+    let lines: Vec<String> = tasks
+        .iter()
+        .map(|task| {
+            let tags: Vec<String> = task
+                .tags
+                .iter()
+                .flatten()
+                .map(|t| format!("#{}", t))
+                .collect();
+            format!(
+                "{}  {:<8} {} {}",
+                task.id,
+                task.status.as_deref().unwrap_or("pending"),
+                task.title,
+                tags.join(" ")
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect();
+
+    let chosen = match run_external_finder(&lines, query)? {
+        Some(chosen) => chosen,
+        None => run_builtin_finder(&lines, query)?,
+    };
+    let Some(id) = chosen.and_then(|line| line.split_whitespace().next().map(str::to_string))
+    else {
+        println!("No task picked.");
+        return Ok(());
+    };
+
+    match command {
+        "start" => {
+            ensure_task_editable(&id, force, config)?;
+            check_wip_limit(&id, force, config)?;
+            mark_task_start(id, config)
+        }
+        "done" => {
+            ensure_task_editable(&id, force, config)?;
+            mark_task_done(id, None, config)
+        }
+        "edit" => edit_task_file(id, config),
+        _ => show_task(id, false, config),
+    }
+}
+
+/// Open a task's file in `$VISUAL` or `$EDITOR`, or the default application
+fn edit_task_file(id: String, config: &Config) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_default();
+    if editor.is_empty() {
+        return open_task_file(id, config);
+    }
+    let task_file = find_task(load_tasks(config)?, &id)?;
+    if dry_run() {
+        println!("✏️  Would edit {}", task_file.file_path);
+        return Ok(());
+    }
+    let status = std::process::Command::new("sh")
+        .args([
+            "-c",
+            &format!("{} \"$1\"", editor),
+            "editor",
+            &task_file.file_path,
+        ])
+        .status()
+        .context(format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Editor '{}' exited with {}",
+            editor,
+            status
+        ));
+    }
+    Ok(())
+}
+
+// Watch mode
+
+fn print_watch_summary(config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for task_file in &tasks {
+        let status = task_file
+            .task
+            .status
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        match counts.iter_mut().find(|(s, _)| *s == status) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((status, 1)),
+        }
+    }
+
+    let breakdown = counts
+        .iter()
+        .map(|(status, count)| format!("{} {}", count, status))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    println!(
+        "📊 [{}] {} task(s){}",
+        local_now().format("%H:%M:%S"),
+        tasks.len(),
+        if breakdown.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", breakdown)
+        }
+    );
+
+    Ok(())
+}
+
+fn watch_tasks(config: &Config) -> Result<()> {
+    use notify::Watcher;
+
+    let tasks_dir = Path::new(&config.tasks.dir);
+    if !tasks_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "Tasks directory does not exist: {}",
+            tasks_dir.display()
+        ));
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to create file watcher")?;
+    watcher
+        .watch(tasks_dir, notify::RecursiveMode::Recursive)
+        .context(format!("Failed to watch: {}", tasks_dir.display()))?;
+
+    println!("👀 Watching {} (Ctrl+C to stop)", tasks_dir.display());
+    print_watch_summary(config)?;
+
+    while let Ok(first) = rx.recv() {
+        // Editors often emit several events per save; collect them before reporting
+        let mut changed = std::collections::BTreeSet::new();
+        let mut pending = Some(first);
+        while let Some(result) = pending {
+            match result {
+                // Ignore access events, which our own reads would trigger
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) => changed.extend(
+                    event
+                        .paths
+                        .into_iter()
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "md")),
+                ),
+                Err(e) => eprintln!("⚠️  Watch error: {}", e),
+            }
+            pending = rx.recv_timeout(std::time::Duration::from_millis(300)).ok();
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+
+        for path in &changed {
+            if !path.exists() {
+                println!("🗑️  Removed: {}", path.display());
+                continue;
+            }
+            match validate_task_file(path) {
+                Ok(task) => println!("✅ {}: {} - {}", path.display(), task.id, task.title),
+                Err(e) => println!("⚠️  {}: {}", path.display(), e),
+            }
+        }
+
+        print_watch_summary(config)?;
+    }
+
+    Ok(())
+}
+
+// REST API server
+
+/// Largest request body the API accepts
+const MAX_API_BODY_BYTES: usize = 1024 * 1024;
+
+/// Largest request line plus headers the API reads
+const MAX_API_HEADER_BYTES: u64 = 64 * 1024;
+
+/// How long a client may take to send its request
+const API_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Minimal HTTP request as read from a client connection
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    /// Bearer token from the Authorization header
+    token: Option<String>,
+    origin: Option<String>,
+    body: String,
+}
+
+/// Read a request line, headers, and a Content-Length delimited body;
+/// failures carry the status code to answer with
+fn read_http_request(
+    stream: &mut std::net::TcpStream,
+) -> std::result::Result<HttpRequest, (u16, String)> {
+    use std::io::{BufRead, BufReader, Read};
+
+    let bad_request = |e: std::io::Error| (400, e.to_string());
+    let mut reader = BufReader::new(Read::by_ref(stream).take(MAX_API_HEADER_BYTES));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(bad_request)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut token = None;
+    let mut origin = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).map_err(bad_request)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => {
+                    content_length = value
+                        .parse()
+                        .map_err(|_| (400, "Invalid Content-Length".to_string()))?
+                }
+                "authorization" => {
+                    token = value.strip_prefix("Bearer ").map(|t| t.trim().to_string())
+                }
+                "origin" => origin = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    if content_length > MAX_API_BODY_BYTES {
+        return Err((
+            413,
+            format!("Request body is over {} bytes", MAX_API_BODY_BYTES),
+        ));
+    }
+    // The body follows whatever the header reader already buffered
+    let mut body = Vec::with_capacity(content_length);
+    let buffered = reader.buffer().len().min(content_length);
+    body.extend_from_slice(&reader.buffer()[..buffered]);
+    reader.consume(buffered);
+    let rest = reader.into_inner().into_inner();
+    body.resize(content_length, 0);
+    rest.read_exact(&mut body[buffered..])
+        .map_err(bad_request)?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target, String::new()),
+    };
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        token,
+        origin,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+/// Decode a `application/x-www-form-urlencoded` query value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Token clients must send as `Authorization: Bearer <token>`, created on first
+/// use from 32 bytes of the system's random source
+fn api_token() -> Result<String> {
+    use std::io::{Read, Write};
+
+    let path = state_dir().join("serve_token");
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .context("Failed to read /dev/urandom for the API token")?;
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    std::fs::create_dir_all(state_dir())?;
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).write(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", token))
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(token)
+}
+
+/// Compare a presented token without returning early at the first
+/// differing byte, so response timing doesn't reveal how much of it matched
+fn token_matches(presented: Option<&str>, token: &str) -> bool {
+    let Some(presented) = presented else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Write a JSON response; `cors_origin` is the configured browser origin, sent
+/// back only to requests from that origin
+fn write_http_response(
+    stream: &mut std::net::TcpStream,
+    status: u16,
+    body: &serde_json::Value,
+    cors_origin: Option<&str>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    let body = if status == 204 {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let cors = match cors_origin {
+        Some(origin) => format!(
+            "Access-Control-Allow-Origin: {}\r\n\
+             Access-Control-Allow-Methods: GET, POST, PATCH, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+             Vary: Origin\r\n",
+            origin
+        ),
+        None => String::new(),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         {}\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        cors,
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// JSON representation of a task, optionally including its markdown body
+fn task_to_json(task_file: &TaskFile, include_content: bool) -> serde_json::Value {
+    let mut value = serde_json::to_value(&task_file.task).unwrap_or_default();
+    value["file"] = serde_json::Value::String(task_file.file_path.clone());
+    if include_content {
+        value["content"] = serde_json::Value::String(task_file.content.clone());
+    }
+    value
+}
+
+fn find_task_json(id: &str, config: &Config) -> Result<Option<serde_json::Value>> {
+    Ok(load_tasks(config)?
+        .iter()
+        .find(|tf| tf.task.id == id)
+        .map(|tf| task_to_json(tf, true)))
+}
+
+fn json_error(message: impl std::fmt::Display) -> serde_json::Value {
+    serde_json::json!({ "error": message.to_string() })
+}
+
+/// Parse a JSON string or array of strings into a comma-separated value
+fn json_field_value(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Route a request to the matching endpoint, returning status and JSON body
+fn handle_api_request(request: &HttpRequest, config: &Config) -> Result<(u16, serde_json::Value)> {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("OPTIONS", _) => Ok((204, serde_json::Value::Null)),
+        ("GET", ["tasks"]) => {
+            let mut filters: [Option<String>; 3] = Default::default();
+            for pair in request.query.split('&') {
+                if let Some((key, value)) = pair.split_once('=') {
+                    let value = Some(percent_decode(value));
+                    match key {
+                        "status" => filters[0] = value,
+                        "tag" => filters[1] = value,
+                        "priority" => filters[2] = value,
+                        _ => {}
+                    }
+                }
+            }
+
+            let tasks: Vec<_> = load_tasks(config)?
+                .iter()
+                .filter(|tf| task_matches_filters(&tf.task, &filters[0], &filters[1], &filters[2]))
+                .map(|tf| task_to_json(tf, false))
+                .collect();
+            Ok((200, serde_json::Value::Array(tasks)))
+        }
+        ("GET", ["tasks", id]) => match find_task_json(id, config)? {
+            Some(task) => Ok((200, task)),
+            None => Ok((404, json_error(format!("Task with ID '{}' not found", id)))),
+        },
+        ("POST", ["tasks"]) => {
+            let input: serde_json::Value = match serde_json::from_str(&request.body) {
+                Ok(input) => input,
+                Err(e) => return Ok((400, json_error(format!("Invalid JSON: {}", e)))),
+            };
+            let field = |name: &str| input.get(name).and_then(json_field_value);
+
+            let title = match field("title") {
+                Some(title) if !title.trim().is_empty() => title,
+                _ => return Ok((400, json_error("Missing required field: title"))),
+            };
+            let tags = field("tags").map(|t| t.split(',').map(|s| s.trim().to_string()).collect());
+            for name in ["status", "priority"] {
+                if let Some(value) = field(name) {
+                    if let Err(e) = validate_field_value(name, &value, config) {
+                        return Ok((400, json_error(e)));
+                    }
+                }
+            }
+
+            let id = add_task(
+                title,
+                field("priority"),
+                field("status"),
+                tags,
+                field("project"),
+                field("due"),
+                field("notes"),
+                false,
+                config,
+            )?;
+            // IDs are written zero-padded but may be read back as YAML integers
+            let task = match find_task_json(&id, config)? {
+                Some(task) => Some(task),
+                None => find_task_json(id.trim_start_matches('0'), config)?,
+            };
+
+            match task {
+                Some(task) => Ok((201, task)),
+                None => Ok((201, serde_json::json!({ "id": id }))),
+            }
+        }
+        ("PATCH", ["tasks", id]) => {
+            if find_task_json(id, config)?.is_none() {
+                return Ok((404, json_error(format!("Task with ID '{}' not found", id))));
+            }
+
+            let input: serde_json::Value = match serde_json::from_str(&request.body) {
+                Ok(input) => input,
+                Err(e) => return Ok((400, json_error(format!("Invalid JSON: {}", e)))),
+            };
+            let changes = match input.as_object() {
+                Some(changes) => changes,
+                None => return Ok((400, json_error("Expected a JSON object of field changes"))),
+            };
+
+            // Validate everything before applying any change
+            let mut updates = Vec::new();
+            for (field, value) in changes {
+                if !MODIFIABLE_FIELDS.contains(&field.as_str()) {
+                    return Ok((400, json_error(format!("Unknown field: {}", field))));
+                }
+                match json_field_value(value) {
+                    Some(value) => {
+                        if let Err(e) = validate_field_value(field, &value, config) {
+                            return Ok((400, json_error(e)));
+                        }
+                        updates.push((field.clone(), value))
+                    }
+                    None => {
+                        return Ok((
+                            400,
+                            json_error(format!("Invalid value for field: {}", field)),
+                        ))
+                    }
+                }
+            }
+
+            for (field, value) in updates {
+                set_task_field(id.to_string(), &field, value, config)?;
+            }
+
+            let task = find_task_json(id, config)?.unwrap_or_default();
+            Ok((200, task))
+        }
+        (_, ["tasks"]) | (_, ["tasks", _]) => Ok((405, json_error("Method not allowed"))),
+        _ => Ok((404, json_error("Not found"))),
+    }
+}
+
+fn serve_api(host: &str, port: u16, config: &Config) -> Result<()> {
+    let listener = std::net::TcpListener::bind((host, port))
+        .context(format!("Failed to bind to {}:{}", host, port))?;
+
+    let token = api_token()?;
+    println!("🌐 Serving tasks API on http://{}:{}", host, port);
+    println!("   GET /tasks, GET /tasks/:id, POST /tasks, PATCH /tasks/:id");
+    println!(
+        "🔑 Requests need `Authorization: Bearer <token>` with the token from {}",
+        state_dir().join("serve_token").display()
+    );
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("⚠️  Connection failed: {}", e);
+                continue;
+            }
+        };
+
+        // A client that stops sending must not block the server
+        if let Err(e) = stream.set_read_timeout(Some(API_READ_TIMEOUT)) {
+            eprintln!("⚠️  Connection failed: {}", e);
+            continue;
+        }
+
+        let request = match read_http_request(&mut stream) {
+            Ok(request) => request,
+            Err((status, message)) => {
+                let _ = write_http_response(&mut stream, status, &json_error(message), None);
+                continue;
+            }
+        };
+
+        let cors_origin = config
+            .serve
+            .allowed_origin
+            .as_deref()
+            .filter(|allowed| request.origin.as_deref() == Some(*allowed));
+        // CORS preflight requests carry no credentials
+        let (status, body) =
+            if request.method != "OPTIONS" && !token_matches(request.token.as_deref(), &token) {
+                (401, json_error("Missing or wrong API token"))
+            } else {
+                match handle_api_request(&request, config) {
+                    Ok(response) => response,
+                    Err(e) => (500, json_error(e)),
+                }
+            };
+
+        println!("{} {} → {}", request.method, request.path, status);
+        if let Err(e) = write_http_response(&mut stream, status, &body, cors_origin) {
+            eprintln!("⚠️  Failed to write response: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+// Markdown rendering
+
+/// Renders markdown for the terminal, with ANSI styles when `color` is set
+struct MarkdownRenderer {
+    out: String,
+    color: bool,
+    styles: Vec<&'static str>,
+    /// Open lists, with the next number for ordered ones
+    lists: Vec<Option<u64>>,
+    /// Bullet of the current list item, written before its first text
+    bullet: Option<String>,
+    quote_depth: usize,
+    in_code_block: bool,
+    /// Position in `out` where each open link's text starts, and its URL
+    links: Vec<(usize, String)>,
+    table: Vec<Vec<String>>,
+    cell: Option<String>,
+}
+
+impl MarkdownRenderer {
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.end_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn ansi(&mut self, code: &str) {
+        if self.color {
+            self.out.push_str(&format!("\x1b[{}m", code));
+        }
+    }
+
+    fn push_style(&mut self, code: &'static str) {
+        self.styles.push(code);
+        self.ansi(code);
+    }
+
+    fn pop_style(&mut self) {
+        self.styles.pop();
+        self.ansi("0");
+        for code in self.styles.clone() {
+            self.ansi(code);
+        }
+    }
+
+    /// Quote markers and the pending list bullet due before text on a new line
+    fn line_prefix(&mut self) {
+        if self.at_line_start() && self.quote_depth > 0 {
+            self.ansi("2");
+            self.out.push_str(&"│ ".repeat(self.quote_depth));
+            self.ansi("0");
+            for code in self.styles.clone() {
+                self.ansi(code);
+            }
+        }
+        if let Some(bullet) = self.bullet.take() {
+            self.out.push_str(&bullet);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(ref mut cell) = self.cell {
+            cell.push_str(text);
+            return;
+        }
+        self.line_prefix();
+        self.out.push_str(text);
+    }
+
+    /// A line break inside a block, keeping list item text aligned
+    fn line_break(&mut self) {
+        if let Some(ref mut cell) = self.cell {
+            cell.push(' ');
+            return;
+        }
+        self.out.push('\n');
+        if !self.lists.is_empty() {
+            self.out.push_str(&"  ".repeat(self.lists.len()));
+        }
+    }
+
+    fn code_block(&mut self, text: &str) {
+        for line in text.lines() {
+            self.line_prefix();
+            self.out.push_str("    ");
+            self.ansi("2");
+            self.out.push_str(line);
+            self.ansi("0");
+            self.out.push('\n');
+        }
+    }
+
+    fn table(&mut self) {
+        let rows = std::mem::take(&mut self.table);
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        self.blank_line();
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(j, width)| {
+                    let cell = row.get(j).map(String::as_str).unwrap_or("");
+                    format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+                })
+                .collect();
+            if i == 0 {
+                self.push_style("1");
+            }
+            self.out.push_str(cells.join(" │ ").trim_end());
+            if i == 0 {
+                self.pop_style();
+                self.out.push('\n');
+                let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+                self.out.push_str(&rule.join("─┼─"));
+            }
+            self.out.push('\n');
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Render task body markdown for the terminal: styled headings and emphasis,
+/// ☐/☑ checklists, bullets, and aligned tables
+fn render_markdown(markdown: &str, color: bool) -> String {
+    use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+    let mut r = MarkdownRenderer {
+        out: String::new(),
+        color,
+        styles: Vec::new(),
+        lists: Vec::new(),
+        bullet: None,
+        quote_depth: 0,
+        in_code_block: false,
+        links: Vec::new(),
+        table: Vec::new(),
+        cell: None,
+    };
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                r.blank_line();
+                r.push_style("1");
+                if level == HeadingLevel::H1 {
+                    r.push_style("4");
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                if level == HeadingLevel::H1 {
+                    r.pop_style();
+                }
+                r.pop_style();
+                r.out.push_str("\n\n");
+            }
+            Event::Start(Tag::Paragraph) => r.end_line(),
+            Event::End(TagEnd::Paragraph) => {
+                r.end_line();
+                if r.lists.is_empty() {
+                    r.out.push('\n');
+                }
+            }
+            Event::Start(Tag::BlockQuote(_)) => r.quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => r.quote_depth -= 1,
+            Event::Start(Tag::CodeBlock(_)) => {
+                r.end_line();
+                r.in_code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                r.in_code_block = false;
+                if r.lists.is_empty() {
+                    r.out.push('\n');
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                r.end_line();
+                r.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                r.lists.pop();
+                if r.lists.is_empty() {
+                    r.blank_line();
+                }
+            }
+            Event::Start(Tag::Item) => {
+                r.end_line();
+                let indent = "  ".repeat(r.lists.len().saturating_sub(1));
+                let marker = match r.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                r.bullet = Some(format!("{}{} ", indent, marker));
+            }
+            Event::End(TagEnd::Item) => {
+                // An item without text still shows its bullet
+                r.line_prefix();
+                r.end_line();
+            }
+            Event::TaskListMarker(checked) => {
+                let indent = "  ".repeat(r.lists.len().saturating_sub(1));
+                r.bullet = None;
+                r.line_prefix();
+                r.out.push_str(&indent);
+                if checked {
+                    r.ansi("32");
+                    r.out.push('☑');
+                    r.ansi("0");
+                } else {
+                    r.out.push('☐');
+                }
+                r.out.push(' ');
+            }
+            Event::Start(Tag::Strong) => r.push_style("1"),
+            Event::Start(Tag::Emphasis) => r.push_style("3"),
+            Event::Start(Tag::Strikethrough) => r.push_style("9"),
+            Event::End(TagEnd::Strong | TagEnd::Emphasis | TagEnd::Strikethrough) => r.pop_style(),
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                r.line_prefix();
+                r.links.push((r.out.len(), dest_url.to_string()));
+                r.push_style("4");
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                r.pop_style();
+                if let Some((start, url)) = r.links.pop() {
+                    let text = r.out.get(start..).unwrap_or("");
+                    if !text.contains(url.as_str()) {
+                        r.ansi("2");
+                        r.out.push_str(&format!(" ({})", url));
+                        r.ansi("0");
+                    }
+                }
+            }
+            Event::Start(Tag::Table(_)) => r.table.clear(),
+            Event::Start(Tag::TableHead | Tag::TableRow) => r.table.push(Vec::new()),
+            Event::Start(Tag::TableCell) => r.cell = Some(String::new()),
+            Event::End(TagEnd::TableCell) => {
+                let cell = r.cell.take().unwrap_or_default();
+                if let Some(row) = r.table.last_mut() {
+                    row.push(cell.trim().to_string());
+                }
+            }
+            Event::End(TagEnd::Table) => r.table(),
+            Event::Text(text) if r.in_code_block => r.code_block(&text),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => r.text(&text),
+            Event::Code(code) => {
+                if r.cell.is_some() {
+                    r.text(&code);
+                } else {
+                    r.push_style("36");
+                    r.text(&code);
+                    r.pop_style();
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => r.line_break(),
+            Event::Rule => {
+                r.blank_line();
+                r.out.push_str(&"─".repeat(40));
+                r.out.push_str("\n\n");
+            }
+            _ => {}
+        }
+    }
+
+    r.out.trim_end().to_string()
 }
 
-fn mark_all_subtasks_complete(content: &str) -> String {
-    let mut result = String::new();
+// Git backend
+//
+// With the default `git2` feature, repository queries and local branch
+// switches go through libgit2, so they work without a `git` binary. Building
+// with `--no-default-features` shells out to `git` instead. Network
+// operations, merges, rebases and commits always run `git`.
 
-    // Find the subtask section (preferring Subtasks over Checklist)
-    let (_section_name, section_start) = match find_subtask_section(content) {
-        Some((name, start)) => (name, start),
-        None => {
-            // No subtask section found, return original content
-            return content.to_string();
-        }
-    };
+#[cfg(feature = "git2")]
+fn open_repository() -> Result<git2::Repository> {
+    git2::Repository::open_from_env().context("Not in a git repository")
+}
 
-    for (i, line) in content.lines().enumerate() {
-        // Check if we're entering the subtasks section
-        if i == section_start {
-            result.push_str(line);
-            result.push('\n');
-            continue;
-        }
+#[cfg(feature = "git2")]
+fn is_git_repo() -> Result<bool> {
+    Ok(git2::Repository::open_from_env().is_ok_and(|repo| !repo.is_bare()))
+}
 
-        // Check if we're leaving the subtasks section
-        if i > section_start && is_leaving_subtask_section(line) {
-            result.push_str(line);
-            result.push('\n');
-            continue;
-        }
+#[cfg(not(feature = "git2"))]
+fn is_git_repo() -> Result<bool> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .context("Failed to run git command")?;
 
-        // If we're in the subtasks section, mark all items as complete
-        if i > section_start && !is_leaving_subtask_section(line) {
-            let trimmed = line.trim();
-            if trimmed.starts_with("- [ ]") {
-                // Replace incomplete checkbox with complete checkbox
-                let item_text = trimmed.strip_prefix("- [ ]").unwrap_or(trimmed).trim();
-                result.push_str(&format!("- [x] {}\n", item_text));
-            } else {
-                result.push_str(line);
-                result.push('\n');
-            }
-        } else {
-            result.push_str(line);
-            result.push('\n');
-        }
-    }
+    Ok(output.status.success())
+}
+
+/// The checked-out branch, or an empty string on a detached HEAD
+#[cfg(feature = "git2")]
+fn get_current_branch() -> Result<String> {
+    let repo = open_repository()?;
+    let head = repo.find_reference("HEAD").context("Failed to read HEAD")?;
+    Ok(head
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[cfg(not(feature = "git2"))]
+fn get_current_branch() -> Result<String> {
+    let output = run_git_command(&["branch", "--show-current"])?;
+    Ok(output.trim().to_string())
+}
 
+#[cfg(feature = "git2")]
+fn branch_exists(branch_name: &str) -> Result<bool> {
+    let repo = open_repository()?;
+    let result = match repo.find_branch(branch_name, git2::BranchType::Local) {
+        Ok(_) => Ok(true),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(false),
+        Err(e) => Err(e).context(format!("Failed to look up branch '{}'", branch_name)),
+    };
     result
 }
 
-/// Find the subtask section in content, preferring "## Subtasks" over "## Checklist"
-fn find_subtask_section(content: &str) -> Option<(&str, usize)> {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut subtasks_start = None;
-    let mut checklist_start = None;
+#[cfg(not(feature = "git2"))]
+fn branch_exists(branch_name: &str) -> Result<bool> {
+    let output = run_git_command(&["branch", "--list", branch_name])?;
+    Ok(!output.trim().is_empty())
+}
 
-    for (i, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("## Subtasks") {
-            subtasks_start = Some(i);
-            break; // Prefer Subtasks over Checklist
-        } else if trimmed.starts_with("## Checklist") && checklist_start.is_none() {
-            checklist_start = Some(i);
-        }
+/// The branch `origin/HEAD` points to, without the `origin/` prefix
+#[cfg(feature = "git2")]
+fn origin_head_branch() -> Option<String> {
+    let repo = open_repository().ok()?;
+    let head = repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+    head.symbolic_target()?
+        .strip_prefix("refs/remotes/origin/")
+        .filter(|branch| !branch.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(not(feature = "git2"))]
+fn origin_head_branch() -> Option<String> {
+    run_git_command(&[
+        "symbolic-ref",
+        "--quiet",
+        "--short",
+        "refs/remotes/origin/HEAD",
+    ])
+    .ok()
+    .and_then(|head| head.trim().strip_prefix("origin/").map(str::to_string))
+    .filter(|head| !head.is_empty())
+}
+
+/// Switch to an existing branch, creating it from `origin/<branch>` when
+/// only the remote has it
+#[cfg(feature = "git2")]
+fn checkout_branch(branch: &str) -> Result<()> {
+    if dry_run() {
+        println!("🔧 Would run: git checkout {}", branch);
+        return Ok(());
+    }
+    let repo = open_repository()?;
+    if repo.find_branch(branch, git2::BranchType::Local).is_err() {
+        let remote = repo
+            .find_branch(&format!("origin/{}", branch), git2::BranchType::Remote)
+            .context(format!("Branch '{}' not found", branch))?;
+        let commit = remote.get().peel_to_commit()?;
+        let mut local = repo
+            .branch(branch, &commit, false)
+            .context(format!("Failed to create branch '{}'", branch))?;
+        local.set_upstream(Some(&format!("origin/{}", branch)))?;
     }
 
-    // Return Subtasks if found, otherwise Checklist
-    if let Some(start) = subtasks_start {
-        Some(("## Subtasks", start))
-    } else {
-        checklist_start.map(|start| ("## Checklist", start))
+    let refname = format!("refs/heads/{}", branch);
+    let target = repo.revparse_single(&refname)?;
+    repo.checkout_tree(&target, Some(git2::build::CheckoutBuilder::new().safe()))
+        .context(format!(
+            "Failed to check out '{}': commit or stash the conflicting changes first",
+            branch
+        ))?;
+    repo.set_head(&refname)
+        .context(format!("Failed to switch to '{}'", branch))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "git2"))]
+fn checkout_branch(branch: &str) -> Result<()> {
+    run_git_command(&["checkout", branch])?;
+    Ok(())
+}
+
+/// Create a branch at HEAD and switch to it, keeping working tree changes
+#[cfg(feature = "git2")]
+fn create_branch(branch: &str) -> Result<()> {
+    if dry_run() {
+        println!("🔧 Would run: git checkout -b {}", branch);
+        return Ok(());
     }
+    let repo = open_repository()?;
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context("Failed to resolve HEAD")?;
+    repo.branch(branch, &head, false)
+        .context(format!("Failed to create branch '{}'", branch))?;
+    repo.set_head(&format!("refs/heads/{}", branch))
+        .context(format!("Failed to switch to '{}'", branch))?;
+    Ok(())
 }
 
-/// Check if we're leaving a subtask section
-fn is_leaving_subtask_section(line: &str) -> bool {
-    let trimmed = line.trim();
-    trimmed.starts_with("##") && !trimmed.starts_with("###")
+#[cfg(not(feature = "git2"))]
+fn create_branch(branch: &str) -> Result<()> {
+    run_git_command(&["checkout", "-b", branch])?;
+    Ok(())
 }
 
-fn list_subtasks(id: String) -> Result<()> {
-    let tasks = load_tasks()?;
+/// Changed and untracked paths in `git status --short` form
+#[cfg(feature = "git2")]
+fn git_status_lines() -> Result<Vec<String>> {
+    let repo = open_repository()?;
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .context("Failed to read git status")?;
+
+    Ok(statuses
+        .iter()
+        .map(|entry| {
+            let status = entry.status();
+            let index = if status.is_conflicted() {
+                'U'
+            } else if status.is_index_new() {
+                'A'
+            } else if status.is_index_modified() {
+                'M'
+            } else if status.is_index_deleted() {
+                'D'
+            } else if status.is_index_renamed() {
+                'R'
+            } else if status.is_index_typechange() {
+                'T'
+            } else if status.is_wt_new() {
+                '?'
+            } else {
+                ' '
+            };
+            let worktree = if status.is_conflicted() {
+                'U'
+            } else if status.is_wt_new() {
+                '?'
+            } else if status.is_wt_modified() {
+                'M'
+            } else if status.is_wt_deleted() {
+                'D'
+            } else if status.is_wt_renamed() {
+                'R'
+            } else if status.is_wt_typechange() {
+                'T'
+            } else {
+                ' '
+            };
+            format!("{}{} {}", index, worktree, entry.path().unwrap_or_default())
+        })
+        .collect())
+}
 
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+#[cfg(not(feature = "git2"))]
+fn git_status_lines() -> Result<Vec<String>> {
+    let output = run_git_command(&["status", "--short"])?;
+    Ok(output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
 
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
+fn has_uncommitted_changes() -> Result<bool> {
+    Ok(!git_status_lines()?.is_empty())
+}
 
-    let task = &task_file.task;
+/// Contents of a repository-relative path at a revision
+#[cfg(feature = "git2")]
+fn file_at_revision(rev: &str, path: &str) -> Result<String> {
+    let repo = open_repository()?;
+    let tree = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+        .context(format!("Unknown revision '{}'", rev))?;
+    let blob = tree
+        .get_path(Path::new(path))
+        .and_then(|entry| entry.to_object(&repo))
+        .and_then(|object| object.peel_to_blob())
+        .context(format!("'{}' does not exist at {}", path, rev))?;
+    Ok(String::from_utf8_lossy(blob.content()).into_owned())
+}
 
-    println!("📋 Subtasks for task {}: {}", id, task.title);
-    println!();
+#[cfg(not(feature = "git2"))]
+fn file_at_revision(rev: &str, path: &str) -> Result<String> {
+    run_git_command(&["show", &format!("{}:{}", rev, path)])
+}
 
-    // Find the subtask section (preferring Subtasks over Checklist)
-    let (_section_name, section_start) = match find_subtask_section(&content) {
-        Some((name, start)) => (name, start),
-        None => {
-            println!("  No subtasks section found.");
-            return Ok(());
+/// Date of the last commit touching each file under `dirs`, keyed by absolute
+/// path; empty outside a git repository
+fn git_last_commit_dates(dirs: &[&str]) -> std::collections::HashMap<PathBuf, chrono::NaiveDate> {
+    let mut dates = std::collections::HashMap::new();
+    if !is_git_repo().unwrap_or(false) {
+        return dates;
+    }
+    let Ok(root) = run_git_command(&["rev-parse", "--show-toplevel"]) else {
+        return dates;
+    };
+    let mut args = vec!["log", "--name-only", "--format=%x1e%cs", "--"];
+    args.extend(dirs);
+    let Ok(log) = run_git_command(&args) else {
+        return dates;
+    };
+    let root = Path::new(root.trim());
+    for entry in log.split('\x1e') {
+        let mut lines = entry.lines().filter(|line| !line.trim().is_empty());
+        let Some(date) = lines
+            .next()
+            .and_then(|date| chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        // Newest commits come first
+        for path in lines {
+            dates.entry(root.join(path)).or_insert(date);
         }
+    }
+    dates
+}
+
+/// The configured git user.name, if any
+#[cfg(feature = "git2")]
+fn git_user_name() -> Option<String> {
+    let config = match open_repository() {
+        Ok(repo) => repo.config(),
+        Err(_) => git2::Config::open_default(),
     };
+    config
+        .and_then(|config| config.get_string("user.name"))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
 
-    // Find and display subtask items
-    let mut in_subtasks = false;
-    let mut has_items = false;
+#[cfg(not(feature = "git2"))]
+fn git_user_name() -> Option<String> {
+    run_git_command(&["config", "user.name"])
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
 
-    for (i, line) in content.lines().enumerate() {
-        // Check if we're entering the subtasks section
-        if i == section_start {
-            in_subtasks = true;
-            continue;
-        }
+// Helper functions
 
-        // Check if we're leaving the subtasks section
-        if in_subtasks && is_leaving_subtask_section(line) {
-            break;
-        }
+/// Quote a value for a curl config file
+fn curl_config_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
 
-        // If we're in the subtasks section, look for subtask items
-        if in_subtasks {
-            let trimmed = line.trim();
-            if trimmed.starts_with("- [") {
-                has_items = true;
-                // Extract the item text (remove the checkbox part)
-                let item_text = if trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]") {
-                    // Completed item
-                    let text = trimmed
-                        .strip_prefix("- [x]")
-                        .or_else(|| trimmed.strip_prefix("- [X]"))
-                        .unwrap_or(trimmed)
-                        .trim();
-                    format!("✅ {}", text)
-                } else if trimmed.starts_with("- [ ]") {
-                    // Incomplete item
-                    let text = trimmed.strip_prefix("- [ ]").unwrap_or(trimmed).trim();
-                    format!("⏳ {}", text)
-                } else {
-                    // Fallback for other formats
-                    trimmed.to_string()
-                };
-                println!("  {}", item_text);
-            }
-        }
+/// Send a request with curl and return the status code and response body.
+/// Everything, credentials, tokens and secret URLs included, goes to curl on stdin rather
+/// than in its arguments, where other users could see them.
+fn curl_request(
+    method: &str,
+    url: &str,
+    credentials: Option<&str>,
+    headers: &[&str],
+    body: Option<&str>,
+) -> Result<(u16, String)> {
+    use std::io::Write;
+
+    let mut request = format!(
+        "url = {}\nrequest = {}\nwrite-out = \"\\n%{{http_code}}\"\n",
+        curl_config_string(url),
+        method
+    );
+    if let Some(credentials) = credentials {
+        request.push_str(&format!("user = {}\n", curl_config_string(credentials)));
+    }
+    for header in headers {
+        request.push_str(&format!("header = {}\n", curl_config_string(header)));
+    }
+    if let Some(body) = body {
+        request.push_str(&format!("data-binary = {}\n", curl_config_string(body)));
     }
 
-    if !has_items {
-        println!("  No subtasks found.");
+    let mut child = std::process::Command::new("curl")
+        .args(["-sS", "-m", "30", "-K", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(request.as_bytes())?;
     }
 
-    Ok(())
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        // The URL may hold a secret (webhook tokens), so errors leave it out
+        return Err(anyhow::anyhow!(
+            "{} request failed: {}",
+            method,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let code = code
+        .trim()
+        .parse()
+        .context(format!("Unexpected curl output for a {} request", method))?;
+    Ok((code, body.to_string()))
 }
-fn set_task_field(id: String, field: &str, value: String) -> Result<()> {
-    let tasks = load_tasks()?;
-    let task_file = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
-
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
 
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
+/// Branch task branches start from: `git.base_branch`, else the branch
+/// `origin/HEAD` points to, else `main` or `master`, whichever exists
+fn base_branch(config: &Config) -> String {
+    if let Some(ref base) = config.git.base_branch {
+        return base.clone();
+    }
+    if let Some(head) = origin_head_branch() {
+        return head;
+    }
+    let exists = |branch: &str| branch_exists(branch).unwrap_or(false);
+    if !exists("main") && exists("master") {
+        "master".to_string()
+    } else {
+        "main".to_string()
+    }
+}
 
-    if let Some(front_matter) = parsed.data {
-        // Extract the task data
-        let mut task = extract_task_from_pod(&front_matter)?;
+/// Resolve `me` to the git user.name; other names are used as given
+fn resolve_assignee(user: &str) -> Result<String> {
+    if user.eq_ignore_ascii_case("me") {
+        git_user_name().context("Cannot resolve 'me': git config user.name is not set")
+    } else {
+        Ok(user.to_string())
+    }
+}
 
-        // Update the specific field
-        match field {
-            "title" => task.title = value.clone(),
-            "priority" => task.priority = Some(value.clone()),
-            "tags" => {
-                let tags: Vec<String> = value.split(',').map(|s| s.trim().to_string()).collect();
-                task.tags = Some(tags);
+/// Join command arguments for display, quoting those containing spaces
+fn quote_args(args: &[&str]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("{:?}", arg)
+            } else {
+                arg.to_string()
             }
-            "due" => task.due = Some(value.clone()),
-            _ => return Err(anyhow::anyhow!("Unknown field: {}", field)),
-        }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-        // Rebuild the file content
-        let mut new_content = String::new();
+/// Git subcommands that only read, and so still run under --dry-run
+const READ_ONLY_GIT_COMMANDS: [&str; 10] = [
+    "rev-parse",
+    "symbolic-ref",
+    "status",
+    "diff",
+    "log",
+    "show",
+    "ls-remote",
+    "ls-files",
+    "rev-list",
+    "config",
+];
 
-        // Add updated front-matter
-        new_content.push_str("---\n");
-        new_content.push_str(&format!("id: {}\n", task.id));
-        new_content.push_str(&format!("title: \"{}\"\n", task.title));
+fn run_git_command(args: &[&str]) -> Result<String> {
+    let read_only = args.first().is_some_and(|cmd| {
+        READ_ONLY_GIT_COMMANDS.contains(cmd)
+            || (*cmd == "branch"
+                && args
+                    .iter()
+                    .any(|a| *a == "--list" || *a == "--show-current"))
+    });
+    if dry_run() && !read_only {
+        println!("🔧 Would run: git {}", quote_args(args));
+        return Ok(String::new());
+    }
 
-        if let Some(ref status) = task.status {
-            new_content.push_str(&format!("status: {}\n", status));
-        }
+    let output = std::process::Command::new("git")
+        .args(args)
+        .output()
+        .context(format!("Failed to run git command: git {}", args.join(" ")))?;
 
-        if let Some(ref priority) = task.priority {
-            new_content.push_str(&format!("priority: {}\n", priority));
-        }
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("Git command failed: {}", error_msg));
+    }
 
-        if let Some(ref tags) = task.tags {
-            if tags.len() == 1 {
-                new_content.push_str(&format!("tags: [\"{}\"]\n", tags[0]));
-            } else {
-                new_content.push_str("tags: [");
-                for (i, tag) in tags.iter().enumerate() {
-                    if i > 0 {
-                        new_content.push_str(", ");
-                    }
-                    new_content.push_str(&format!("\"{}\"", tag));
-                }
-                new_content.push_str("]\n");
-            }
-        }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
 
-        if let Some(ref project) = task.project {
-            new_content.push_str(&format!("project: {}\n", project));
-        }
+fn run_terminal_cmd_internal(args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new(args[0])
+        .args(&args[1..])
+        .status()
+        .context(format!("Failed to run command: {}", args.join(" ")))?;
 
-        if let Some(ref created) = task.created {
-            new_content.push_str(&format!("created: {}\n", created));
-        }
+    if !status.success() {
+        return Err(anyhow::anyhow!("Command failed: {}", args.join(" ")));
+    }
+
+    Ok(())
+}
+
+fn cleanup_done_tasks(yes: bool, config: &Config) -> Result<()> {
+    let tasks = load_tasks(config)?;
+    let done_tasks: Vec<_> = tasks
+        .into_iter()
+        .filter(|task_file| task_file.task.status.as_deref() == Some("done"))
+        .collect();
+
+    if done_tasks.is_empty() {
+        println!("✅ No done tasks to clean up");
+        return Ok(());
+    }
 
-        if let Some(ref due) = task.due {
-            new_content.push_str(&format!("due: {}\n", due));
-        }
+    println!("🗑️  Found {} done task(s) to clean up:", done_tasks.len());
+    for task_file in &done_tasks {
+        println!("  - {}: {}", task_file.task.id, task_file.task.title);
+    }
 
-        new_content.push_str("---\n\n");
+    if !yes && !dry_run() {
+        print!("❓ Are you sure you want to delete these task files? (y/N): ");
+        use std::io::{self, Write};
+        io::stdout().flush()?;
 
-        // Add the original markdown content
-        new_content.push_str(&parsed.content);
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
 
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
-        ))?;
+        if !input.trim().to_lowercase().starts_with('y') {
+            println!("❌ Cleanup cancelled");
+            return Ok(());
+        }
+    }
 
-        println!("✅ Updated {} for task {}: {}", field, id, value);
-    } else {
-        return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
-        ));
+    let _lock = lock_tasks(config)?;
+    let mut deleted_count = 0;
+    for task_file in done_tasks {
+        if let Err(e) = remove_task_file(&task_file.file_path, config) {
+            eprintln!("⚠️  Failed to delete {}: {}", task_file.file_path, e);
+        } else {
+            if !dry_run() {
+                println!("🗑️  Deleted: {}", task_file.file_path);
+            }
+            deleted_count += 1;
+        }
     }
 
+    let verb = if dry_run() {
+        "Would clean up"
+    } else {
+        "Cleaned up"
+    };
+    println!("✅ {} {} done task(s)", verb, deleted_count);
     Ok(())
 }
 
-fn add_task_note(id: String, note: String) -> Result<()> {
-    let tasks = load_tasks()?;
-    let task_file = tasks
+fn renumber_tasks(dry_run: bool, config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
+
+    let tasks = load_tasks(config)?;
+    let mut numbered: Vec<(u32, TaskFile)> = tasks
         .into_iter()
-        .find(|tf| tf.task.id == id)
-        .context(format!("Task with ID '{}' not found", id))?;
+        .filter_map(|tf| tf.task.id.parse::<u32>().ok().map(|n| (n, tf)))
+        .collect();
+    numbered.sort_by_key(|(n, _)| *n);
 
-    // Read the current file content
-    let content = std::fs::read_to_string(&task_file.file_path)
-        .context(format!("Failed to read task file: {}", task_file.file_path))?;
+    if let Some(pair) = numbered.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(anyhow::anyhow!(
+            "Task ID '{}' is used more than once; run `mdtasks doctor --fix-duplicates` first",
+            pair[0].1.task.id
+        ));
+    }
 
-    // Parse the front-matter and content
-    let matter = Matter::<gray_matter::engine::YAML>::new();
-    let parsed = matter.parse(&content);
+    let mapping: Vec<(String, String)> = numbered
+        .iter()
+        .enumerate()
+        .map(|(i, (_, tf))| (tf.task.id.clone(), format!("{:03}", i + 1)))
+        .collect();
+    let new_id = |id: &str| {
+        mapping
+            .iter()
+            .find(|(old, _)| same_task_id(old, id))
+            .map(|(_, new)| new.clone())
+    };
 
-    if let Some(front_matter) = parsed.data {
-        // Extract the task data
-        let task = extract_task_from_pod(&front_matter)?;
+    // Work out every new file before touching anything
+    let attachments = Path::new(&config.tasks.dir).join("attachments");
+    let mut moves = Vec::new();
+    for (_, task_file) in &numbered {
+        let old_id = &task_file.task.id;
+        let id = new_id(old_id).unwrap_or_else(|| old_id.clone());
+
+        let mut task = task_file.task.clone();
+        task.id = id.clone();
+        task.parent = task.parent.map(|p| new_id(&p).unwrap_or(p));
+        task.depends_on = task
+            .depends_on
+            .map(|ids| ids.into_iter().map(|d| new_id(&d).unwrap_or(d)).collect());
+
+        // Bodies are read for every task, since any of them may link to a moved one
+        let content = read_task_file(&task_file.file_path, config)?;
+        let body = Matter::<gray_matter::engine::YAML>::new()
+            .parse(&content)
+            .content;
+        let new_body = remap_wiki_links(
+            &body.replace(
+                &format!("attachments/{}/", old_id),
+                &format!("attachments/{}/", id),
+            ),
+            &|link| new_id(link).filter(|new| !same_task_id(new, link)),
+        );
+        if task.id == task_file.task.id
+            && task.parent == task_file.task.parent
+            && task.depends_on == task_file.task.depends_on
+            && new_body == body
+        {
+            continue;
+        }
+        let new_content = rewrite_front_matter(&content, &task) + &new_body;
 
-        // Rebuild the file content
-        let mut new_content = String::new();
+        let old_path = PathBuf::from(&task_file.file_path);
+        let new_path = if id != *old_id {
+            renumbered_path(&old_path, &id).unwrap_or_else(|| old_path.clone())
+        } else {
+            old_path.clone()
+        };
+        moves.push((old_id.clone(), id, old_path, new_path, new_content));
+    }
 
-        // Add front-matter (unchanged)
-        new_content.push_str("---\n");
-        new_content.push_str(&format!("id: {}\n", task.id));
-        new_content.push_str(&format!("title: \"{}\"\n", task.title));
+    if moves.is_empty() {
+        println!("✅ Task IDs are already sequential");
+        return Ok(());
+    }
 
-        if let Some(ref status) = task.status {
-            new_content.push_str(&format!("status: {}\n", status));
+    for (old_id, id, old_path, new_path, _) in &moves {
+        if old_id == id {
+            println!("🔗 {}: update references ({})", old_id, old_path.display());
+        } else {
+            println!(
+                "🔢 {} → {}: {} → {}",
+                old_id,
+                id,
+                old_path.display(),
+                new_path.display()
+            );
         }
+    }
+
+    if dry_run {
+        println!("🔍 {} task(s) would be changed", moves.len());
+        return Ok(());
+    }
 
-        if let Some(ref priority) = task.priority {
-            new_content.push_str(&format!("priority: {}\n", priority));
+    // A new path may only be taken by a file that is itself being moved away
+    for (_, _, _, new_path, _) in &moves {
+        if new_path.exists() && !moves.iter().any(|(_, _, old, _, _)| old == new_path) {
+            return Err(anyhow::anyhow!(
+                "Cannot renumber: {} already exists",
+                new_path.display()
+            ));
         }
+    }
 
-        if let Some(ref tags) = task.tags {
-            if tags.len() == 1 {
-                new_content.push_str(&format!("tags: [\"{}\"]\n", tags[0]));
-            } else {
-                new_content.push_str("tags: [");
-                for (i, tag) in tags.iter().enumerate() {
-                    if i > 0 {
-                        new_content.push_str(", ");
-                    }
-                    new_content.push_str(&format!("\"{}\"", tag));
-                }
-                new_content.push_str("]\n");
+    // Stage the new contents next to their targets; nothing is changed if this fails
+    let staged: Vec<PathBuf> = moves
+        .iter()
+        .map(|(_, _, _, new_path, _)| {
+            new_path.with_file_name(format!(
+                ".{}.renumber.tmp",
+                new_path.file_name().unwrap_or_default().to_string_lossy()
+            ))
+        })
+        .collect();
+    for ((_, _, _, _, content), temp) in moves.iter().zip(&staged) {
+        if let Err(e) = std::fs::write(temp, content) {
+            for temp in &staged {
+                let _ = std::fs::remove_file(temp);
             }
+            return Err(e).context(format!("Failed to write {}", temp.display()));
         }
+    }
 
-        if let Some(ref project) = task.project {
-            new_content.push_str(&format!("project: {}\n", project));
-        }
+    // Swap the staged files in; `mdtasks undo` restores the old ones
+    for (_, _, old_path, new_path, _) in &moves {
+        record_undo(&old_path.to_string_lossy())?;
+        record_undo(&new_path.to_string_lossy())?;
+    }
+    for (_, _, old_path, _, _) in &moves {
+        std::fs::remove_file(old_path)
+            .context(format!("Failed to remove {}", old_path.display()))?;
+    }
+    for ((_, _, _, new_path, _), temp) in moves.iter().zip(&staged) {
+        std::fs::rename(temp, new_path).context(format!(
+            "Failed to move {} into place (run `mdtasks undo` to restore)",
+            new_path.display()
+        ))?;
+    }
 
-        if let Some(ref created) = task.created {
-            new_content.push_str(&format!("created: {}\n", created));
+    // Attachment directories follow their task, via temporary names to avoid clashes
+    let renamed_dirs: Vec<(PathBuf, PathBuf)> = moves
+        .iter()
+        .filter(|(old_id, id, _, _, _)| old_id != id && attachments.join(old_id).is_dir())
+        .map(|(old_id, id, _, _, _)| {
+            let temp = attachments.join(format!(".renumber-{}", old_id));
+            (temp, attachments.join(id))
+        })
+        .collect();
+    for (old_id, id, _, _, _) in &moves {
+        if old_id != id && attachments.join(old_id).is_dir() {
+            std::fs::rename(
+                attachments.join(old_id),
+                attachments.join(format!(".renumber-{}", old_id)),
+            )?;
         }
+    }
+    for (temp, target) in &renamed_dirs {
+        std::fs::rename(temp, target)?;
+    }
 
-        if let Some(ref due) = task.due {
-            new_content.push_str(&format!("due: {}\n", due));
-        }
+    println!("✅ Renumbered {} task(s)", moves.len());
 
-        new_content.push_str("---\n\n");
+    Ok(())
+}
 
-        // Process the markdown content to add the note
-        let processed_content = add_note_to_content(&parsed.content, &note);
-        new_content.push_str(&processed_content);
+// Benchmark
+
+/// Write `count` varied task files into `dir`
+fn generate_bench_tasks(dir: &Path, count: usize) -> Result<()> {
+    let statuses = ["pending", "active", "done", "partial"];
+    let priorities = ["high", "medium", "low"];
+    let tags = ["backend", "frontend", "docs", "bug", "infra"];
+
+    std::fs::create_dir_all(dir)?;
+    for i in 1..=count {
+        let task = Task {
+            id: format!("{:03}", i),
+            title: format!("Synthetic task number {}", i),
+            status: Some(statuses[i % statuses.len()].to_string()),
+            priority: Some(priorities[i % priorities.len()].to_string()),
+            tags: Some(vec![
+                tags[i % tags.len()].to_string(),
+                tags[(i / 7) % tags.len()].to_string(),
+            ]),
+            project: Some(format!("project-{}", i % 10)),
+            created: Some(format!("2025-{:02}-{:02}", i % 12 + 1, i % 28 + 1)),
+            due: (i % 3 == 0).then(|| format!("2026-{:02}-{:02}", i % 12 + 1, i % 28 + 1)),
+            completed: None,
+            started: None,
+            branch: None,
+            scheduled: None,
+            estimate: Some(format!("{}h", i % 8 + 1)),
+            parent: None,
+            depends_on: None,
+            recur: None,
+            wait_until: None,
+            postponed: None,
+            milestone: None,
+            assignee: None,
+            delegated_to: None,
+            follow_up: None,
+            updated: None,
+        };
 
-        // Write the updated file
-        std::fs::write(&task_file.file_path, new_content).context(format!(
-            "Failed to write updated task file: {}",
-            task_file.file_path
-        ))?;
+        let mut content = format_front_matter(&task);
+        content.push_str("# Task Details\n\n## Notes\n");
+        content
+            .push_str(&"Some notes about the task to give the body a realistic size.\n".repeat(5));
+        content.push_str("\n## Subtasks\n\n");
+        for j in 1..=5 {
+            let mark = if j <= i % 6 { "x" } else { " " };
+            content.push_str(&format!("- [{}] Step {}\n", mark, j));
+        }
 
-        println!("✅ Added note to task {}: {}", id, note);
-    } else {
-        return Err(anyhow::anyhow!(
-            "Could not parse front-matter from task file"
-        ));
+        std::fs::write(
+            dir.join(format!("{:03}-synthetic-task-{}.md", i, i)),
+            content,
+        )?;
     }
 
     Ok(())
 }
 
-fn add_note_to_content(content: &str, note: &str) -> String {
-    let mut result = String::new();
-    let mut in_notes = false;
-    let mut notes_added = false;
-
-    for line in content.lines() {
-        // Check if we're entering the notes section
-        if line.trim().starts_with("## Notes") {
-            in_notes = true;
-            result.push_str(line);
-            result.push('\n');
-            continue;
-        }
+/// Fastest of `runs` timings of `f`, in milliseconds
+fn time_best(runs: usize, mut f: impl FnMut() -> Result<()>) -> Result<f64> {
+    let mut best = f64::MAX;
+    for _ in 0..runs.max(1) {
+        let started = std::time::Instant::now();
+        f()?;
+        best = best.min(started.elapsed().as_secs_f64() * 1000.0);
+    }
+    Ok(best)
+}
 
-        // Check if we're leaving the notes section
-        if in_notes && line.trim().starts_with("##") && !line.trim().starts_with("###") {
-            // Add the note before leaving the section
-            if !notes_added {
-                result.push_str(&format!("{}\n\n", note));
-                notes_added = true;
-            }
-            in_notes = false;
-        }
+fn run_benchmark(count: usize, runs: usize) -> Result<()> {
+    let root = std::env::temp_dir().join(format!("mdtasks-bench-{}", std::process::id()));
+    let tasks_dir = root.join("tasks");
 
-        // If we're in the notes section, add the note after the first empty line
-        if in_notes && line.trim().is_empty() && !notes_added {
-            result.push_str(line);
-            result.push('\n');
-            result.push_str(&format!("{}\n", note));
-            notes_added = true;
-        } else {
-            result.push_str(line);
-            result.push('\n');
-        }
+    // Keep the benchmark's index away from the real one
+    if CONTEXT_STATE_DIR.set(root.join(".mdtasks")).is_err() {
+        return Err(anyhow::anyhow!(
+            "bench cannot be combined with --context or --tasks-dir"
+        ));
     }
 
-    // If we never found a notes section, add it
-    if !notes_added {
-        result.push_str("\n## Notes\n");
-        result.push_str(&format!("{}\n", note));
-    }
+    println!(
+        "⏱️  Benchmarking {} synthetic tasks in {}",
+        count,
+        root.display()
+    );
 
-    result
-}
-fn git_start_branch(task_id: String, config: &Config) -> Result<()> {
-    // First, check if we're in a git repository
-    if !is_git_repo()? {
-        return Err(anyhow::anyhow!("Not in a git repository"));
-    }
+    let result = (|| -> Result<Vec<(&str, f64)>> {
+        let started = std::time::Instant::now();
+        generate_bench_tasks(&tasks_dir, count)?;
+        let mut timings = vec![("generate", started.elapsed().as_secs_f64() * 1000.0)];
 
-    // Get the task details
-    let tasks = load_tasks()?;
-    let task = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == task_id)
-        .context(format!("Task with ID '{}' not found", task_id))?;
+        let mut config = Config::default();
+        config.tasks.dir = tasks_dir.to_string_lossy().to_string();
 
-    // Check if we're on main branch
-    let current_branch = get_current_branch()?;
-    if current_branch != "main" {
-        return Err(anyhow::anyhow!(
-            "Must be on main branch to start a task branch. Current branch: {}",
-            current_branch
+        config.tasks.index = false;
+        timings.push((
+            "load (no index)",
+            time_best(runs, || load_tasks(&config).map(drop))?,
         ));
-    }
 
-    // Check if there are unstaged changes and warn
-    let has_unstaged = has_uncommitted_changes()?;
-    if has_unstaged {
-        println!("⚠️  Warning: You have unstaged changes that will be auto-stashed and restored");
-    }
+        config.tasks.index = true;
+        timings.push((
+            "load (index, cold)",
+            time_best(runs, || {
+                let _ = std::fs::remove_file(task_index_path());
+                load_tasks(&config).map(drop)
+            })?,
+        ));
+        timings.push((
+            "load (index, warm)",
+            time_best(runs, || load_tasks(&config).map(drop))?,
+        ));
 
-    // Pull latest changes from main with auto-stash (keeps changes)
-    println!("🔄 Pulling latest changes from main...");
-    run_git_command(&["pull", "--rebase", "--autostash", "origin", "main"])?;
+        timings.push((
+            "list --sort due",
+            time_best(runs, || {
+                let mut tasks = load_tasks(&config)?;
+                sort_task_files(&mut tasks, "due", false, &config);
+                Ok(())
+            })?,
+        ));
 
-    // Create branch name from task
-    let branch_name = format!(
-        "{}{}-{}",
-        config.git.branch_prefix,
-        task_id,
-        task.task
-            .title
-            .to_lowercase()
-            .replace(" ", "-")
-            .replace(":", "")
-            .replace(",", "")
-            .replace(".", "")
-            .replace("!", "")
-            .replace("?", "")
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '-')
-            .collect::<String>()
-    );
+        let (status, tag) = (Some("pending".to_string()), Some("backend".to_string()));
+        timings.push((
+            "query status+tag",
+            time_best(runs, || {
+                let matching = load_tasks(&config)?
+                    .iter()
+                    .filter(|tf| task_matches_filters(&tf.task, &status, &tag, &None))
+                    .count();
+                std::hint::black_box(matching);
+                Ok(())
+            })?,
+        ));
 
-    // Check if branch already exists
-    if branch_exists(&branch_name)? {
-        return Err(anyhow::anyhow!("Branch '{}' already exists", branch_name));
-    }
+        Ok(timings)
+    })();
 
-    // Create and checkout new branch
-    println!("🌿 Creating branch: {}", branch_name);
-    run_git_command(&["checkout", "-b", &branch_name])?;
+    let _ = std::fs::remove_dir_all(&root);
+    let timings = result?;
 
-    // Update task status to active if it's pending
-    if task.task.status.as_deref() == Some("pending") {
-        println!("🚀 Marking task {} as active", task_id);
-        run_terminal_cmd_internal(&["mdtasks", "start", &task_id])?;
+    println!("   (fastest of {} run(s))", runs.max(1));
+    for (name, ms) in &timings {
+        println!("  {:<20} {:>10.1} ms", name, ms);
     }
 
-    println!(
-        "✅ Started work on task {} in branch '{}'",
-        task_id, branch_name
-    );
-    println!("📝 Task: {}", task.task.title);
+    let time_of = |name: &str| timings.iter().find(|(n, _)| *n == name).map(|(_, ms)| *ms);
+    if let (Some(plain), Some(warm)) = (time_of("load (no index)"), time_of("load (index, warm)")) {
+        println!(
+            "📈 Index speedup for loading: {:.1}x ([tasks] index = {})",
+            plain / warm.max(0.001),
+            if plain > warm {
+                "true recommended"
+            } else {
+                "false is fine"
+            }
+        );
+    }
 
     Ok(())
 }
 
-fn is_gh_cli_available() -> Result<bool> {
-    let output = std::process::Command::new("gh")
-        .args(["--version"])
-        .output();
-
-    match output {
-        Ok(output) => Ok(output.status.success()),
-        Err(_) => Ok(false),
+// Shell completions
+
+/// Subcommand paths (e.g. ["subtasks", "add"]) whose first positional argument is a task ID
+fn id_completion_paths() -> Vec<Vec<String>> {
+    fn walk(command: &clap::Command, prefix: &[String], paths: &mut Vec<Vec<String>>) {
+        for sub in command.get_subcommands() {
+            let mut path = prefix.to_vec();
+            path.push(sub.get_name().to_string());
+
+            if sub
+                .get_positionals()
+                .next()
+                .is_some_and(|arg| matches!(arg.get_id().as_str(), "id" | "ids"))
+            {
+                paths.push(path.clone());
+            }
+            walk(sub, &path, paths);
+        }
     }
+
+    let mut paths = Vec::new();
+    walk(&Cli::command(), &[], &mut paths);
+    paths
 }
 
-fn format_pr_body(task: &Task, task_content: &str) -> String {
-    let mut body = String::new();
+fn print_completions(shell: clap_complete::Shell) -> Result<()> {
+    let mut command = Cli::command();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, "mdtasks", &mut script);
+    let mut script = String::from_utf8(script).context("Completion script is not valid UTF-8")?;
+
+    let paths = id_completion_paths();
+
+    // Extend the generated script so task ID arguments complete from the tasks directory
+    match shell {
+        clap_complete::Shell::Bash => {
+            let cases: Vec<String> = paths
+                .iter()
+                .map(|p| format!("\"{}\"", p.join(" ")))
+                .collect();
+            script.push_str(&format!(
+                r#"
+_mdtasks_with_ids() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local path="${{COMP_WORDS[*]:1:COMP_CWORD-1}}"
+    case "$path" in
+        {})
+            COMPREPLY=( $(compgen -W "$(mdtasks _complete-ids 2>/dev/null | cut -f1)" -- "$cur") )
+            return 0
+            ;;
+    esac
+    _mdtasks "$@"
+}}
+complete -F _mdtasks_with_ids -o nosort -o bashdefault -o default mdtasks
+"#,
+                cases.join("|")
+            ));
+        }
+        clap_complete::Shell::Zsh => {
+            // Positional ID arguments are generated with the `_default` action
+            script = script
+                .lines()
+                .map(|line| {
+                    let trimmed = line.trim_start();
+                    let is_id = trimmed.starts_with("':id -") || trimmed.starts_with("'*::ids -");
+                    if is_id && line.ends_with(":_default' \\") {
+                        line.replace(":_default' \\", ":_mdtasks_task_ids' \\")
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            // Define the helper right after `#compdef` so it exists when autoloaded
+            let helper = r#"
+(( $+functions[_mdtasks_task_ids] )) ||
+_mdtasks_task_ids() {
+    local -a ids
+    ids=(${(f)"$(mdtasks _complete-ids 2>/dev/null | sed 's/:/\\:/g; s/\t/:/')"})
+    _describe 'task id' ids
+}
+"#;
+            script = match script.split_once('\n') {
+                Some((first, rest)) => format!("{}\n{}{}\n", first, helper, rest),
+                None => script,
+            };
+        }
+        clap_complete::Shell::Fish => {
+            for path in &paths {
+                let condition = path
+                    .iter()
+                    .map(|name| format!("__fish_seen_subcommand_from {}", name))
+                    .collect::<Vec<_>>()
+                    .join("; and ");
+                script.push_str(&format!(
+                    "complete -c mdtasks -n \"{}\" -f -a \"(mdtasks _complete-ids 2>/dev/null)\"\n",
+                    condition
+                ));
+            }
+        }
+        _ => {}
+    }
 
-    // Add task description
-    body.push_str(&format!("## Task: {}\n\n", task.title));
+    print!("{}", script);
+    Ok(())
+}
 
-    // Add task details
-    if let Some(ref status) = task.status {
-        body.push_str(&format!("**Status:** {}\n", status));
-    }
-    if let Some(ref priority) = task.priority {
-        body.push_str(&format!("**Priority:** {}\n", priority));
-    }
-    if let Some(ref tags) = task.tags {
-        body.push_str(&format!("**Tags:** {}\n", tags.join(", ")));
-    }
-    if let Some(ref project) = task.project {
-        body.push_str(&format!("**Project:** {}\n", project));
+fn print_completion_ids(config: &Config) -> Result<()> {
+    for task_file in load_tasks(config)? {
+        println!("{}\t{}", task_file.task.id, task_file.task.title);
     }
+    Ok(())
+}
 
-    body.push('\n');
+// Locking
 
-    // Add task content (checklist, notes, etc.)
-    if !task_content.trim().is_empty() {
-        body.push_str("## Task Details\n\n");
-        body.push_str(task_content);
-    }
+/// Nesting depth of `lock_tasks` guards held by this process
+static LOCK_DEPTH: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 
-    body
+/// How long to wait for another mdtasks process before giving up
+const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Advisory lock serializing ID allocation and read-modify-write operations
+/// across mdtasks processes; released when the outermost guard is dropped
+struct TasksLock {
+    _file: Option<std::fs::File>,
 }
 
-fn create_github_pr(
-    _branch_name: &str,
-    task: &Task,
-    task_content: &str,
-    config: &GitConfig,
-    draft: bool,
-    reviewers: Option<String>,
-    labels: Option<String>,
-) -> Result<String> {
-    // Check if GitHub CLI is available
-    if !is_gh_cli_available()? {
-        return Err(anyhow::anyhow!(
-            "GitHub CLI (gh) is not installed. Please install it to create PRs automatically.\n\
-            Visit: https://cli.github.com/"
-        ));
+impl Drop for TasksLock {
+    fn drop(&mut self) {
+        LOCK_DEPTH.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
     }
+}
 
-    // Build PR title
-    let pr_title = format!("feat: {} (task #{})", task.title, task.id);
+/// Take the lock in `.mdtasks/lock`; nested calls within a held lock are free
+fn lock_tasks(config: &Config) -> Result<TasksLock> {
+    use std::sync::atomic::Ordering;
 
-    // Build PR body
-    let pr_body = format_pr_body(task, task_content);
+    // A dry run writes nothing, so it needs neither the lock nor writable directories
+    if LOCK_DEPTH.fetch_add(1, Ordering::SeqCst) > 0 || dry_run() {
+        return Ok(TasksLock { _file: None });
+    }
+
+    let acquire = || -> Result<std::fs::File> {
+        // Fail before the first write rather than halfway through a change
+        ensure_writable(Path::new(&config.tasks.dir))?;
+        ensure_writable(&state_dir())?;
+
+        std::fs::create_dir_all(state_dir())?;
+        let path = state_dir().join("lock");
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .context(format!("Failed to open lock file: {}", path.display()))?;
+
+        let started = std::time::Instant::now();
+        let mut waiting = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(file),
+                Err(std::fs::TryLockError::WouldBlock) => {
+                    if started.elapsed() > LOCK_TIMEOUT {
+                        return Err(anyhow::anyhow!(
+                            "Timed out waiting for another mdtasks process (lock: {})",
+                            path.display()
+                        ));
+                    }
+                    if !waiting && started.elapsed() > std::time::Duration::from_millis(500) {
+                        eprintln!("⏳ Waiting for another mdtasks process to finish...");
+                        waiting = true;
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                Err(std::fs::TryLockError::Error(e)) => {
+                    return Err(e).context(format!("Failed to lock: {}", path.display()))
+                }
+            }
+        }
+    };
 
-    // Build gh pr create command
-    let mut args = vec!["pr", "create", "--title", &pr_title, "--body", &pr_body];
+    match acquire() {
+        Ok(file) => Ok(TasksLock { _file: Some(file) }),
+        Err(e) => {
+            LOCK_DEPTH.fetch_sub(1, Ordering::SeqCst);
+            Err(e)
+        }
+    }
+}
 
-    // Add draft flag if requested
-    if draft || config.pr_draft {
-        args.push("--draft");
+// Timezone
+
+/// Timezone from MDTASKS_TZ or `[tasks] timezone`; unset means the system timezone
+static TIMEZONE: OnceLock<chrono_tz::Tz> = OnceLock::new();
+
+fn init_timezone(config: &Config) -> Result<()> {
+    let name = std::env::var("MDTASKS_TZ")
+        .ok()
+        .or_else(|| config.tasks.timezone.clone())
+        .filter(|name| !name.is_empty());
+    if let Some(name) = name {
+        let tz: chrono_tz::Tz = name.parse().map_err(|_| {
+            anyhow::anyhow!(
+                "Unknown timezone '{}' (expected an IANA name like Europe/Amsterdam)",
+                name
+            )
+        })?;
+        let _ = TIMEZONE.set(tz);
     }
+    Ok(())
+}
 
-    // Add reviewers
-    let reviewers_list =
-        reviewers.or_else(|| config.pr_default_reviewers.as_ref().map(|r| r.join(",")));
-    if let Some(ref reviewers_str) = reviewers_list {
-        args.extend(&["--reviewer", reviewers_str]);
+/// Convert an instant to the configured timezone
+fn to_local_time(time: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::FixedOffset> {
+    match TIMEZONE.get() {
+        Some(tz) => time.with_timezone(tz).fixed_offset(),
+        None => time.with_timezone(&chrono::Local).fixed_offset(),
     }
+}
 
-    // Add labels (only if explicitly provided via command line)
-    if let Some(ref labels_str) = labels {
-        args.extend(&["--label", labels_str]);
+/// Interpret a wall-clock time in the configured timezone, or None if it
+/// falls in a DST gap
+fn from_local_time(naive: chrono::NaiveDateTime) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    match TIMEZONE.get() {
+        Some(tz) => naive
+            .and_local_timezone(*tz)
+            .earliest()
+            .map(|t| t.fixed_offset()),
+        None => naive
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .map(|t| t.fixed_offset()),
     }
+}
 
-    // Execute the command
-    let output = std::process::Command::new("gh")
-        .args(&args)
-        .output()
-        .context("Failed to run gh pr create command")?;
+fn local_now() -> chrono::DateTime<chrono::FixedOffset> {
+    to_local_time(chrono::Utc::now())
+}
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("Failed to create PR: {}", error_msg));
-    }
+/// Today's date in the configured timezone
+fn local_today() -> chrono::NaiveDate {
+    local_now().date_naive()
+}
 
-    // Extract PR URL from output
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    let pr_url = output_str.trim().to_string();
+// Undo journal
 
-    Ok(pr_url)
+/// Maximum number of modifications kept in the undo journal
+const UNDO_HISTORY_LIMIT: usize = 20;
+
+/// Journal entry for the current invocation, created on first write
+static UNDO_ENTRY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Task file picked with --file when several files share an ID
+static TARGET_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set by --dry-run: file writes, deletions, and git changes are only printed
+static DRY_RUN: OnceLock<bool> = OnceLock::new();
+
+fn dry_run() -> bool {
+    DRY_RUN.get().copied().unwrap_or(false)
 }
 
-fn git_done_branch(
-    message: Option<String>,
-    no_pr: bool,
-    draft: bool,
-    reviewers: Option<String>,
-    labels: Option<String>,
-    switch_to_main: bool,
-    config: &Config,
-) -> Result<()> {
-    // Check if we're in a git repository
-    if !is_git_repo()? {
-        return Err(anyhow::anyhow!("Not in a git repository"));
+/// State directory of the selected context or --tasks-dir, if one was chosen
+static CONTEXT_STATE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Parent directory holding the project's config or tasks, when found above
+/// the current directory (e.g. `..` when run from `src/`)
+static PROJECT_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+fn state_dir() -> PathBuf {
+    if let Some(dir) = CONTEXT_STATE_DIR.get() {
+        return dir.clone();
     }
+    match PROJECT_ROOT.get() {
+        Some(root) => root.join(".mdtasks"),
+        None => PathBuf::from(".mdtasks"),
+    }
+}
 
-    let current_branch = get_current_branch()?;
+fn undo_dir() -> PathBuf {
+    state_dir().join("undo")
+}
 
-    // Check if we're on a task branch
-    if !current_branch.starts_with(&config.git.branch_prefix) {
-        return Err(anyhow::anyhow!(
-            "Not on a task branch. Current branch: {}",
-            current_branch
-        ));
+/// Get (or create) the journal entry directory for this invocation
+fn current_undo_entry() -> Result<&'static PathBuf> {
+    if let Some(entry) = UNDO_ENTRY.get() {
+        return Ok(entry);
     }
 
-    // Get task ID from branch name
-    let task_id = current_branch
-        .strip_prefix(&config.git.branch_prefix)
-        .ok_or_else(|| anyhow::anyhow!("Invalid task branch format"))?
-        .split('-')
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("Invalid task branch format"))?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let entry = undo_dir().join(format!("{:020}", timestamp));
+    std::fs::create_dir_all(&entry).context(format!(
+        "Failed to create undo journal: {}",
+        entry.display()
+    ))?;
 
-    // Get task details
-    let tasks = load_tasks()?;
-    let task = tasks
-        .into_iter()
-        .find(|tf| tf.task.id == task_id)
-        .context(format!("Task with ID '{}' not found", task_id))?;
+    let command = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    std::fs::write(entry.join("command"), command)?;
 
-    // Mark task as done first (so the task file update gets committed)
-    println!("✅ Marking task {} as done", task_id);
-    run_terminal_cmd_internal(&["mdtasks", "done", task_id])?;
+    prune_undo_entries()?;
 
-    // Commit message
-    let commit_msg =
-        message.unwrap_or_else(|| format!("feat: {} (task #{})", task.task.title, task_id));
+    Ok(UNDO_ENTRY.get_or_init(|| entry))
+}
 
-    // Add all changes and commit (only if there are changes)
-    if has_uncommitted_changes()? {
-        println!("📝 Committing changes...");
-        run_git_command(&["add", "."])?;
-        run_git_command(&["commit", "-m", &commit_msg])?;
-    } else {
-        println!("📝 No changes to commit");
-    }
+/// Record the current state of a file before it is modified or deleted
+fn record_undo(path: &str) -> Result<()> {
+    let entry = current_undo_entry()?;
+    let journal_path = entry.join("journal");
+    let journal = std::fs::read_to_string(&journal_path).unwrap_or_default();
 
-    // Push the task branch to remote
-    println!("🚀 Pushing task branch to remote...");
-    run_git_command(&["push", "origin", &current_branch])?;
+    // Only the state before the first change in this invocation matters
+    if journal
+        .lines()
+        .any(|line| line.split_once('\t').map(|(_, p)| p) == Some(path))
+    {
+        return Ok(());
+    }
 
-    // Create PR if enabled and not skipped
-    let pr_url = if !no_pr && config.git.pr_enabled {
-        println!("🔗 Creating pull request...");
-        match create_github_pr(
-            &current_branch,
-            &task.task,
-            &task.content,
-            &config.git,
-            draft || config.git.pr_draft,
-            reviewers,
-            labels,
-        ) {
-            Ok(url) => {
-                println!("✅ Pull request created: {}", url);
-                Some(url)
-            }
-            Err(e) => {
-                println!("⚠️  Failed to create PR: {}", e);
-                None
-            }
-        }
-    } else if no_pr {
-        println!("⏭️  Skipping PR creation (--no-pr flag)");
-        None
+    let line = if Path::new(path).exists() {
+        let backup = format!("{}.bak", journal.lines().count());
+        std::fs::copy(path, entry.join(&backup))
+            .context(format!("Failed to back up file: {}", path))?;
+        format!("{}\t{}\n", backup, path)
     } else {
-        println!("⏭️  PR creation disabled in config");
-        None
+        format!("-\t{}\n", path)
     };
 
-    // Switch back to main if requested
-    if switch_to_main || config.git.pr_switch_to_main {
-        println!("🔄 Switching back to main branch...");
-        run_git_command(&["checkout", "main"])?;
-        println!("✅ Switched to main branch");
-    }
+    std::fs::write(&journal_path, journal + &line).context(format!(
+        "Failed to write undo journal: {}",
+        journal_path.display()
+    ))?;
 
-    println!(
-        "🎉 Successfully finished task {}: {}",
-        task_id, task.task.title
-    );
-    println!("✅ Changes pushed to remote repository");
+    Ok(())
+}
 
-    if let Some(url) = pr_url {
-        println!("🔗 Pull request: {}", url);
-    }
+/// Check that files can be created in `dir` (or the nearest existing parent,
+/// if it doesn't exist yet), naming the directory when they can't
+fn ensure_writable(dir: &Path) -> Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|ancestor| ancestor.as_os_str().is_empty() || ancestor.exists())
+        .unwrap_or(dir);
+    let existing = if existing.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        existing
+    };
 
-    Ok(())
+    let probe = existing.join(format!(".mdtasks-write-test-{}", std::process::id()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => {
+            let reason = match e.kind() {
+                std::io::ErrorKind::ReadOnlyFilesystem => "read-only file system".to_string(),
+                std::io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+                _ => e.to_string(),
+            };
+            Err(anyhow::anyhow!(
+                "Cannot modify tasks: {} is not writable ({})",
+                existing.display(),
+                reason
+            ))
+        }
+    }
 }
 
-fn git_status(config: &Config) -> Result<()> {
-    // Check if we're in a git repository
-    if !is_git_repo()? {
-        return Err(anyhow::anyhow!("Not in a git repository"));
+/// Write a task file, recording its previous state in the undo journal.
+/// The content goes to a temporary file that is renamed into place, so a
+/// failed write never leaves a truncated task file behind
+fn write_task_file(path: &str, content: &str, config: &Config) -> Result<()> {
+    ensure_inside_tasks_dir(path, config)?;
+
+    let target = Path::new(path);
+    let file_name = target
+        .file_name()
+        .context(format!("Invalid task file path: {}", path))?
+        .to_string_lossy();
+    let temp = target.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    // Renaming would replace a read-only file, which a plain write refuses
+    let permissions = std::fs::metadata(target).ok().map(|m| m.permissions());
+    if permissions.as_ref().is_some_and(|p| p.readonly()) {
+        return Err(anyhow::anyhow!("Cannot modify {}: file is read-only", path));
     }
 
-    let current_branch = get_current_branch()?;
-    println!("🌿 Current branch: {}", current_branch);
+    // Hooks compare against the stored front-matter, which is never encrypted
+    let before = std::fs::read_to_string(path).ok();
 
-    if current_branch.starts_with(&config.git.branch_prefix) {
-        // Extract task ID from branch name
-        if let Some(task_id) = current_branch
-            .strip_prefix(&config.git.branch_prefix)
-            .and_then(|s| s.split('-').next())
-        {
-            // Try to get task details
-            if let Ok(tasks) = load_tasks() {
-                if let Some(task) = tasks.into_iter().find(|tf| tf.task.id == task_id) {
-                    println!("📋 Current task: {} - {}", task_id, task.task.title);
-                    println!(
-                        "📊 Status: {}",
-                        task.task.status.as_deref().unwrap_or("unknown")
-                    );
-                    println!(
-                        "⭐ Priority: {}",
-                        task.task.priority.as_deref().unwrap_or("none")
-                    );
-                } else {
-                    println!("⚠️ Task {} not found in tasks directory", task_id);
-                }
-            }
-        }
-    } else {
-        println!("📋 No active task branch");
+    if dry_run() {
+        print_dry_run_write(path, content, config);
+        run_task_hooks(path, before.as_deref(), content, config);
+        return Ok(());
     }
 
-    // Show git status
-    println!("\n📊 Git status:");
-    run_git_command(&["status", "--short"])?;
-
-    Ok(())
-}
+    let content = &record_task_update(path, content, config);
+    let encrypted = encrypt_task_content(content, config)?;
+    let stored = encrypted.as_deref().unwrap_or(content);
 
-// Helper functions
+    record_undo(path)?;
 
-fn is_git_repo() -> Result<bool> {
-    let output = std::process::Command::new("git")
-        .args(["rev-parse", "--is-inside-work-tree"])
-        .output()
-        .context("Failed to run git command")?;
+    let result = std::fs::write(&temp, stored)
+        .and_then(|_| match permissions {
+            Some(permissions) => std::fs::set_permissions(&temp, permissions),
+            None => Ok(()),
+        })
+        .and_then(|_| std::fs::rename(&temp, target));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e).context(format!("Failed to write {}", path));
+    }
 
-    Ok(output.status.success())
+    run_task_hooks(path, before.as_deref(), content, config);
+    Ok(())
 }
 
-fn get_current_branch() -> Result<String> {
-    let output = run_git_command(&["branch", "--show-current"])?;
-    Ok(output.trim().to_string())
+/// Stamp `updated:` on a task file being written and, with `tasks.history`,
+/// log the front-matter fields that changed to its `## History` section
+fn record_task_update(path: &str, content: &str, config: &Config) -> String {
+    if split_front_matter(content).0.is_empty() {
+        return content.to_string();
+    }
+    let content = set_front_matter_value(content, "updated", &task_timestamp(config));
+    if !config.tasks.history {
+        return content;
+    }
+    // New files have nothing to compare against
+    let Ok(old) = read_task_file(path, config) else {
+        return content;
+    };
+    let changes = field_changes(&front_matter_values(&old), &front_matter_values(&content));
+    let (front_matter, body) = split_front_matter(&content);
+    // Text appended to an armored body would be left unencrypted after it
+    // and break decryption
+    if changes.is_empty() || is_encrypted_body(body) {
+        return content;
+    }
+    let entry = format!(
+        "- {} {}",
+        local_now().format("%Y-%m-%dT%H:%M:%S%:z"),
+        changes.join(", ")
+    );
+    format!(
+        "{}{}",
+        front_matter,
+        append_to_section(body, "History", &entry)
+    )
 }
 
-fn branch_exists(branch_name: &str) -> Result<bool> {
-    let output = run_git_command(&["branch", "--list", branch_name])?;
-    Ok(!output.trim().is_empty())
+/// Describe the change `write_task_file` would make under --dry-run
+fn print_dry_run_write(path: &str, content: &str, config: &Config) {
+    let Ok(old) = read_task_file(path, config) else {
+        println!("📄 Would create {}", path);
+        return;
+    };
+    let mut changes = field_changes(&front_matter_values(&old), &front_matter_values(content));
+    if split_front_matter(&old).1 != split_front_matter(content).1 {
+        changes.push("body edited".to_string());
+    }
+    if changes.is_empty() {
+        println!("📝 Would rewrite {} (no changes)", path);
+    } else {
+        println!("📝 Would update {}: {}", path, changes.join(", "));
+    }
 }
 
-fn has_uncommitted_changes() -> Result<bool> {
-    let output = run_git_command(&["status", "--porcelain"])?;
-    Ok(!output.trim().is_empty())
+/// Delete a task file, recording its previous state in the undo journal
+fn remove_task_file(path: &str, config: &Config) -> Result<()> {
+    ensure_inside_tasks_dir(path, config)?;
+    if dry_run() {
+        println!("🗑️  Would delete {}", path);
+        return Ok(());
+    }
+    record_undo(path)?;
+    std::fs::remove_file(path).context(format!("Failed to remove {}", path))?;
+    Ok(())
 }
 
-fn run_git_command(args: &[&str]) -> Result<String> {
-    let output = std::process::Command::new("git")
-        .args(args)
-        .output()
-        .context(format!("Failed to run git command: git {}", args.join(" ")))?;
-
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("Git command failed: {}", error_msg));
+fn undo_entries() -> Result<Vec<PathBuf>> {
+    let dir = undo_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .context(format!("Failed to read undo journal: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    entries.sort();
 
-fn run_terminal_cmd_internal(args: &[&str]) -> Result<()> {
-    let status = std::process::Command::new(args[0])
-        .args(&args[1..])
-        .status()
-        .context(format!("Failed to run command: {}", args.join(" ")))?;
+    Ok(entries)
+}
 
-    if !status.success() {
-        return Err(anyhow::anyhow!("Command failed: {}", args.join(" ")));
+fn prune_undo_entries() -> Result<()> {
+    let entries = undo_entries()?;
+    if entries.len() > UNDO_HISTORY_LIMIT {
+        for entry in &entries[..entries.len() - UNDO_HISTORY_LIMIT] {
+            std::fs::remove_dir_all(entry)?;
+        }
     }
-
     Ok(())
 }
 
-fn cleanup_done_tasks(yes: bool) -> Result<()> {
-    let tasks = load_tasks()?;
-    let done_tasks: Vec<_> = tasks
-        .into_iter()
-        .filter(|task_file| task_file.task.status.as_deref() == Some("done"))
-        .collect();
-
-    if done_tasks.is_empty() {
-        println!("✅ No done tasks to clean up");
+fn list_undo_entries() -> Result<()> {
+    let entries = undo_entries()?;
+    if entries.is_empty() {
+        println!("✅ Nothing to undo");
         return Ok(());
     }
 
-    println!("🗑️  Found {} done task(s) to clean up:", done_tasks.len());
-    for task_file in &done_tasks {
-        println!("  - {}: {}", task_file.task.id, task_file.task.title);
+    println!("↩️  Recorded modifications (most recent first):");
+    for entry in entries.iter().rev() {
+        let command = std::fs::read_to_string(entry.join("command")).unwrap_or_default();
+        let journal = std::fs::read_to_string(entry.join("journal")).unwrap_or_default();
+        println!(
+            "  - mdtasks {} ({} file(s))",
+            command.trim(),
+            journal.lines().count()
+        );
     }
 
-    if !yes {
-        print!("❓ Are you sure you want to delete these task files? (y/N): ");
-        use std::io::{self, Write};
-        io::stdout().flush()?;
+    Ok(())
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+fn undo_last_change(config: &Config) -> Result<()> {
+    let _lock = lock_tasks(config)?;
 
-        if !input.trim().to_lowercase().starts_with('y') {
-            println!("❌ Cleanup cancelled");
+    let entries = undo_entries()?;
+    let entry = match entries.last() {
+        Some(entry) => entry,
+        None => {
+            println!("✅ Nothing to undo");
             return Ok(());
         }
+    };
+
+    let command = std::fs::read_to_string(entry.join("command")).unwrap_or_default();
+    let journal = std::fs::read_to_string(entry.join("journal")).unwrap_or_default();
+
+    // Restores get the same guard as writes, and every line is checked before
+    // any file is touched
+    let mut changes = Vec::new();
+    for line in journal.lines().rev() {
+        let (backup, path) = match line.split_once('\t') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if backup != "-" && Path::new(backup).file_name() != Some(std::ffi::OsStr::new(backup)) {
+            return Err(anyhow::anyhow!("Invalid undo journal line: {}", line));
+        }
+        ensure_inside_tasks_dir(path, config)
+            .context(format!("Cannot undo: mdtasks {}", command.trim()))?;
+        changes.push((backup, path));
     }
 
-    let mut deleted_count = 0;
-    for task_file in done_tasks {
-        if let Err(e) = std::fs::remove_file(&task_file.file_path) {
-            eprintln!("⚠️  Failed to delete {}: {}", task_file.file_path, e);
+    for (backup, path) in changes {
+        if dry_run() {
+            let verb = if backup == "-" { "remove" } else { "restore" };
+            println!("↩️  Would {} {}", verb, path);
+            continue;
+        }
+        if backup == "-" {
+            // The file was created by the undone command
+            if Path::new(path).exists() {
+                std::fs::remove_file(path).context(format!("Failed to remove file: {}", path))?;
+            }
+            println!("🗑️  Removed: {}", path);
         } else {
-            println!("🗑️  Deleted: {}", task_file.file_path);
-            deleted_count += 1;
+            std::fs::copy(entry.join(backup), path)
+                .context(format!("Failed to restore file: {}", path))?;
+            println!("♻️  Restored: {}", path);
         }
     }
 
-    println!("✅ Cleaned up {} done task(s)", deleted_count);
+    if dry_run() {
+        println!("↩️  Would undo: mdtasks {}", command.trim());
+        return Ok(());
+    }
+    std::fs::remove_dir_all(entry)?;
+
+    println!("↩️  Undid: mdtasks {}", command.trim());
     Ok(())
 }
+
 fn init_config_file(path: Option<String>) -> Result<()> {
     let config_path = path.unwrap_or_else(|| "./mdtasks.toml".to_string());
     let expanded_path = shellexpand::tilde(&config_path).to_string();
@@ -1967,3 +16623,172 @@ fn init_config_file(path: Option<String>) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory with a `tasks/` folder, state under `.mdtasks/`, and
+    /// an `age` stand-in on PATH that armors base64 instead of encrypting
+    fn scratch_config(name: &str) -> (PathBuf, Config) {
+        let dir = std::env::temp_dir().join(format!("mdtasks-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("tasks")).unwrap();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        let _ = CONTEXT_STATE_DIR.set(dir.join(".mdtasks"));
+
+        let age = dir.join("bin").join("age");
+        std::fs::write(
+            &age,
+            "#!/bin/sh\n\
+             case \"$1\" in\n\
+             --encrypt) echo '-----BEGIN AGE ENCRYPTED FILE-----'; base64; echo '-----END AGE ENCRYPTED FILE-----' ;;\n\
+             --decrypt) grep -v -- ----- | base64 -d ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&age, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{}", dir.join("bin").display(), path));
+
+        let mut config = Config::default();
+        config.tasks.dir = dir.join("tasks").display().to_string();
+        config.tasks.index = false;
+        config.encryption.recipients = vec!["age1test".to_string()];
+        config.encryption.identity = Some("key.txt".to_string());
+        (dir, config)
+    }
+
+    #[test]
+    fn start_keeps_encrypted_task_readable_with_history() {
+        let (dir, mut config) = scratch_config("encrypted-history");
+        config.tasks.history = true;
+
+        let path = dir.join("tasks").join("001-secret.md");
+        let plain = "---\nid: '001'\ntitle: Secret\nstatus: pending\nencrypted: true\n---\n\nSecret notes\n";
+        let stored = encrypt_task_content(plain, &config).unwrap().unwrap();
+        std::fs::write(&path, stored).unwrap();
+
+        mark_task_start("001".to_string(), &config).unwrap();
+
+        let path = path.display().to_string();
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(is_encrypted_body(split_front_matter(&raw).1));
+        assert!(!raw.contains("Secret notes"));
+        let content = read_task_file(&path, &config).unwrap();
+        assert!(content.contains("status: active"));
+        assert!(content.contains("Secret notes"));
+        assert!(content.contains("## History"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn resolve_keeps_fields_only_one_side_has_without_a_base() {
+        let content = "---
+id: 001
+title: \"A\"
+<<<<<<< HEAD
+status: active
+wait_until: 2026-10-20
+tags: [api]
+=======
+status: pending
+tags: [ui]
+follow_up: 2026-11-01
+>>>>>>> feature
+---
+
+## Subtasks
+
+<<<<<<< HEAD
+- [x] one
+- [ ] two
+=======
+- [ ] one
+- [x] two
+- [ ] three
+>>>>>>> feature
+";
+        let merged = resolve_conflicted_content("001.md", content, None).unwrap();
+        assert_eq!(
+            merged,
+            "---
+id: 001
+title: \"A\"
+status: active
+wait_until: 2026-10-20
+tags: [api, ui]
+follow_up: 2026-11-01
+---
+
+## Subtasks
+
+- [x] one
+- [x] two
+- [ ] three
+"
+        );
+    }
+
+    #[test]
+    fn resolve_uses_the_diff3_base_to_drop_removed_fields() {
+        // Theirs un-snoozed the task and ours dropped its tags and added a follow-up
+        let content = "---
+id: 001
+title: \"A\"
+<<<<<<< HEAD
+status: active
+wait_until: 2026-10-20
+follow_up: 2026-11-01
+||||||| merged common ancestors
+status: pending
+wait_until: 2026-10-20
+tags: [api]
+=======
+status: pending
+tags: [api]
+>>>>>>> feature
+---
+";
+        let merged = resolve_conflicted_content("001.md", content, None).unwrap();
+        assert_eq!(
+            merged,
+            "---
+id: 001
+title: \"A\"
+status: active
+follow_up: 2026-11-01
+---
+"
+        );
+    }
+
+    #[test]
+    fn resolve_takes_the_only_changed_side_from_the_base() {
+        let content = "---
+id: 001
+<<<<<<< HEAD
+title: \"A\"
+project: web
+||||||| base
+title: \"A\"
+project: api
+=======
+title: \"Renamed\"
+project: api
+>>>>>>> feature
+---
+";
+        // Without the base both fields would need an answer
+        let merged = resolve_conflicted_content("001.md", content, None).unwrap();
+        assert_eq!(
+            merged,
+            "---\nid: 001\ntitle: \"Renamed\"\nproject: web\n---\n"
+        );
+    }
+}
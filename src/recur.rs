@@ -0,0 +1,722 @@
+//! Recurrence rules for the `recur` front-matter field: parsing human phrases
+//! in several languages and finding the next occurrence of a rule
+
+use anyhow::{Context, Result};
+
+/// A parsed `recur` rule; intervals count in the rule's own unit
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Recurrence {
+    Days(u32),
+    /// An empty weekday list repeats on the starting weekday
+    Weeks {
+        interval: u32,
+        weekdays: Vec<chrono::Weekday>,
+    },
+    /// Day 0 repeats on the starting day of the month, -1 on the last day
+    MonthDay {
+        interval: u32,
+        day: i32,
+    },
+    /// `nth` -1 is the last such weekday of the month
+    NthWeekday {
+        interval: u32,
+        nth: i32,
+        weekday: chrono::Weekday,
+    },
+    Years(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecurUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecurToken {
+    Every,
+    Number(u32),
+    Ordinal(i32),
+    Unit(RecurUnit),
+    Weekday(chrono::Weekday),
+    Weekdays,
+    Weekend,
+    Filler,
+}
+
+/// Words understood in `recur` rules, in English, Dutch, German, French and Spanish
+fn recur_word(word: &str) -> Option<Vec<RecurToken>> {
+    use chrono::Weekday::*;
+    use RecurToken::*;
+    use RecurUnit::*;
+
+    let weekday = |w| Some(vec![Weekday(w)]);
+    let every = |unit| Some(vec![Every, Unit(unit)]);
+    match word {
+        "every" | "each" | "elke" | "elk" | "iedere" | "jeden" | "jede" | "jedes" | "alle"
+        | "tous" | "toutes" | "chaque" | "cada" | "todos" | "todas" => Some(vec![Every]),
+        "other" => Some(vec![Number(2)]),
+        "on" | "the" | "of" | "in" | "and" | "at" | "a" | "op" | "de" | "het" | "van" | "en"
+        | "om" | "am" | "im" | "des" | "der" | "den" | "und" | "le" | "la" | "les" | "du"
+        | "et" | "el" | "los" | "las" | "del" | "y" => Some(vec![Filler]),
+        "day" | "days" | "dag" | "dagen" | "tag" | "tage" | "jour" | "jours" | "día" | "dia"
+        | "días" | "dias" => Some(vec![Unit(Day)]),
+        "week" | "weeks" | "weken" | "woche" | "wochen" | "semaine" | "semaines" | "semana"
+        | "semanas" => Some(vec![Unit(Week)]),
+        "month" | "months" | "maand" | "maanden" | "monat" | "monate" | "monats" | "mois"
+        | "mes" | "meses" => Some(vec![Unit(Month)]),
+        "year" | "years" | "jaar" | "jaren" | "jahr" | "jahre" | "an" | "ans" | "année"
+        | "années" | "año" | "años" => Some(vec![Unit(Year)]),
+        "daily" | "dagelijks" | "täglich" | "taeglich" | "quotidien" | "quotidienne" | "diario"
+        | "diaria" => every(Day),
+        "weekly" | "wekelijks" | "wöchentlich" | "woechentlich" | "hebdomadaire" | "semanal" => {
+            every(Week)
+        }
+        "biweekly" | "fortnightly" | "tweewekelijks" | "zweiwöchentlich" => {
+            Some(vec![Every, Number(2), Unit(Week)])
+        }
+        "monthly" | "maandelijks" | "monatlich" | "mensuel" | "mensuelle" | "mensual" => {
+            every(Month)
+        }
+        "yearly" | "annually" | "jaarlijks" | "jährlich" | "jaehrlich" | "annuel" | "annuelle"
+        | "anual" => every(Year),
+        "weekday" | "weekdays" | "werkdag" | "werkdagen" | "werktag" | "werktage" | "werktags"
+        | "ouvrable" | "ouvrables" | "ouvré" | "ouvrés" | "laborable" | "laborables" => {
+            Some(vec![Weekdays])
+        }
+        "weekend" | "weekends" | "weekenddagen" | "wochenende" | "wochenenden" => {
+            Some(vec![Weekend])
+        }
+        "last" | "laatste" | "letzte" | "letzten" | "letzter" | "dernier" | "dernière"
+        | "último" | "última" | "ultimo" | "ultima" => Some(vec![Ordinal(-1)]),
+        "first" | "eerste" | "erste" | "ersten" | "premier" | "première" | "primer" | "primero"
+        | "primera" => Some(vec![Ordinal(1)]),
+        "second" | "tweede" | "zweite" | "zweiten" | "deuxième" | "segundo" | "segunda" => {
+            Some(vec![Ordinal(2)])
+        }
+        "third" | "derde" | "dritte" | "dritten" | "troisième" | "tercer" | "tercero"
+        | "tercera" => Some(vec![Ordinal(3)]),
+        "fourth" | "vierde" | "vierte" | "vierten" | "quatrième" | "cuarto" | "cuarta" => {
+            Some(vec![Ordinal(4)])
+        }
+        "fifth" | "vijfde" | "fünfte" | "fünften" | "cinquième" | "quinto" | "quinta" => {
+            Some(vec![Ordinal(5)])
+        }
+        "monday" | "mon" | "maandag" | "montag" | "lundi" | "lunes" => weekday(Mon),
+        "tuesday" | "tue" | "tues" | "dinsdag" | "dienstag" | "mardi" | "martes" => weekday(Tue),
+        "wednesday" | "wed" | "woensdag" | "mittwoch" | "mercredi" | "miércoles" | "miercoles" => {
+            weekday(Wed)
+        }
+        "thursday" | "thu" | "thur" | "thurs" | "donderdag" | "donnerstag" | "jeudi" | "jueves" => {
+            weekday(Thu)
+        }
+        "friday" | "fri" | "vrijdag" | "freitag" | "vendredi" | "viernes" => weekday(Fri),
+        "saturday" | "sat" | "zaterdag" | "samstag" | "sonnabend" | "samedi" | "sábado"
+        | "sabado" => weekday(Sat),
+        "sunday" | "sun" | "zondag" | "sonntag" | "dimanche" | "domingo" => weekday(Sun),
+        _ => None,
+    }
+}
+
+/// The weekday a word names in any supported language, e.g. "fri" or "vrijdag"
+pub(crate) fn weekday_from_word(word: &str) -> Option<chrono::Weekday> {
+    match recur_word(word)?.as_slice() {
+        [RecurToken::Weekday(weekday)] => Some(*weekday),
+        _ => None,
+    }
+}
+
+/// Turn one word of a rule into tokens, accepting plurals ("mondays",
+/// "maandagen", "lundis") and ordinals like "2nd", "2e", "2.", "1er" or "2º"
+fn recur_tokens(word: &str) -> Option<Vec<RecurToken>> {
+    if let Ok(n) = word.parse::<u32>() {
+        return Some(vec![RecurToken::Number(n)]);
+    }
+
+    let digits: String = word.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if !digits.is_empty() {
+        let suffix = &word[digits.len()..];
+        let ordinal_suffixes = [
+            "st", "nd", "rd", "th", "e", "ste", "de", ".", "er", "re", "ème", "eme", "º", "ª", "o",
+            "a",
+        ];
+        return match digits.parse::<i32>() {
+            Ok(n) if n > 0 && ordinal_suffixes.contains(&suffix) => {
+                Some(vec![RecurToken::Ordinal(n)])
+            }
+            _ => None,
+        };
+    }
+
+    recur_word(word)
+        .or_else(|| word.strip_suffix("en").and_then(recur_word))
+        .or_else(|| word.strip_suffix('s').and_then(recur_word))
+}
+
+/// Parse a human recurrence phrase such as "every 2nd Tuesday",
+/// "monthly on the 1st", "weekdays" or "elke maandag en donderdag"
+pub(crate) fn parse_recurrence(expr: &str) -> Result<Recurrence> {
+    let mut tokens = Vec::new();
+    for word in expr
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+        .filter(|w| !w.is_empty())
+    {
+        let word_tokens = recur_tokens(word).context(format!(
+            "Unrecognized word '{}' in recurrence '{}'",
+            word, expr
+        ))?;
+        tokens.extend(word_tokens);
+    }
+
+    let mut interval = None;
+    let mut ordinal = None;
+    let mut units = Vec::new();
+    let mut weekdays = Vec::new();
+    let mut weekday_set = None;
+    for token in tokens {
+        match token {
+            RecurToken::Every | RecurToken::Filler => {}
+            RecurToken::Number(n) => {
+                if interval.replace(n).is_some() {
+                    return Err(anyhow::anyhow!(
+                        "More than one interval in recurrence '{}'",
+                        expr
+                    ));
+                }
+            }
+            RecurToken::Ordinal(n) => {
+                if ordinal.replace(n).is_some() {
+                    return Err(anyhow::anyhow!(
+                        "More than one ordinal in recurrence '{}'",
+                        expr
+                    ));
+                }
+            }
+            RecurToken::Unit(unit) => units.push(unit),
+            RecurToken::Weekday(day) => {
+                if !weekdays.contains(&day) {
+                    weekdays.push(day);
+                }
+            }
+            RecurToken::Weekdays => weekday_set = Some(RecurToken::Weekdays),
+            RecurToken::Weekend => weekday_set = Some(RecurToken::Weekend),
+        }
+    }
+
+    let interval = interval.unwrap_or(1);
+    if interval == 0 {
+        return Err(anyhow::anyhow!(
+            "Recurrence interval must be at least 1: '{}'",
+            expr
+        ));
+    }
+    weekdays.sort_by_key(|day| day.num_days_from_monday());
+
+    // "weekdays" and "weekends" name a fixed set of days
+    if let Some(set) = weekday_set {
+        use chrono::Weekday::*;
+        let weekdays = if set == RecurToken::Weekdays {
+            vec![Mon, Tue, Wed, Thu, Fri]
+        } else {
+            vec![Sat, Sun]
+        };
+        return Ok(Recurrence::Weeks { interval, weekdays });
+    }
+
+    // The largest unit mentioned decides the rule: "1st day of the month" is monthly
+    let unit = [
+        RecurUnit::Year,
+        RecurUnit::Month,
+        RecurUnit::Week,
+        RecurUnit::Day,
+    ]
+    .into_iter()
+    .find(|unit| units.contains(unit));
+
+    let rule = match (unit, ordinal, weekdays.as_slice()) {
+        (None | Some(RecurUnit::Month), Some(nth), [weekday]) => Recurrence::NthWeekday {
+            interval,
+            nth,
+            weekday: *weekday,
+        },
+        (None | Some(RecurUnit::Month), Some(day), []) => {
+            if day > 31 {
+                return Err(anyhow::anyhow!("No month has a day {}: '{}'", day, expr));
+            }
+            Recurrence::MonthDay { interval, day }
+        }
+        (Some(RecurUnit::Month), None, []) => Recurrence::MonthDay { interval, day: 0 },
+        (None | Some(RecurUnit::Week), None, _) if unit.is_some() || !weekdays.is_empty() => {
+            Recurrence::Weeks {
+                interval,
+                weekdays,
+            }
+        }
+        (Some(RecurUnit::Day), None, []) => Recurrence::Days(interval),
+        (Some(RecurUnit::Year), None, []) => Recurrence::Years(interval),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Could not understand recurrence '{}' (try \"every 2 weeks\", \"weekdays\", \"monthly on the 1st\" or \"every 2nd tuesday\")",
+                expr
+            ))
+        }
+    };
+    if let Recurrence::NthWeekday { nth, .. } = rule {
+        if nth > 5 {
+            return Err(anyhow::anyhow!(
+                "No month has a {}th weekday: '{}'",
+                nth,
+                expr
+            ));
+        }
+    }
+    Ok(rule)
+}
+
+fn ordinal_suffix(n: i32) -> String {
+    match n {
+        -1 => "last".to_string(),
+        n if (11..=13).contains(&(n % 100)) => format!("{}th", n),
+        n if n % 10 == 1 => format!("{}st", n),
+        n if n % 10 == 2 => format!("{}nd", n),
+        n if n % 10 == 3 => format!("{}rd", n),
+        n => format!("{}th", n),
+    }
+}
+
+fn weekday_name(day: chrono::Weekday) -> &'static str {
+    use chrono::Weekday::*;
+    match day {
+        Mon => "Monday",
+        Tue => "Tuesday",
+        Wed => "Wednesday",
+        Thu => "Thursday",
+        Fri => "Friday",
+        Sat => "Saturday",
+        Sun => "Sunday",
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let every = |interval: u32, unit: &str| match interval {
+            1 => format!("every {}", unit),
+            n => format!("every {} {}s", n, unit),
+        };
+        match self {
+            Recurrence::Days(n) => write!(f, "{}", every(*n, "day")),
+            Recurrence::Weeks { interval, weekdays } if weekdays.is_empty() => {
+                write!(f, "{}", every(*interval, "week"))
+            }
+            Recurrence::Weeks { interval, weekdays } => {
+                let days: Vec<&str> = weekdays.iter().map(|d| weekday_name(*d)).collect();
+                write!(f, "{} on {}", every(*interval, "week"), days.join(", "))
+            }
+            Recurrence::MonthDay { interval, day: 0 } => write!(f, "{}", every(*interval, "month")),
+            Recurrence::MonthDay { interval, day } => write!(
+                f,
+                "{} on the {} day",
+                every(*interval, "month"),
+                ordinal_suffix(*day)
+            ),
+            Recurrence::NthWeekday {
+                interval,
+                nth,
+                weekday,
+            } => write!(
+                f,
+                "{} on the {} {}",
+                every(*interval, "month"),
+                ordinal_suffix(*nth),
+                weekday_name(*weekday)
+            ),
+            Recurrence::Years(n) => write!(f, "{}", every(*n, "year")),
+        }
+    }
+}
+
+/// Date `months` months after the first of `date`'s month
+fn add_months(date: chrono::NaiveDate, months: u32) -> chrono::NaiveDate {
+    use chrono::Datelike;
+
+    let first = date.with_day(1).unwrap_or(date);
+    first
+        .checked_add_months(chrono::Months::new(months))
+        .unwrap_or(first)
+}
+
+fn days_in_month(first: chrono::NaiveDate) -> u32 {
+    use chrono::Datelike;
+
+    add_months(first, 1)
+        .pred_opt()
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+/// The occurrence of `rule` within the month starting at `first`, if it exists;
+/// days past the end of a short month fall on its last day
+fn occurrence_in_month(
+    rule: &Recurrence,
+    first: chrono::NaiveDate,
+    anchor: chrono::NaiveDate,
+) -> Option<chrono::NaiveDate> {
+    use chrono::Datelike;
+
+    let last_day = days_in_month(first);
+    match *rule {
+        Recurrence::MonthDay { day, .. } => {
+            let day = match day {
+                0 => anchor.day(),
+                -1 => last_day,
+                day => day as u32,
+            };
+            first.with_day(day.min(last_day))
+        }
+        Recurrence::NthWeekday {
+            nth: -1, weekday, ..
+        } => (1..=last_day)
+            .rev()
+            .filter_map(|day| first.with_day(day))
+            .find(|date| date.weekday() == weekday),
+        Recurrence::NthWeekday { nth, weekday, .. } => {
+            chrono::NaiveDate::from_weekday_of_month_opt(
+                first.year(),
+                first.month(),
+                weekday,
+                nth as u8,
+            )
+        }
+        _ => None,
+    }
+}
+
+impl Recurrence {
+    /// First occurrence strictly after `after`, counting intervals from `anchor`;
+    /// nothing before `anchor` counts as an occurrence
+    pub(crate) fn next_after(
+        &self,
+        anchor: chrono::NaiveDate,
+        after: chrono::NaiveDate,
+    ) -> chrono::NaiveDate {
+        use chrono::Datelike;
+
+        match self {
+            Recurrence::Days(n) => {
+                let elapsed = (after - anchor).num_days().max(-1);
+                let steps = elapsed.div_euclid(*n as i64) + 1;
+                anchor + chrono::Duration::days(steps * *n as i64)
+            }
+            Recurrence::Weeks { interval, weekdays } => {
+                let weekdays = if weekdays.is_empty() {
+                    vec![anchor.weekday()]
+                } else {
+                    weekdays.clone()
+                };
+                let week_start = |date: chrono::NaiveDate| {
+                    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+                };
+                let anchor_week = week_start(anchor);
+                let mut date = after.max(anchor - chrono::Duration::days(1));
+                loop {
+                    date = date.succ_opt().unwrap_or(date);
+                    let weeks = (week_start(date) - anchor_week).num_days() / 7;
+                    if weeks % *interval as i64 == 0 && weekdays.contains(&date.weekday()) {
+                        return date;
+                    }
+                }
+            }
+            Recurrence::MonthDay { interval, .. } | Recurrence::NthWeekday { interval, .. } => {
+                let mut months = 0;
+                loop {
+                    let first = add_months(anchor, months);
+                    if let Some(date) = occurrence_in_month(self, first, anchor) {
+                        if date > after && date >= anchor {
+                            return date;
+                        }
+                    }
+                    months += interval;
+                }
+            }
+            Recurrence::Years(n) => {
+                let mut years = 0;
+                loop {
+                    let date = anchor
+                        .checked_add_months(chrono::Months::new(12 * years))
+                        .unwrap_or(anchor);
+                    if date > after {
+                        return date;
+                    }
+                    years += n;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday::*;
+
+    fn date(value: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn rule(expr: &str) -> Recurrence {
+        parse_recurrence(expr).unwrap_or_else(|e| panic!("{}: {:#}", expr, e))
+    }
+
+    /// The next `count` occurrences strictly after `after`
+    fn occurrences(expr: &str, anchor: &str, after: &str, count: usize) -> Vec<String> {
+        let rule = rule(expr);
+        let anchor = date(anchor);
+        let mut date = date(after);
+        (0..count)
+            .map(|_| {
+                date = rule.next_after(anchor, date);
+                date.format("%Y-%m-%d").to_string()
+            })
+            .collect()
+    }
+
+    fn weeks(interval: u32, weekdays: &[chrono::Weekday]) -> Recurrence {
+        Recurrence::Weeks {
+            interval,
+            weekdays: weekdays.to_vec(),
+        }
+    }
+
+    fn nth_weekday(nth: i32, weekday: chrono::Weekday) -> Recurrence {
+        Recurrence::NthWeekday {
+            interval: 1,
+            nth,
+            weekday,
+        }
+    }
+
+    fn month_day(interval: u32, day: i32) -> Recurrence {
+        Recurrence::MonthDay { interval, day }
+    }
+
+    #[test]
+    fn parses_day_rules() {
+        assert_eq!(rule("daily"), Recurrence::Days(1));
+        assert_eq!(rule("every day"), Recurrence::Days(1));
+        assert_eq!(rule("every 3 days"), Recurrence::Days(3));
+    }
+
+    #[test]
+    fn parses_week_rules() {
+        assert_eq!(rule("weekly"), weeks(1, &[]));
+        assert_eq!(rule("every 2 weeks"), weeks(2, &[]));
+        assert_eq!(rule("biweekly"), weeks(2, &[]));
+        assert_eq!(rule("every monday and thursday"), weeks(1, &[Mon, Thu]));
+        assert_eq!(rule("Thursdays, Mondays"), weeks(1, &[Mon, Thu]));
+        assert_eq!(rule("every other tuesday"), weeks(2, &[Tue]));
+        assert_eq!(rule("weekdays"), weeks(1, &[Mon, Tue, Wed, Thu, Fri]));
+        assert_eq!(rule("weekends"), weeks(1, &[Sat, Sun]));
+    }
+
+    #[test]
+    fn parses_month_rules() {
+        assert_eq!(rule("monthly"), month_day(1, 0));
+        assert_eq!(rule("monthly on the 15th"), month_day(1, 15));
+        assert_eq!(rule("every 3 months on the 1st"), month_day(3, 1));
+        assert_eq!(rule("1st day of the month"), month_day(1, 1));
+        assert_eq!(rule("last day of the month"), month_day(1, -1));
+        assert_eq!(rule("every 2nd tuesday"), nth_weekday(2, Tue));
+        assert_eq!(rule("last friday of the month"), nth_weekday(-1, Fri));
+        assert_eq!(rule("every 5th friday"), nth_weekday(5, Fri));
+    }
+
+    #[test]
+    fn parses_year_rules() {
+        assert_eq!(rule("yearly"), Recurrence::Years(1));
+        assert_eq!(rule("annually"), Recurrence::Years(1));
+        assert_eq!(rule("every 2 years"), Recurrence::Years(2));
+    }
+
+    #[test]
+    fn parses_dutch() {
+        assert_eq!(rule("dagelijks"), Recurrence::Days(1));
+        assert_eq!(rule("elke maandag en donderdag"), weeks(1, &[Mon, Thu]));
+        assert_eq!(rule("elke 2 weken"), weeks(2, &[]));
+        assert_eq!(rule("werkdagen"), weeks(1, &[Mon, Tue, Wed, Thu, Fri]));
+        assert_eq!(rule("maandelijks op de 1e"), month_day(1, 1));
+        assert_eq!(rule("laatste vrijdag van de maand"), nth_weekday(-1, Fri));
+        assert_eq!(rule("jaarlijks"), Recurrence::Years(1));
+    }
+
+    #[test]
+    fn parses_german() {
+        assert_eq!(rule("täglich"), Recurrence::Days(1));
+        assert_eq!(rule("alle 2 Wochen am Montag"), weeks(2, &[Mon]));
+        assert_eq!(rule("monatlich am 15."), month_day(1, 15));
+        assert_eq!(rule("jeden 2. Dienstag"), nth_weekday(2, Tue));
+        assert_eq!(rule("letzter Freitag im Monat"), nth_weekday(-1, Fri));
+        assert_eq!(rule("jährlich"), Recurrence::Years(1));
+    }
+
+    #[test]
+    fn parses_french() {
+        assert_eq!(rule("quotidien"), Recurrence::Days(1));
+        assert_eq!(rule("tous les lundis"), weeks(1, &[Mon]));
+        assert_eq!(rule("toutes les 2 semaines"), weeks(2, &[]));
+        assert_eq!(rule("le 1er de chaque mois"), month_day(1, 1));
+        assert_eq!(rule("le dernier vendredi du mois"), nth_weekday(-1, Fri));
+        assert_eq!(rule("annuel"), Recurrence::Years(1));
+    }
+
+    #[test]
+    fn parses_spanish() {
+        assert_eq!(rule("cada 3 días"), Recurrence::Days(3));
+        assert_eq!(rule("cada martes"), weeks(1, &[Tue]));
+        assert_eq!(rule("el 2º martes de cada mes"), nth_weekday(2, Tue));
+        assert_eq!(rule("el último viernes del mes"), nth_weekday(-1, Fri));
+        assert_eq!(rule("anual"), Recurrence::Years(1));
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        for expr in [
+            "every 0 days",
+            "every 32nd",
+            "every 6th monday",
+            "every blue moon",
+            "every 2 3 days",
+            "every 1st 2nd tuesday",
+            "",
+        ] {
+            assert!(parse_recurrence(expr).is_err(), "{} should not parse", expr);
+        }
+    }
+
+    #[test]
+    fn display_parses_back_to_the_same_rule() {
+        for expr in [
+            "every 3 days",
+            "every 2 weeks",
+            "every monday and thursday",
+            "monthly",
+            "every 3 months on the 1st",
+            "last day of the month",
+            "every 2nd tuesday",
+            "last friday of the month",
+            "every 2 years",
+        ] {
+            let parsed = rule(expr);
+            assert_eq!(rule(&parsed.to_string()), parsed, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn ordinal_weekday_is_monthly_but_interval_weekday_is_weekly() {
+        // "2nd Tuesday" is the second Tuesday of each month, while
+        // "every 2 weeks on Tuesday" is every other Tuesday
+        assert_eq!(
+            occurrences("every 2nd tuesday", "2026-10-01", "2026-10-01", 3),
+            ["2026-10-13", "2026-11-10", "2026-12-08"]
+        );
+        assert_eq!(
+            occurrences("every 2 weeks on tuesday", "2026-10-01", "2026-10-01", 3),
+            ["2026-10-13", "2026-10-27", "2026-11-10"]
+        );
+    }
+
+    #[test]
+    fn next_days_and_weeks() {
+        assert_eq!(
+            occurrences("every 3 days", "2026-10-01", "2026-10-05", 2),
+            ["2026-10-07", "2026-10-10"]
+        );
+        assert_eq!(
+            occurrences("every other tuesday", "2026-10-06", "2026-10-06", 2),
+            ["2026-10-20", "2026-11-03"]
+        );
+        assert_eq!(
+            occurrences("weekdays", "2026-10-16", "2026-10-16", 2),
+            ["2026-10-19", "2026-10-20"]
+        );
+    }
+
+    #[test]
+    fn month_days_clamp_to_short_months() {
+        assert_eq!(
+            occurrences("monthly on the 31st", "2027-01-31", "2027-01-31", 3),
+            ["2027-02-28", "2027-03-31", "2027-04-30"]
+        );
+        assert_eq!(
+            occurrences("monthly on the 31st", "2028-01-31", "2028-01-31", 1),
+            ["2028-02-29"]
+        );
+        // A plain "monthly" keeps the anchor's day after a short month
+        assert_eq!(
+            occurrences("monthly", "2026-01-31", "2026-01-31", 2),
+            ["2026-02-28", "2026-03-31"]
+        );
+        assert_eq!(
+            occurrences("last day of the month", "2026-02-10", "2026-02-10", 3),
+            ["2026-02-28", "2026-03-31", "2026-04-30"]
+        );
+    }
+
+    #[test]
+    fn last_weekday_of_the_month() {
+        assert_eq!(
+            occurrences("last friday of the month", "2026-10-01", "2026-10-01", 2),
+            ["2026-10-30", "2026-11-27"]
+        );
+    }
+
+    #[test]
+    fn fifth_weekday_skips_months_without_one() {
+        assert_eq!(
+            occurrences("every 5th friday", "2026-10-01", "2026-10-01", 2),
+            ["2026-10-30", "2027-01-29"]
+        );
+    }
+
+    #[test]
+    fn years_clamp_leap_days() {
+        assert_eq!(
+            occurrences("yearly", "2024-02-29", "2024-02-29", 1),
+            ["2025-02-28"]
+        );
+    }
+
+    #[test]
+    fn next_after_a_date_before_the_anchor_starts_at_the_anchor() {
+        assert_eq!(
+            occurrences("every 7 days", "2026-10-16", "2026-09-01", 1),
+            ["2026-10-16"]
+        );
+        assert_eq!(
+            occurrences("every monday", "2026-10-16", "2026-01-01", 1),
+            ["2026-10-19"]
+        );
+        assert_eq!(
+            occurrences("monthly on the 1st", "2026-10-16", "2026-09-15", 1),
+            ["2026-11-01"]
+        );
+        assert_eq!(
+            occurrences("last friday of the month", "2026-10-31", "2026-10-01", 1),
+            ["2026-11-27"]
+        );
+        assert_eq!(
+            occurrences("yearly", "2026-10-16", "2020-01-01", 1),
+            ["2026-10-16"]
+        );
+    }
+
+    #[test]
+    fn weekday_words_in_every_language() {
+        for word in ["fri", "friday", "vrijdag", "freitag", "vendredi", "viernes"] {
+            assert_eq!(weekday_from_word(word), Some(Fri), "{}", word);
+        }
+        assert_eq!(weekday_from_word("weekly"), None);
+    }
+}